@@ -24,6 +24,12 @@ use rome_cli::run_cli;
 /// ```bash
 /// cargo run --example run_cli format examples/input.js
 /// ```
+///
+/// Directories and globs are formatted too:
+///
+/// ```bash
+/// cargo run --example run_cli format examples/ "examples/**/*.json"
+/// ```
 fn main() {
 	run_cli();
 }
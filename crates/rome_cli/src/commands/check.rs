@@ -0,0 +1,359 @@
+use crate::{commands::daemon::Daemon, traversal::Traversal};
+use rome_core::{configuration::ProjectSettings, fs::FileSystem, App};
+use rome_formatter::{format_parsed, parse, FormatError};
+use rome_path::RomePath;
+use rslint_errors::{Diagnostic, Formatter, LongFormatter, ShortFormatter};
+use std::{
+	path::{Path, PathBuf},
+	time::Instant,
+};
+
+use super::{
+	emit_diagnostics_with,
+	format::{catch_panic, panic_message, report_error, FileError},
+	process_files, EXIT_ERROR, EXIT_SUCCESS, EXIT_UNFORMATTED,
+};
+
+/// The settings of a run of `rome check`
+pub(crate) struct CheckCommand {
+	/// The settings of the project the files belong to
+	pub(crate) settings: ProjectSettings,
+	/// The file system the files are read from
+	pub(crate) fs: Box<dyn FileSystem>,
+	/// The daemon the files are forwarded to, when it's running
+	pub(crate) daemon: Option<Daemon>,
+	/// The handlers of the files, which also cache the types of their packages
+	pub(crate) app: App,
+	/// The directory the relative paths are resolved from, to find the `package.json` files
+	/// above it
+	pub(crate) working_directory: PathBuf,
+	/// The number of diagnostics printed at most, the others are only counted
+	pub(crate) max_diagnostics: usize,
+	/// Print each diagnostic on a single line
+	pub(crate) short: bool,
+}
+
+/// What checking a file found
+#[derive(Debug)]
+pub(crate) enum CheckOutcome {
+	/// The file was parsed
+	Checked(FileReport),
+	/// Rome can't parse the file
+	Skipped,
+	/// The file couldn't be read or formatted
+	Errored(Box<FileError>),
+}
+
+/// The problems of a file that Rome parsed
+#[derive(Debug)]
+pub(crate) struct FileReport {
+	/// The content of the file, which the diagnostics point into
+	source: String,
+	/// The syntax errors of the file
+	syntax_errors: Vec<Diagnostic>,
+	/// Where the formatted code starts to differ from the file, when it isn't formatted. The
+	/// files with syntax errors aren't formatted, so they don't have one
+	unformatted: Option<Diagnostic>,
+}
+
+impl FileReport {
+	fn diagnostics(&self) -> Vec<Diagnostic> {
+		self.syntax_errors
+			.iter()
+			.chain(&self.unformatted)
+			.cloned()
+			.collect()
+	}
+}
+
+/// The number of files of each outcome
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct CheckSummary {
+	pub(crate) checked: usize,
+	/// The files with syntax errors
+	pub(crate) invalid: usize,
+	pub(crate) unformatted: usize,
+	pub(crate) skipped: usize,
+	pub(crate) errored: usize,
+}
+
+impl CheckSummary {
+	fn add(&mut self, outcome: &CheckOutcome) {
+		match outcome {
+			CheckOutcome::Checked(report) => {
+				self.checked += 1;
+				if !report.syntax_errors.is_empty() {
+					self.invalid += 1;
+				}
+				if report.unformatted.is_some() {
+					self.unformatted += 1;
+				}
+			}
+			CheckOutcome::Skipped => self.skipped += 1,
+			CheckOutcome::Errored(_) => self.errored += 1,
+		}
+	}
+}
+
+/// Checks the files of `traversal` in parallel, prints their diagnostics up to the limit of
+/// `command` and a summary.
+///
+/// Returns the exit code of the command: [EXIT_ERROR] if any of the files has syntax errors or
+/// couldn't be checked, [EXIT_UNFORMATTED] if some of them aren't formatted.
+pub(crate) fn run(traversal: Traversal, command: &CheckCommand) -> i32 {
+	let start = Instant::now();
+
+	for error in &traversal.errors {
+		eprintln!("warning: {}", error);
+	}
+	for input in &traversal.unmatched {
+		eprintln!("warning: \"{}\" didn't match any file", input);
+	}
+
+	let mut results = process_files(traversal.files, |path| check_path(path, command));
+	results.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+	let mut formatter: Box<dyn Formatter> = if command.short {
+		Box::new(ShortFormatter)
+	} else {
+		Box::new(LongFormatter)
+	};
+
+	let mut summary = CheckSummary::default();
+	let mut total = 0;
+	let mut printed = 0;
+	for (path, outcome) in &results {
+		summary.add(outcome);
+		match outcome {
+			CheckOutcome::Checked(report) => {
+				let diagnostics = report.diagnostics();
+				let shown = diagnostics.len().min(command.max_diagnostics - printed);
+				if shown > 0 {
+					emit_diagnostics_with(
+						formatter.as_mut(),
+						&path.to_string_lossy(),
+						&report.source,
+						&diagnostics[..shown],
+					);
+				}
+				total += diagnostics.len();
+				printed += shown;
+			}
+			CheckOutcome::Errored(error) => {
+				if printed < command.max_diagnostics {
+					report_error(path, error);
+					printed += 1;
+				}
+				total += 1;
+			}
+			CheckOutcome::Skipped => {}
+		}
+	}
+
+	if total > printed {
+		eprintln!(
+			"{} more diagnostics weren't printed, raise --max-diagnostics to see them",
+			total - printed
+		);
+	}
+
+	eprintln!(
+		"Checked {} files in {:.2}s: {} with syntax errors, {} not formatted, {} skipped, {} errored",
+		summary.checked,
+		start.elapsed().as_secs_f32(),
+		summary.invalid,
+		summary.unformatted,
+		summary.skipped,
+		summary.errored
+	);
+
+	if summary.invalid > 0 || summary.errored > 0 {
+		EXIT_ERROR
+	} else if summary.unformatted > 0 {
+		EXIT_UNFORMATTED
+	} else {
+		EXIT_SUCCESS
+	}
+}
+
+/// Parses the file at `path`, and formats it when it doesn't have syntax errors and its
+/// formatting is enabled
+pub(crate) fn check_path(path: &Path, command: &CheckCommand) -> CheckOutcome {
+	let source = match command.fs.read(path) {
+		Ok(source) => source,
+		Err(error) => return CheckOutcome::Errored(Box::new(FileError::Io(error))),
+	};
+
+	let app = &command.app;
+	let absolute_path = command.working_directory.join(path);
+	let rome_path = RomePath::new(&absolute_path.to_string_lossy())
+		.deduce_handler(app)
+		.sniff_handler(app, &source)
+		.resolve_syntax(app, command.fs.as_ref());
+
+	let configuration = rome_path.format_configuration(&command.settings);
+
+	// The files the daemon can't check, or that have syntax errors, are parsed here, which
+	// reports their errors
+	if let Some(daemon) = &command.daemon {
+		if let Some(formatted) = daemon.check(&rome_path.to_string_lossy(), &source, &configuration)
+		{
+			let unformatted = formatted.and_then(|code| unformatted_diagnostic(&source, &code));
+			return CheckOutcome::Checked(FileReport {
+				source,
+				syntax_errors: Vec::new(),
+				unformatted,
+			});
+		}
+	}
+
+	let parsed = match parse(&rome_path, &source) {
+		Some(parsed) => parsed,
+		None => return CheckOutcome::Skipped,
+	};
+
+	let syntax_errors = parsed.diagnostics().to_vec();
+	if !syntax_errors.is_empty() || !configuration.enabled {
+		return CheckOutcome::Checked(FileReport {
+			source,
+			syntax_errors,
+			unformatted: None,
+		});
+	}
+
+	let result = catch_panic(|| format_parsed(&rome_path, &parsed, &configuration));
+	let unformatted = match result {
+		Ok(Ok(formatted)) => unformatted_diagnostic(&source, formatted.code()),
+		Ok(Err(FormatError::UnsupportedLanguage | FormatError::CapabilityDisabled)) => None,
+		Ok(Err(error)) => {
+			return CheckOutcome::Errored(Box::new(FileError::Format { source, error }));
+		}
+		Err(panic) => {
+			return CheckOutcome::Errored(Box::new(FileError::Panic(panic_message(panic))));
+		}
+	};
+
+	CheckOutcome::Checked(FileReport {
+		source,
+		syntax_errors,
+		unformatted,
+	})
+}
+
+/// Returns a diagnostic pointing at the line of `source` where `formatted` starts to differ, or
+/// [None] if `source` is formatted
+fn unformatted_diagnostic(source: &str, formatted: &str) -> Option<Diagnostic> {
+	if source == formatted {
+		return None;
+	}
+
+	let mut offset = source
+		.bytes()
+		.zip(formatted.bytes())
+		.take_while(|(left, right)| left == right)
+		.count();
+	while !source.is_char_boundary(offset) {
+		offset -= 1;
+	}
+
+	let line_start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+	let line_end = source[offset..]
+		.find('\n')
+		.map_or(source.len(), |index| offset + index);
+
+	Some(
+		Diagnostic::error(0, "Format", "The file isn't formatted")
+			.primary(
+				line_start..line_end,
+				"the formatted code differs from this line",
+			)
+			.footer_help("run `rome format` to format the file"),
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{check_path, CheckCommand, CheckOutcome};
+	use rome_core::{
+		configuration::{parse_configuration, ProjectConfiguration, ProjectSettings},
+		fs::MemoryFileSystem,
+	};
+	use rome_formatter::create_app;
+	use std::path::{Path, PathBuf};
+
+	fn command() -> CheckCommand {
+		CheckCommand {
+			settings: ProjectSettings::default(),
+			fs: Box::new(MemoryFileSystem::default()),
+			daemon: None,
+			app: create_app(),
+			working_directory: PathBuf::new(),
+			max_diagnostics: 20,
+			short: false,
+		}
+	}
+
+	fn check(command: &CheckCommand, path: &str, source: &str) -> CheckOutcome {
+		command.fs.write(Path::new(path), source).unwrap();
+		check_path(Path::new(path), command)
+	}
+
+	#[test]
+	fn reports_the_syntax_errors_and_the_unformatted_files() {
+		let command = command();
+
+		match check(&command, "formatted.js", "let a = 1;\n") {
+			CheckOutcome::Checked(report) => assert!(report.diagnostics().is_empty()),
+			outcome => panic!("unexpected outcome {:?}", outcome),
+		}
+
+		match check(&command, "invalid.js", "let a = ;\n") {
+			CheckOutcome::Checked(report) => {
+				assert_eq!(report.syntax_errors.len(), 1);
+				assert!(report.unformatted.is_none());
+			}
+			outcome => panic!("unexpected outcome {:?}", outcome),
+		}
+
+		match check(&command, "unformatted.js", "let a = 1;\nlet   b=2;\n") {
+			CheckOutcome::Checked(report) => {
+				assert!(report.syntax_errors.is_empty());
+				let diagnostic = report.unformatted.unwrap();
+				assert_eq!(diagnostic.primary.unwrap().span.range, 11..21);
+			}
+			outcome => panic!("unexpected outcome {:?}", outcome),
+		}
+
+		assert!(matches!(
+			check(&command, "notes.txt", "some notes"),
+			CheckOutcome::Skipped
+		));
+		assert!(matches!(
+			check_path(Path::new("missing.js"), &command),
+			CheckOutcome::Errored(_)
+		));
+	}
+
+	#[test]
+	fn doesnt_report_the_files_whose_formatting_is_disabled() {
+		let configuration = parse_configuration(
+			r#"{ "overrides": [{ "include": ["legacy"], "format": { "enabled": false } }] }"#,
+		)
+		.unwrap();
+		let project = ProjectConfiguration {
+			path: PathBuf::from("/project/rome.json"),
+			configuration,
+		};
+		let mut command = command();
+		command.settings = ProjectSettings::new(Some(&project), Path::new("/project"));
+
+		match check(&command, "legacy/index.js", "let   a=1") {
+			CheckOutcome::Checked(report) => assert!(report.diagnostics().is_empty()),
+			outcome => panic!("unexpected outcome {:?}", outcome),
+		}
+		match check(&command, "legacy/invalid.js", "let a = ;") {
+			CheckOutcome::Checked(report) => assert_eq!(report.syntax_errors.len(), 1),
+			outcome => panic!("unexpected outcome {:?}", outcome),
+		}
+	}
+}
@@ -0,0 +1,251 @@
+//! `rome start` and `rome stop`, which run the daemon of a project in the background. While it's
+//! running, `rome format` and `rome check` forward the files to it instead of parsing them in a
+//! new process.
+//!
+//! The daemon listens on `.rome/daemon.sock` at the root of the project, next to the cache.
+use super::{EXIT_ERROR, EXIT_SUCCESS};
+use rome_core::{cache::CACHE_DIRECTORY, configuration::FormatConfiguration};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use {
+	rome_lsp::daemon::{CheckReply, Client, FormatReply},
+	std::{
+		env, fs,
+		os::unix::net::UnixListener,
+		process::{Command, Stdio},
+		sync::Mutex,
+		thread,
+		time::Duration,
+	},
+};
+
+/// The hidden subcommand that runs the daemon in the foreground, `rome start` spawns it
+pub(crate) const RUN_DAEMON: &str = "__run_daemon";
+
+/// How long `rome start` and `rome stop` wait for the daemon, in steps of 50ms
+#[cfg(unix)]
+const WAIT_STEPS: u32 = 100;
+
+/// The socket of the daemon of the project at `root`
+pub(crate) fn socket_path(root: &Path) -> PathBuf {
+	root.join(CACHE_DIRECTORY).join("daemon.sock")
+}
+
+/// A connection to a running daemon, shared by the threads that format the files
+pub(crate) struct Daemon {
+	/// `None` once the connection failed, the files are then formatted by the command itself
+	#[cfg(unix)]
+	client: Mutex<Option<Client>>,
+}
+
+impl Daemon {
+	/// Connects to the daemon of the project at `root`, if it's running
+	#[cfg(unix)]
+	pub(crate) fn connect(root: &Path) -> Option<Self> {
+		let client = Client::connect(&socket_path(root)).ok()?;
+		Some(Self {
+			client: Mutex::new(Some(client)),
+		})
+	}
+
+	#[cfg(not(unix))]
+	pub(crate) fn connect(_root: &Path) -> Option<Self> {
+		None
+	}
+
+	/// Formats `source`, the content of the file at `path`, in the daemon. Returns `None` if the
+	/// daemon couldn't format it, `Some(None)` if Rome doesn't format this kind of file
+	#[cfg(unix)]
+	pub(crate) fn format(
+		&self,
+		path: &str,
+		source: &str,
+		configuration: &FormatConfiguration,
+	) -> Option<Option<String>> {
+		let mut client = self.client.lock().ok()?;
+		match client
+			.as_mut()?
+			.format(Path::new(path), source, configuration)
+		{
+			Ok(FormatReply::Formatted(code)) => Some(Some(code)),
+			Ok(FormatReply::Skipped) => Some(None),
+			Ok(FormatReply::Failed(_)) => None,
+			Err(_) => {
+				*client = None;
+				None
+			}
+		}
+	}
+
+	#[cfg(not(unix))]
+	pub(crate) fn format(
+		&self,
+		_path: &str,
+		_source: &str,
+		_configuration: &FormatConfiguration,
+	) -> Option<Option<String>> {
+		None
+	}
+
+	/// Checks `source`, the content of the file at `path`, in the daemon. Returns the formatted
+	/// code of a file without syntax errors, or `Some(None)` if its formatting is disabled.
+	///
+	/// Returns `None` if the daemon couldn't check the file, or if the file has syntax errors:
+	/// the command parses it again to report them.
+	#[cfg(unix)]
+	pub(crate) fn check(
+		&self,
+		path: &str,
+		source: &str,
+		configuration: &FormatConfiguration,
+	) -> Option<Option<String>> {
+		let mut client = self.client.lock().ok()?;
+		match client
+			.as_mut()?
+			.check(Path::new(path), source, configuration)
+		{
+			Ok(CheckReply::Checked {
+				syntax_errors: false,
+				formatted,
+			}) => Some(formatted),
+			Ok(_) => None,
+			Err(_) => {
+				*client = None;
+				None
+			}
+		}
+	}
+
+	#[cfg(not(unix))]
+	pub(crate) fn check(
+		&self,
+		_path: &str,
+		_source: &str,
+		_configuration: &FormatConfiguration,
+	) -> Option<Option<String>> {
+		None
+	}
+}
+
+/// Starts the daemon of the project at `root` in the background, unless it's already running
+#[cfg(unix)]
+pub(crate) fn start(root: &Path) -> i32 {
+	let socket = socket_path(root);
+	if Client::connect(&socket).is_ok() {
+		println!("The daemon is already running on {}", socket.display());
+		return EXIT_SUCCESS;
+	}
+
+	// Left by a daemon that didn't stop cleanly, nothing listens on it
+	let _ = fs::remove_file(&socket);
+	if let Err(error) = fs::create_dir_all(root.join(CACHE_DIRECTORY)) {
+		eprintln!(
+			"error: cannot create the directory of the daemon socket: {}",
+			error
+		);
+		return EXIT_ERROR;
+	}
+
+	let spawned = env::current_exe().and_then(|exe| {
+		Command::new(exe)
+			.arg(RUN_DAEMON)
+			.arg(&socket)
+			.stdin(Stdio::null())
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+	});
+	if let Err(error) = spawned {
+		eprintln!("error: cannot start the daemon: {}", error);
+		return EXIT_ERROR;
+	}
+
+	for _ in 0..WAIT_STEPS {
+		if Client::connect(&socket).is_ok() {
+			println!("Started the daemon on {}", socket.display());
+			return EXIT_SUCCESS;
+		}
+		thread::sleep(Duration::from_millis(50));
+	}
+
+	eprintln!("error: the daemon didn't listen on {}", socket.display());
+	EXIT_ERROR
+}
+
+/// Stops the daemon of the project at `root`, if it's running
+#[cfg(unix)]
+pub(crate) fn stop(root: &Path) -> i32 {
+	let socket = socket_path(root);
+	let client = match Client::connect(&socket) {
+		Ok(client) => client,
+		Err(_) => {
+			let _ = fs::remove_file(&socket);
+			println!("The daemon isn't running");
+			return EXIT_SUCCESS;
+		}
+	};
+
+	if let Err(error) = client.shutdown() {
+		eprintln!("error: cannot stop the daemon: {}", error);
+		return EXIT_ERROR;
+	}
+
+	// The daemon removes its socket once it stopped listening
+	for _ in 0..WAIT_STEPS {
+		if !socket.exists() {
+			println!("Stopped the daemon");
+			return EXIT_SUCCESS;
+		}
+		thread::sleep(Duration::from_millis(50));
+	}
+
+	eprintln!(
+		"error: the daemon didn't stop listening on {}",
+		socket.display()
+	);
+	EXIT_ERROR
+}
+
+/// Runs the daemon in the foreground, listening on `socket`, until it's asked to stop
+#[cfg(unix)]
+pub(crate) fn run(socket: &Path) -> i32 {
+	let listener = match UnixListener::bind(socket) {
+		Ok(listener) => listener,
+		Err(error) => {
+			eprintln!("error: cannot listen on {}: {}", socket.display(), error);
+			return EXIT_ERROR;
+		}
+	};
+
+	let result = rome_lsp::daemon::serve(listener);
+	let _ = fs::remove_file(socket);
+
+	match result {
+		Ok(()) => EXIT_SUCCESS,
+		Err(error) => {
+			eprintln!("error: the daemon stopped: {}", error);
+			EXIT_ERROR
+		}
+	}
+}
+
+#[cfg(not(unix))]
+pub(crate) fn start(_root: &Path) -> i32 {
+	unsupported()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn stop(_root: &Path) -> i32 {
+	unsupported()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn run(_socket: &Path) -> i32 {
+	unsupported()
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> i32 {
+	eprintln!("error: the daemon is only supported on Unix");
+	EXIT_ERROR
+}
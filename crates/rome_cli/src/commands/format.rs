@@ -0,0 +1,607 @@
+use crate::{commands::daemon::Daemon, traversal::Traversal};
+use rome_core::{
+	cache::{Cache, CacheKey, CacheKind},
+	configuration::{FormatConfiguration, FormatOverride, ProjectSettings},
+	fs::{FileSystem, FileSystemError},
+	App,
+};
+use rome_formatter::{format_source, FormatError};
+use rome_path::RomePath;
+use similar::TextDiff;
+use std::{
+	any::Any,
+	cell::Cell,
+	io::Read,
+	panic::{self, AssertUnwindSafe},
+	path::{Path, PathBuf},
+	sync::Once,
+	time::Instant,
+};
+
+use super::{emit_diagnostics, process_files, EXIT_ERROR, EXIT_SUCCESS, EXIT_UNFORMATTED};
+
+/// What to do with the files that aren't formatted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FormatMode {
+	/// Write the formatted code back to the files
+	Write,
+	/// List the files that aren't formatted
+	Check,
+	/// Print a unified diff of the changes, without writing them
+	Diff,
+}
+
+/// The settings of a run of the formatter
+pub(crate) struct FormatCommand {
+	/// The settings of the project the files belong to
+	pub(crate) settings: ProjectSettings,
+	/// The settings passed on the command line, they take precedence over the ones of the project
+	pub(crate) flags: FormatOverride,
+	/// Check that the formatted code has the same meaning as the original code
+	pub(crate) verify: bool,
+	pub(crate) mode: FormatMode,
+	/// The files known to be formatted, [None] when the cache is disabled
+	pub(crate) cache: Option<Cache>,
+	/// The file system the files are read from and written to
+	pub(crate) fs: Box<dyn FileSystem>,
+	/// The daemon the files are forwarded to, when it's running
+	pub(crate) daemon: Option<Daemon>,
+	/// The handlers of the files, which also cache the types of their packages
+	pub(crate) app: App,
+	/// The directory the relative paths are resolved from, to find the `package.json` files
+	/// above it
+	pub(crate) working_directory: PathBuf,
+}
+
+impl FormatCommand {
+	/// The configuration of the formatter for the file at `rome_path`
+	fn format_configuration(&self, rome_path: &RomePath) -> FormatConfiguration {
+		let mut configuration = rome_path.format_configuration(&self.settings);
+		self.flags.apply_to(&mut configuration);
+		configuration
+	}
+
+	/// Resolves the handler and the syntax of the file at `path`, which contains `source`. The
+	/// relative paths are resolved from the working directory
+	fn resolve_path(&self, path: &str, source: &str) -> RomePath<'_> {
+		let absolute_path = self.working_directory.join(path);
+		RomePath::new(&absolute_path.to_string_lossy())
+			.deduce_handler(&self.app)
+			.sniff_handler(&self.app, source)
+			.resolve_syntax(&self.app, self.fs.as_ref())
+	}
+
+	fn cache_key(&self, path: &str, content: &str) -> CacheKey {
+		let rome_path = self.resolve_path(path, content);
+		let configuration = self.format_configuration(&rome_path);
+		let kind = if self.verify {
+			CacheKind::Verified
+		} else {
+			CacheKind::Formatted
+		};
+		CacheKey::new(
+			kind,
+			Path::new(path),
+			content,
+			rome_path.syntax(),
+			&configuration,
+		)
+	}
+
+	/// Returns `true` if the cache knows that `content` is formatted
+	fn is_cached(&self, path: &str, content: &str) -> bool {
+		self.cache
+			.as_ref()
+			.map_or(false, |cache| cache.contains(self.cache_key(path, content)))
+	}
+
+	/// Records in the cache that `content` is formatted
+	fn add_to_cache(&self, path: &str, content: &str) {
+		if let Some(cache) = &self.cache {
+			cache.insert(self.cache_key(path, content));
+		}
+	}
+}
+
+/// What happened to a file when it was formatted
+#[derive(Debug)]
+pub(crate) enum FormatOutcome {
+	/// The file isn't formatted. It was written back in [FormatMode::Write], and `diff` holds the
+	/// changes in [FormatMode::Diff]
+	Changed { diff: Option<String> },
+	/// The file was already formatted
+	Unchanged,
+	/// Rome can't format the file, or its formatting is disabled
+	Skipped,
+	/// The file couldn't be formatted
+	Errored(Box<FileError>),
+}
+
+/// Why a file couldn't be formatted
+#[derive(Debug)]
+pub(crate) enum FileError {
+	/// The file couldn't be read or written
+	Io(FileSystemError),
+	/// The formatter returned an error. The source is kept to print its diagnostics
+	Format { source: String, error: FormatError },
+	/// The formatter panicked, with the message of the panic
+	Panic(String),
+}
+
+/// The number of files of each outcome
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct FormatSummary {
+	pub(crate) changed: usize,
+	pub(crate) unchanged: usize,
+	pub(crate) skipped: usize,
+	pub(crate) errored: usize,
+}
+
+impl FormatSummary {
+	fn add(&mut self, outcome: &FormatOutcome) {
+		match outcome {
+			FormatOutcome::Changed { .. } => self.changed += 1,
+			FormatOutcome::Unchanged => self.unchanged += 1,
+			FormatOutcome::Skipped => self.skipped += 1,
+			FormatOutcome::Errored(_) => self.errored += 1,
+		}
+	}
+}
+
+/// Formats the files of `traversal` in parallel and prints a summary.
+///
+/// Returns the exit code of the command: [EXIT_ERROR] if any of the files couldn't be formatted,
+/// [EXIT_UNFORMATTED] if the mode doesn't write the files and some of them aren't formatted.
+pub(crate) fn run(traversal: Traversal, command: &FormatCommand) -> i32 {
+	let start = Instant::now();
+	let mode = command.mode;
+
+	for error in &traversal.errors {
+		eprintln!("warning: {}", error);
+	}
+	for input in &traversal.unmatched {
+		eprintln!("warning: \"{}\" didn't match any file", input);
+	}
+
+	let mut results = process_files(traversal.files, |path| format_path(path, command));
+	results.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+	let mut summary = FormatSummary::default();
+	for (path, outcome) in &results {
+		summary.add(outcome);
+		match outcome {
+			FormatOutcome::Changed { diff: Some(diff) } => print!("{}", diff),
+			FormatOutcome::Changed { diff: None } if mode == FormatMode::Check => {
+				println!("{}", path.display())
+			}
+			FormatOutcome::Errored(error) => report_error(path, error),
+			_ => {}
+		}
+	}
+
+	if let Some(cache) = &command.cache {
+		if let Err(error) = cache.save() {
+			eprintln!(
+				"warning: cannot write the cache to {}: {}",
+				cache.path().display(),
+				error
+			);
+		}
+	}
+
+	// The summary goes to stderr, so that the output of --check and --diff can be piped
+	let elapsed = start.elapsed().as_secs_f32();
+	let processed = summary.changed + summary.unchanged;
+	if mode == FormatMode::Write {
+		eprintln!(
+			"Formatted {} files in {:.2}s: {} changed, {} unchanged, {} skipped, {} errored",
+			processed,
+			elapsed,
+			summary.changed,
+			summary.unchanged,
+			summary.skipped,
+			summary.errored
+		);
+	} else {
+		eprintln!(
+			"Checked {} files in {:.2}s: {} not formatted, {} formatted, {} skipped, {} errored",
+			processed,
+			elapsed,
+			summary.changed,
+			summary.unchanged,
+			summary.skipped,
+			summary.errored
+		);
+	}
+
+	if summary.errored > 0 {
+		EXIT_ERROR
+	} else if summary.changed > 0 && mode != FormatMode::Write {
+		EXIT_UNFORMATTED
+	} else {
+		EXIT_SUCCESS
+	}
+}
+
+/// Formats the file at `path` and handles the changes according to the mode of `command`
+pub(crate) fn format_path(path: &Path, command: &FormatCommand) -> FormatOutcome {
+	let source = match command.fs.read(path) {
+		Ok(source) => source,
+		Err(error) => return FormatOutcome::Errored(Box::new(FileError::Io(error))),
+	};
+
+	let path = path.to_string_lossy();
+	if command.is_cached(&path, &source) {
+		return FormatOutcome::Unchanged;
+	}
+
+	let formatted = match format_content(&path, &source, command) {
+		Ok(Some(formatted)) => formatted,
+		Ok(None) => return FormatOutcome::Skipped,
+		Err(error) => return FormatOutcome::Errored(error),
+	};
+
+	if formatted == source {
+		command.add_to_cache(&path, &source);
+		return FormatOutcome::Unchanged;
+	}
+
+	match command.mode {
+		FormatMode::Write => match RomePath::new(&path).save(command.fs.as_ref(), &formatted) {
+			Ok(()) => {
+				command.add_to_cache(&path, &formatted);
+				FormatOutcome::Changed { diff: None }
+			}
+			Err(error) => FormatOutcome::Errored(Box::new(FileError::Io(error))),
+		},
+		FormatMode::Check => FormatOutcome::Changed { diff: None },
+		FormatMode::Diff => FormatOutcome::Changed {
+			diff: Some(unified_diff(&path, &source, &formatted)),
+		},
+	}
+}
+
+/// Formats the code read from stdin as the content of a file at `file_path`, which doesn't need
+/// to exist. The formatted code is written to stdout in [FormatMode::Write].
+///
+/// Returns the exit code of the command, see [run].
+pub(crate) fn run_stdin(file_path: &str, command: &FormatCommand) -> i32 {
+	let mut source = String::new();
+	if let Err(error) = std::io::stdin().read_to_string(&mut source) {
+		eprintln!("error: cannot read stdin: {}", error);
+		return EXIT_ERROR;
+	}
+
+	// Editors replace the code with the output, it must be left as is
+	if !command
+		.format_configuration(&RomePath::new(file_path))
+		.enabled
+	{
+		if command.mode == FormatMode::Write {
+			print!("{}", source);
+		}
+		return EXIT_SUCCESS;
+	}
+
+	let result = format_content(file_path, &source, command);
+	let formatted = match result {
+		Ok(Some(formatted)) => formatted,
+		Ok(None) => {
+			eprintln!("error: {}: Rome can't format this kind of file", file_path);
+			return EXIT_ERROR;
+		}
+		Err(error) => {
+			report_error(Path::new(file_path), &error);
+			return EXIT_ERROR;
+		}
+	};
+
+	match command.mode {
+		FormatMode::Write => {
+			print!("{}", formatted);
+			EXIT_SUCCESS
+		}
+		_ if formatted == source => EXIT_SUCCESS,
+		FormatMode::Check => {
+			println!("{}", file_path);
+			EXIT_UNFORMATTED
+		}
+		FormatMode::Diff => {
+			print!("{}", unified_diff(file_path, &source, &formatted));
+			EXIT_UNFORMATTED
+		}
+	}
+}
+
+/// Formats `source` as the content of the file at `path`.
+///
+/// Returns [None] when Rome can't format this kind of file, or its formatting is disabled.
+fn format_content(
+	path: &str,
+	source: &str,
+	command: &FormatCommand,
+) -> Result<Option<String>, Box<FileError>> {
+	let rome_path = command.resolve_path(path, source);
+	let path = rome_path.to_string_lossy();
+
+	let configuration = command.format_configuration(&rome_path);
+	if !configuration.enabled {
+		return Ok(None);
+	}
+
+	// The daemon doesn't verify the formatted code. The files it can't format are formatted
+	// here, which reports their errors
+	if let Some(daemon) = command.daemon.as_ref().filter(|_| !command.verify) {
+		if let Some(formatted) = daemon.format(&path, source, &configuration) {
+			return Ok(formatted);
+		}
+	}
+
+	let result = catch_panic(|| {
+		let formatted = format_source(&rome_path, source, &configuration)?;
+		if command.verify {
+			rome_formatter::verify(&rome_path, source, &formatted, &configuration)?;
+		}
+		Ok(formatted)
+	});
+
+	match result {
+		Ok(Ok(formatted)) => Ok(Some(formatted.code().clone())),
+		Ok(Err(FormatError::UnsupportedLanguage | FormatError::CapabilityDisabled)) => Ok(None),
+		Ok(Err(error)) => Err(Box::new(FileError::Format {
+			source: source.to_string(),
+			error,
+		})),
+		Err(panic) => Err(Box::new(FileError::Panic(panic_message(panic)))),
+	}
+}
+
+thread_local! {
+	/// Whether the panics of the current thread are caught by [catch_panic]
+	static CATCHING_PANICS: Cell<bool> = Cell::new(false);
+}
+
+/// Calls `func` and catches its panics, without printing their message: they are reported with
+/// the other errors, the default hook would print them in the middle of the output.
+///
+/// The other panics, like the ones of the other threads, are printed by the previous hook.
+pub(crate) fn catch_panic<T>(func: impl FnOnce() -> T) -> std::thread::Result<T> {
+	static INSTALL_HOOK: Once = Once::new();
+	INSTALL_HOOK.call_once(|| {
+		let hook = panic::take_hook();
+		panic::set_hook(Box::new(move |info| {
+			if !CATCHING_PANICS.with(Cell::get) {
+				hook(info);
+			}
+		}));
+	});
+
+	CATCHING_PANICS.with(|catching| catching.set(true));
+	let result = panic::catch_unwind(AssertUnwindSafe(func));
+	CATCHING_PANICS.with(|catching| catching.set(false));
+	result
+}
+
+/// Returns the changes from `source` to `formatted` as a unified diff
+fn unified_diff(path: &str, source: &str, formatted: &str) -> String {
+	TextDiff::from_lines(source, formatted)
+		.unified_diff()
+		.header(path, path)
+		.to_string()
+}
+
+pub(crate) fn panic_message(panic: Box<dyn Any + Send>) -> String {
+	if let Some(message) = panic.downcast_ref::<&str>() {
+		message.to_string()
+	} else if let Some(message) = panic.downcast_ref::<String>() {
+		message.clone()
+	} else {
+		String::from("unknown panic")
+	}
+}
+
+pub(crate) fn report_error(path: &Path, error: &FileError) {
+	let path = path.display();
+	match error {
+		FileError::Io(error) => emit_diagnostics(&path.to_string(), "", &[error.to_diagnostic(0)]),
+		FileError::Format { source, error } => match error {
+			FormatError::SyntaxError(diagnostics) => {
+				emit_diagnostics(&path.to_string(), source, diagnostics)
+			}
+			FormatError::VerificationFailed(diagnostic) | FormatError::Io(diagnostic) => {
+				emit_diagnostics(&path.to_string(), source, std::slice::from_ref(diagnostic))
+			}
+			error => eprintln!("error: {}: {}", path, error),
+		},
+		FileError::Panic(message) => {
+			eprintln!("error: {}: the formatter panicked: {}", path, message)
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{
+		catch_panic, format_path, panic_message, FormatCommand, FormatMode, FormatOutcome,
+	};
+	use rome_core::{
+		cache::Cache,
+		configuration::{
+			parse_configuration, FormatOverride, ProjectConfiguration, ProjectSettings,
+		},
+		fs::MemoryFileSystem,
+	};
+	use rome_formatter::create_app;
+	use std::path::{Path, PathBuf};
+
+	fn command(mode: FormatMode) -> FormatCommand {
+		FormatCommand {
+			settings: ProjectSettings::default(),
+			flags: FormatOverride::default(),
+			verify: false,
+			mode,
+			cache: None,
+			fs: Box::new(MemoryFileSystem::default()),
+			daemon: None,
+			app: create_app(),
+			working_directory: PathBuf::new(),
+		}
+	}
+
+	#[test]
+	fn reports_the_outcome_of_each_file() {
+		let command = command(FormatMode::Write);
+
+		let changed = Path::new("changed.json");
+		command.fs.write(changed, "{ \"a\":1 }").unwrap();
+		assert!(matches!(
+			format_path(changed, &command),
+			FormatOutcome::Changed { diff: None }
+		));
+		assert_eq!(command.fs.read(changed).unwrap(), "{\"a\": 1}\n");
+		assert!(matches!(
+			format_path(changed, &command),
+			FormatOutcome::Unchanged
+		));
+
+		let skipped = Path::new("notes.txt");
+		command.fs.write(skipped, "some notes").unwrap();
+		assert!(matches!(
+			format_path(skipped, &command),
+			FormatOutcome::Skipped
+		));
+
+		let errored = Path::new("broken.json");
+		command.fs.write(errored, "{ /* comment */ }").unwrap();
+		assert!(matches!(
+			format_path(errored, &command),
+			FormatOutcome::Errored(_)
+		));
+		assert_eq!(
+			command.fs.read(errored).unwrap(),
+			"{ /* comment */ }",
+			"a file that can't be formatted is left untouched"
+		);
+
+		assert!(matches!(
+			format_path(Path::new("missing.json"), &command),
+			FormatOutcome::Errored(_)
+		));
+	}
+
+	#[test]
+	fn resolves_the_options_of_each_file() {
+		let configuration = parse_configuration(
+			r#"{
+				"format": { "indentStyle": "space", "lineWidth": 10 },
+				"overrides": [{ "include": ["legacy"], "format": { "enabled": false } }]
+			}"#,
+		)
+		.unwrap();
+		let project = ProjectConfiguration {
+			path: PathBuf::from("/project/rome.json"),
+			configuration,
+		};
+		let mut command = command(FormatMode::Write);
+		command.settings = ProjectSettings::new(Some(&project), Path::new("/project"));
+		command.flags.indent_size = Some(4);
+
+		let source = "{\"a\": [1, 2, 3]}\n";
+		let formatted = Path::new("formatted.json");
+		command.fs.write(formatted, source).unwrap();
+		assert!(matches!(
+			format_path(formatted, &command),
+			FormatOutcome::Changed { diff: None }
+		));
+		assert_eq!(
+			command.fs.read(formatted).unwrap(),
+			"{\n    \"a\": [\n        1,\n        2,\n        3\n    ]\n}\n"
+		);
+
+		let legacy = Path::new("legacy/file.json");
+		command.fs.write(legacy, source).unwrap();
+		assert!(matches!(
+			format_path(legacy, &command),
+			FormatOutcome::Skipped
+		));
+		assert_eq!(command.fs.read(legacy).unwrap(), source);
+	}
+
+	#[test]
+	fn check_and_diff_leave_the_files_untouched() {
+		let path = Path::new("file.json");
+		let mut command = command(FormatMode::Check);
+		command.fs.write(path, "{ \"a\":1 }\n").unwrap();
+
+		assert!(matches!(
+			format_path(path, &command),
+			FormatOutcome::Changed { diff: None }
+		));
+
+		command.mode = FormatMode::Diff;
+		match format_path(path, &command) {
+			FormatOutcome::Changed { diff: Some(diff) } => assert_eq!(
+				diff,
+				"--- file.json\n+++ file.json\n@@ -1 +1 @@\n-{ \"a\":1 }\n+{\"a\": 1}\n"
+			),
+			outcome => panic!("expected a diff, got {:?}", outcome),
+		}
+
+		assert_eq!(command.fs.read(path).unwrap(), "{ \"a\":1 }\n");
+	}
+
+	#[test]
+	fn skips_the_files_known_to_be_formatted() {
+		let root = std::env::temp_dir().join(format!("rome_cli_cache_{}", std::process::id()));
+		let path = Path::new("file.json");
+
+		let mut command = command(FormatMode::Write);
+		command.cache = Some(Cache::load(&root, "test"));
+		command.fs.write(path, "{ \"a\":1 }\n").unwrap();
+		assert!(matches!(
+			format_path(path, &command),
+			FormatOutcome::Changed { diff: None }
+		));
+
+		// The cache knows the written content is formatted, the file isn't read again as code
+		command.fs.write(path, "{\"a\": 1}\n").unwrap();
+		command.mode = FormatMode::Check;
+		assert!(matches!(
+			format_path(path, &command),
+			FormatOutcome::Unchanged
+		));
+
+		// The runs that verify the files don't trust the entries of the runs that don't
+		let unformatted = "{ \"b\":1 }\n";
+		command.add_to_cache("file.json", unformatted);
+		command.fs.write(path, unformatted).unwrap();
+		assert!(matches!(
+			format_path(path, &command),
+			FormatOutcome::Unchanged
+		));
+		command.verify = true;
+		assert!(matches!(
+			format_path(path, &command),
+			FormatOutcome::Changed { diff: None }
+		));
+		command.fs.write(path, "{\"b\": 1}\n").unwrap();
+
+		command.flags.indent_size = Some(4);
+		command.flags.line_width = Some(10);
+		command.fs.write(path, "{\"a\": [1, 2, 3]}\n").unwrap();
+		assert!(
+			matches!(
+				format_path(path, &command),
+				FormatOutcome::Changed { diff: None }
+			),
+			"the entries depend on the options"
+		);
+	}
+
+	#[test]
+	fn catches_the_panics_of_the_current_thread() {
+		let panic = catch_panic(|| panic!("the formatter failed")).unwrap_err();
+		assert_eq!(panic_message(panic), "the formatter failed");
+		assert_eq!(catch_panic(|| 1).unwrap(), 1);
+	}
+}
@@ -0,0 +1,16 @@
+use crate::commands::EXIT_ERROR;
+use std::io;
+
+/// Runs the language server over stdin and stdout, and returns the exit code of the process
+pub(crate) fn run() -> i32 {
+	let stdin = io::stdin();
+	let stdout = io::stdout();
+
+	match rome_lsp::run(stdin.lock(), stdout.lock()) {
+		Ok(exit_code) => exit_code,
+		Err(error) => {
+			eprintln!("error: the language server stopped: {}", error);
+			EXIT_ERROR
+		}
+	}
+}
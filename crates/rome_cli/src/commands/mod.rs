@@ -0,0 +1,59 @@
+use rslint_errors::{file::SimpleFile, Diagnostic, Formatter, LongFormatter};
+use std::path::{Path, PathBuf};
+use yastl::Pool;
+
+pub(crate) mod check;
+pub(crate) mod daemon;
+pub(crate) mod format;
+pub(crate) mod lsp;
+pub(crate) mod parse;
+
+/// The command completed, and the files it checked are formatted
+pub(crate) const EXIT_SUCCESS: i32 = 0;
+/// The files were checked and some of them aren't formatted
+pub(crate) const EXIT_UNFORMATTED: i32 = 1;
+/// Some of the files couldn't be read, parsed, formatted or written
+pub(crate) const EXIT_ERROR: i32 = 2;
+
+/// Prints the diagnostics of the file at `path` to stderr
+pub(crate) fn emit_diagnostics(path: &str, source: &str, diagnostics: &[Diagnostic]) {
+	emit_diagnostics_with(&mut LongFormatter, path, source, diagnostics)
+}
+
+/// Prints the diagnostics of the file at `path` to stderr with `formatter`
+pub(crate) fn emit_diagnostics_with(
+	formatter: &mut dyn Formatter,
+	path: &str,
+	source: &str,
+	diagnostics: &[Diagnostic],
+) {
+	let file = SimpleFile::new(path.to_string(), source.to_string());
+	formatter
+		.emit_stderr(diagnostics, &file)
+		.expect("Could not print the diagnostics");
+}
+
+/// Runs `func` on each of `files` on a thread pool with a thread per CPU, and returns the results
+/// in no particular order
+pub(crate) fn process_files<T: Send>(
+	files: impl IntoIterator<Item = PathBuf>,
+	func: impl Fn(&Path) -> T + Sync,
+) -> Vec<(PathBuf, T)> {
+	let pool = Pool::new(num_cpus::get());
+	let (tx, rx) = std::sync::mpsc::channel();
+	let func = &func;
+
+	pool.scoped(|scope| {
+		for path in files {
+			let tx = tx.clone();
+			scope.execute(move || {
+				let result = func(&path);
+				tx.send((path, result)).unwrap();
+			});
+		}
+	});
+
+	drop(tx);
+
+	rx.into_iter().collect()
+}
@@ -0,0 +1,210 @@
+use rome_core::{fs::FileSystem, App};
+use rome_formatter::parse;
+use rome_path::RomePath;
+use rslint_errors::Diagnostic;
+use rslint_parser::{tokenize, SyntaxNode, WalkEvent};
+use std::{
+	fmt::Write,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+use super::{emit_diagnostics, EXIT_ERROR, EXIT_SUCCESS};
+
+/// What `rome parse` prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Emit {
+	/// The syntax tree with its tokens, in the debug format of the tree
+	Cst,
+	/// The nodes of the syntax tree, without the tokens
+	Ast,
+	/// The tokens of the lexer, before they're parsed
+	Tokens,
+	/// The syntax tree with its tokens, serialized to JSON
+	Json,
+}
+
+impl FromStr for Emit {
+	type Err = &'static str;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"cst" => Ok(Self::Cst),
+			"ast" => Ok(Self::Ast),
+			"tokens" => Ok(Self::Tokens),
+			"json" => Ok(Self::Json),
+			_ => Err("Value not supported for emit. Try cst, ast, tokens or json"),
+		}
+	}
+}
+
+/// The settings of a run of `rome parse`
+pub(crate) struct ParseCommand {
+	/// The file system the file is read from
+	pub(crate) fs: Box<dyn FileSystem>,
+	/// The handlers of the files, which also cache the types of their packages
+	pub(crate) app: App,
+	/// The directory the relative paths are resolved from, to find the `package.json` files
+	/// above it
+	pub(crate) working_directory: PathBuf,
+	pub(crate) emit: Emit,
+}
+
+/// Prints what `command` emits for the file at `path` to stdout, and its syntax errors to stderr.
+///
+/// Returns the exit code of the command: [EXIT_ERROR] if the file can't be read or parsed, or if
+/// it has syntax errors. The output is printed in any case.
+pub(crate) fn run(path: &Path, command: &ParseCommand) -> i32 {
+	let source = match command.fs.read(path) {
+		Ok(source) => source,
+		Err(error) => {
+			eprintln!("error: cannot read {}: {}", path.display(), error);
+			return EXIT_ERROR;
+		}
+	};
+
+	let (output, diagnostics) = match dump(path, &source, command) {
+		Some(dumped) => dumped,
+		None => {
+			eprintln!("error: Rome can't parse {}", path.display());
+			return EXIT_ERROR;
+		}
+	};
+
+	print!("{}", output);
+	if diagnostics.is_empty() {
+		EXIT_SUCCESS
+	} else {
+		emit_diagnostics(&path.to_string_lossy(), &source, &diagnostics);
+		EXIT_ERROR
+	}
+}
+
+/// Returns what `command` emits for `source`, the content of the file at `path`, along with its
+/// syntax errors, or [None] if Rome can't parse the file.
+///
+/// The trees are the ones the handler of the file parses. The JSON files are parsed as a
+/// parenthesized expression, so the ranges of their trees are off by one.
+pub(crate) fn dump(
+	path: &Path,
+	source: &str,
+	command: &ParseCommand,
+) -> Option<(String, Vec<Diagnostic>)> {
+	let app = &command.app;
+	let absolute_path = command.working_directory.join(path);
+	let rome_path = RomePath::new(&absolute_path.to_string_lossy())
+		.deduce_handler(app)
+		.sniff_handler(app, source)
+		.resolve_syntax(app, command.fs.as_ref());
+
+	let parsed = parse(&rome_path, source)?;
+	let root = parsed.root();
+
+	let dumped = match command.emit {
+		Emit::Cst => (format!("{:#?}", root), parsed.diagnostics().to_vec()),
+		Emit::Ast => (ast(root), parsed.diagnostics().to_vec()),
+		Emit::Json => {
+			let mut json =
+				serde_json::to_string_pretty(root).expect("Could not serialize the syntax tree");
+			json.push('\n');
+			(json, parsed.diagnostics().to_vec())
+		}
+		// The tokens don't depend on the syntax of the file, only its errors of the lexer are
+		// reported
+		Emit::Tokens => tokens(source),
+	};
+	Some(dumped)
+}
+
+/// Prints the nodes of the tree, indented like in the debug format of the tree
+fn ast(root: &SyntaxNode) -> String {
+	let mut output = String::new();
+	let mut level = 0;
+	for event in root.preorder() {
+		match event {
+			WalkEvent::Enter(node) => {
+				writeln!(output, "{}{:?}", "  ".repeat(level), node).unwrap();
+				level += 1;
+			}
+			WalkEvent::Leave(_) => level -= 1,
+		}
+	}
+	output
+}
+
+/// Prints the tokens of `source` like the tokens of the debug format of the tree, along with
+/// the errors of the lexer
+fn tokens(source: &str) -> (String, Vec<Diagnostic>) {
+	let (tokens, errors) = tokenize(source, 0);
+
+	let mut output = String::new();
+	let mut start = 0;
+	for token in tokens {
+		let end = start + token.len;
+		writeln!(
+			output,
+			"{:?}@{}..{} {:?}",
+			token.kind,
+			start,
+			end,
+			&source[start..end]
+		)
+		.unwrap();
+		start = end;
+	}
+	(output, errors)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{dump, Emit, ParseCommand};
+	use rome_core::fs::MemoryFileSystem;
+	use rome_formatter::create_app;
+	use std::path::{Path, PathBuf};
+
+	fn command(emit: Emit) -> ParseCommand {
+		ParseCommand {
+			fs: Box::new(MemoryFileSystem::default()),
+			app: create_app(),
+			working_directory: PathBuf::new(),
+			emit,
+		}
+	}
+
+	fn emit(emit: Emit, path: &str, source: &str) -> String {
+		let (output, diagnostics) = dump(Path::new(path), source, &command(emit)).unwrap();
+		assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+		output
+	}
+
+	#[test]
+	fn emits_the_trees_and_the_tokens() {
+		let cst = emit(Emit::Cst, "index.js", "a;");
+		assert!(cst.starts_with("JS_ROOT@0..2\n"), "{}", cst);
+		assert!(cst.contains("    SEMICOLON@1..2 \";\"\n"), "{}", cst);
+
+		let ast = emit(Emit::Ast, "index.js", "a;");
+		assert!(ast.starts_with("JS_ROOT@0..2\n"), "{}", ast);
+		assert!(!ast.contains("SEMICOLON"), "{}", ast);
+
+		assert_eq!(
+			emit(Emit::Tokens, "index.js", "let a"),
+			"IDENT@0..3 \"let\"\nWHITESPACE@3..4 \" \"\nIDENT@4..5 \"a\"\nEOF@5..5 \"\"\n"
+		);
+
+		let json: serde_json::Value =
+			serde_json::from_str(&emit(Emit::Json, "index.js", "a;")).unwrap();
+		assert_eq!(json["kind"], "JS_ROOT");
+		assert_eq!(json["text_range"], serde_json::json!([0, 2]));
+	}
+
+	#[test]
+	fn returns_the_syntax_errors() {
+		let (output, diagnostics) =
+			dump(Path::new("index.js"), "let a = ;", &command(Emit::Cst)).unwrap();
+		assert!(output.starts_with("JS_ROOT@0..9\n"));
+		assert_eq!(diagnostics.len(), 1);
+
+		assert!(dump(Path::new("notes.txt"), "some notes", &command(Emit::Cst)).is_none());
+	}
+}
@@ -0,0 +1,105 @@
+//! Lists the files changed in the git repository of the working directory, by running the local
+//! `git` binary.
+//!
+//! Deleted files are left out, and the untracked files that aren't ignored count as changed.
+use std::{
+	fmt::{self, Display},
+	fs,
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+/// Which changes to look for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Changes {
+	/// The files that differ from the commit `REF`, committed or not, and the untracked files
+	Since(String),
+	/// The files whose changes are staged
+	Staged,
+}
+
+/// Why the changed files couldn't be listed
+#[derive(Debug)]
+pub(crate) struct GitError(String);
+
+impl Display for GitError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+/// Returns the files matching `changes`. They're relative to `working_directory` when they're
+/// inside it, absolute otherwise.
+pub(crate) fn changed_files(
+	working_directory: &Path,
+	changes: &Changes,
+) -> Result<Vec<PathBuf>, GitError> {
+	let top_level = git(working_directory, &["rev-parse", "--show-toplevel"])?;
+	let top_level = PathBuf::from(top_level.trim_end());
+
+	let names = match changes {
+		Changes::Since(reference) => {
+			let mut names = git(
+				working_directory,
+				&[
+					"diff",
+					"-z",
+					"--name-only",
+					"--diff-filter=d",
+					reference,
+					"--",
+				],
+			)?;
+			// ls-files only lists the files of the directory it runs in
+			names.push_str(&git(
+				&top_level,
+				&["ls-files", "-z", "--others", "--exclude-standard"],
+			)?);
+			names
+		}
+		Changes::Staged => git(
+			working_directory,
+			&["diff", "-z", "--name-only", "--diff-filter=d", "--cached"],
+		)?,
+	};
+
+	// git prints the paths relative to the top level of the repository, which is canonical
+	let working_directory =
+		fs::canonicalize(working_directory).unwrap_or_else(|_| working_directory.to_path_buf());
+	let mut files: Vec<PathBuf> = names
+		.split('\0')
+		.filter(|name| !name.is_empty())
+		.map(|name| {
+			let path = top_level.join(name);
+			match path.strip_prefix(&working_directory) {
+				Ok(relative) => relative.to_path_buf(),
+				Err(_) => path,
+			}
+		})
+		.collect();
+
+	files.sort();
+	files.dedup();
+	Ok(files)
+}
+
+/// Runs git with `args` in `directory` and returns what it printed
+fn git(directory: &Path, args: &[&str]) -> Result<String, GitError> {
+	let output = Command::new("git")
+		.args(args)
+		.current_dir(directory)
+		.output()
+		.map_err(|error| GitError(format!("cannot run git: {}", error)))?;
+
+	if !output.status.success() {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		return Err(GitError(format!(
+			"git {} failed: {}",
+			args[0],
+			stderr.trim_end()
+		)));
+	}
+
+	String::from_utf8(output.stdout)
+		.map_err(|_| GitError(format!("git {} printed a path that isn't UTF-8", args[0])))
+}
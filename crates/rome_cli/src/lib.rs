@@ -1,8 +1,31 @@
 use clap::{crate_version, App, AppSettings, Arg};
-use rome_core::create_app;
-use rome_formatter::{format_file_and_save, FormatOptions, IndentStyle};
-use rome_path::RomePath;
-use std::{path::PathBuf, str::FromStr};
+use commands::{
+	check::CheckCommand,
+	daemon::{Daemon, RUN_DAEMON},
+	format::{FormatCommand, FormatMode},
+	parse::{Emit, ParseCommand},
+};
+use git::Changes;
+use rome_core::{
+	cache::Cache,
+	configuration::{
+		load_configuration, ConfigurationError, FormatOverride, IndentStyle, JsonOverride,
+		ObjectWrap, ProjectConfiguration, ProjectSettings,
+	},
+	fs::{FileSystem, OsFileSystem},
+	walker::{FileWalker, WalkOptions},
+};
+use rome_formatter::create_app;
+use std::{
+	env,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+mod commands;
+mod git;
+mod traversal;
+mod watch;
 
 /// Main function to run Rome CLI
 pub fn run_cli() {
@@ -12,66 +35,344 @@ pub fn run_cli() {
 		.setting(AppSettings::SubcommandRequiredElseHelp)
 		.subcommand(
 			App::new("format")
-				.about("Format a file")
+				.about("Format files, directories and globs")
 				.arg(
 					Arg::new("indent_style")
 						.long("indent-style")
-						.about("The style of indentation")
+						.about("The style of indentation. Defaults to the indentStyle of rome.json, or tab")
 						.value_name("tab|space")
-						.default_value("tab")
+						.takes_value(true)
 						.validator(|value| IndentStyle::from_str(value).map(|_| ())),
 				)
 				.arg(
 					Arg::new("indent_size")
 						.long("indent-size")
-						.about("The size of the indent.")
+						.about("The size of the indent. Defaults to the indentSize of rome.json, or 2")
 						.value_name("NUMBER")
-						.default_value("2")
+						.takes_value(true)
 						.validator(|value| {
 							value
 								.parse::<u8>()
 								.map_err(|_| "Invalid indent-size value. Try using a number")
 						}),
 				)
+				.arg(
+					Arg::new("json_sort_keys")
+						.long("json-sort-keys")
+						.about("Sort the keys of JSON objects. package.json files use the conventional order of their fields"),
+				)
+				.arg(
+					Arg::new("json_object_wrap")
+						.long("json-object-wrap")
+						.about("Whether JSON objects and arrays written over multiple lines stay expanded. Defaults to the json.objectWrap of rome.json, or preserve")
+						.value_name("preserve|collapse")
+						.takes_value(true)
+						.validator(|value| ObjectWrap::from_str(value).map(|_| ())),
+				)
+				.arg(
+					Arg::new("verify")
+						.long("verify")
+						.about("Check that the formatted code has the same meaning as the original code and that formatting it again doesn't change it. The file is only written when both checks pass"),
+				)
+				.arg(
+					Arg::new("write")
+						.long("write")
+						.about("Write the formatted code back to the files. This is the default mode"),
+				)
+				.arg(
+					Arg::new("check")
+						.long("check")
+						.about("List the files that aren't formatted without writing them. Exits with 1 if there are any")
+						.conflicts_with_all(&["write", "diff"]),
+				)
+				.arg(
+					Arg::new("diff")
+						.long("diff")
+						.about("Print a unified diff of the changes without writing them. Exits with 1 if there are any")
+						.conflicts_with("write"),
+				)
+				.arg(
+					Arg::new("watch")
+						.long("watch")
+						.about("Keep running, and format the files again when they change")
+						.conflicts_with_all(&["changed", "staged"]),
+				)
+				.arg(
+					Arg::new("no_cache")
+						.long("no-cache")
+						.about("Format all the files, even the ones that .rome/cache knows are formatted, and don't update the cache"),
+				)
+				.arg(
+					Arg::new("stdin_file_path")
+						.long("stdin-file-path")
+						.about("Format the code read from stdin as if it was the content of this file, and print it to stdout. The file doesn't need to exist")
+						.value_name("PATH")
+						.takes_value(true)
+						.conflicts_with_all(&["input", "changed", "staged", "watch"]),
+				)
+				.arg(
+					Arg::new("changed")
+						.long("changed")
+						.about("Only format the files changed in git since --since, committed or not, and the untracked files. The inputs, if any, narrow them down"),
+				)
+				.arg(
+					Arg::new("since")
+						.long("since")
+						.about("The git reference --changed compares the files with. Defaults to HEAD")
+						.value_name("REF")
+						.takes_value(true)
+						.requires("changed"),
+				)
+				.arg(
+					Arg::new("staged")
+						.long("staged")
+						.about("Only format the files whose changes are staged in git. The inputs, if any, narrow them down")
+						.conflicts_with("changed"),
+				)
 				.arg(
 					Arg::new("input")
-						.about("File to format")
-						.required(true)
+						.about("Files, directories or globs to format. Directories are walked recursively")
+						.required_unless_present_any(&["stdin_file_path", "changed", "staged"])
+						.multiple_values(true)
+						.validator(traversal::validate_input),
+				),
+		)
+		.subcommand(
+			App::new("check")
+				.about("Report the syntax errors of files, directories and globs, and the files that aren't formatted. Exits with 2 if there are syntax errors, 1 if some files aren't formatted")
+				.arg(
+					Arg::new("max_diagnostics")
+						.long("max-diagnostics")
+						.about("The number of diagnostics to print at most")
+						.value_name("NUMBER")
+						.takes_value(true)
+						.default_value("20")
 						.validator(|value| {
-							let path = PathBuf::from(&value);
-							if !path.exists() {
-								return Err(format!("The file \"{}\" doesn't exist.", value));
-							}
-							Ok(())
+							value
+								.parse::<usize>()
+								.map_err(|_| "Invalid max-diagnostics value. Try using a number")
 						}),
+				)
+				.arg(
+					Arg::new("short")
+						.long("short")
+						.about("Print the diagnostics on a single line each"),
+				)
+				.arg(
+					Arg::new("watch")
+						.long("watch")
+						.about("Keep running, and check the files again when they change"),
+				)
+				.arg(
+					Arg::new("input")
+						.about("Files, directories or globs to check. Directories are walked recursively")
+						.required(true)
+						.multiple_values(true)
+						.validator(traversal::validate_input),
 				),
 		)
+		.subcommand(
+			App::new("parse")
+				.about("Print the syntax tree or the tokens of a file, and its syntax errors to stderr. Exits with 2 if there are syntax errors")
+				.arg(
+					Arg::new("emit")
+						.long("emit")
+						.about("What to print: the syntax tree with its tokens, its nodes only, the tokens of the lexer, or the syntax tree serialized to JSON")
+						.value_name("cst|ast|tokens|json")
+						.takes_value(true)
+						.default_value("cst")
+						.validator(|value| Emit::from_str(value).map(|_| ())),
+				)
+				.arg(
+					Arg::new("input")
+						.about("The file to parse")
+						.required(true),
+				),
+		)
+		.subcommand(
+			App::new("lsp")
+				.about("Start a language server that speaks the Language Server Protocol over stdin and stdout"),
+		)
+		.subcommand(
+			App::new("start")
+				.about("Start a daemon in the background, which keeps the files of the project in memory. The commands forward the files to it while it's running"),
+		)
+		.subcommand(App::new("stop").about("Stop the daemon of the project"))
+		.subcommand(
+			App::new(RUN_DAEMON)
+				.setting(AppSettings::Hidden)
+				.arg(Arg::new("socket").required(true)),
+		)
 		.try_get_matches();
 	let subcommand_matches = match &matches {
 		Ok(r) => r.subcommand(),
 		Err(err) => err.exit(),
 	};
 
-	let app = create_app();
-
 	match subcommand_matches {
 		Some(("format", matches)) => {
-			let size = matches.value_of("indent_size");
-			let style = matches.value_of("indent_style");
-			let input = matches.value_of("input").unwrap();
-			let options: IndentStyle = style
-				.map(|s| match s {
-					"tab" => IndentStyle::Tab,
-					"space" => {
-						let size = size.unwrap_or("2");
-						IndentStyle::Space(size.parse::<u8>().unwrap_or(2))
-					}
-					_ => IndentStyle::default(),
-				})
-				.unwrap_or_default();
+			let working_directory = current_dir();
+			let project = load_project_configuration(&OsFileSystem, &working_directory);
+			let settings = ProjectSettings::new(project.as_ref(), &working_directory);
+			let root = project_root(project.as_ref(), &working_directory);
+
+			let cache = if matches.is_present("no_cache") || matches.is_present("stdin_file_path") {
+				None
+			} else {
+				Some(Cache::load(root, crate_version!()))
+			};
+
+			// The flags of the command line take precedence over the configuration file
+			let flags = FormatOverride {
+				indent_style: matches
+					.value_of("indent_style")
+					.map(|value| IndentStyle::from_str(value).unwrap()),
+				indent_size: matches
+					.value_of("indent_size")
+					.map(|value| value.parse().unwrap()),
+				json: JsonOverride {
+					sort_keys: if matches.is_present("json_sort_keys") {
+						Some(true)
+					} else {
+						None
+					},
+					object_wrap: matches
+						.value_of("json_object_wrap")
+						.map(|value| ObjectWrap::from_str(value).unwrap()),
+				},
+				..FormatOverride::default()
+			};
+
+			let mode = if matches.is_present("check") {
+				FormatMode::Check
+			} else if matches.is_present("diff") {
+				FormatMode::Diff
+			} else {
+				FormatMode::Write
+			};
+
+			let command = FormatCommand {
+				settings,
+				flags,
+				verify: matches.is_present("verify"),
+				mode,
+				cache,
+				fs: Box::new(OsFileSystem),
+				daemon: Daemon::connect(root),
+				app: create_app(),
+				working_directory: working_directory.clone(),
+			};
+
+			let changes = if matches.is_present("changed") {
+				let reference = matches.value_of("since").unwrap_or("HEAD");
+				Some(Changes::Since(reference.to_string()))
+			} else if matches.is_present("staged") {
+				Some(Changes::Staged)
+			} else {
+				None
+			};
 
-			let mut file = RomePath::new(input).deduce_handler(&app);
-			format_file_and_save(&mut file, FormatOptions::new(options));
+			let exit_code = if let Some(file_path) = matches.value_of("stdin_file_path") {
+				commands::format::run_stdin(file_path, &command)
+			} else {
+				let inputs: Vec<&str> = matches
+					.values_of("input")
+					.map_or_else(Vec::new, Iterator::collect);
+				let app = &command.app;
+				let walker = FileWalker::new(command.settings.files(), WalkOptions::default());
+				let traversal = match changes {
+					Some(changes) => match git::changed_files(&working_directory, &changes) {
+						Ok(changed) => traversal::filter_changed(
+							changed,
+							&inputs,
+							app,
+							command.settings.files(),
+						),
+						Err(error) => {
+							eprintln!("error: cannot list the changed files: {}", error);
+							std::process::exit(commands::EXIT_ERROR);
+						}
+					},
+					None => traversal::traverse(&inputs, app, &walker),
+				};
+				let exit_code = commands::format::run(traversal, &command);
+
+				if matches.is_present("watch") {
+					watch::watch(&inputs, app, &walker, |traversal| {
+						commands::format::run(traversal, &command);
+					})
+				} else {
+					exit_code
+				}
+			};
+			if exit_code != commands::EXIT_SUCCESS {
+				std::process::exit(exit_code);
+			}
+		}
+		Some(("check", matches)) => {
+			let working_directory = current_dir();
+			let project = load_project_configuration(&OsFileSystem, &working_directory);
+			let root = project_root(project.as_ref(), &working_directory);
+			let command = CheckCommand {
+				settings: ProjectSettings::new(project.as_ref(), &working_directory),
+				fs: Box::new(OsFileSystem),
+				daemon: Daemon::connect(root),
+				app: create_app(),
+				working_directory: working_directory.clone(),
+				// The value has a default, and the validator checked it
+				max_diagnostics: matches
+					.value_of("max_diagnostics")
+					.unwrap()
+					.parse()
+					.unwrap(),
+				short: matches.is_present("short"),
+			};
+
+			let inputs: Vec<&str> = matches.values_of("input").unwrap().collect();
+			let walker = FileWalker::new(command.settings.files(), WalkOptions::default());
+			let traversal = traversal::traverse(&inputs, &command.app, &walker);
+			let exit_code = commands::check::run(traversal, &command);
+
+			let exit_code = if matches.is_present("watch") {
+				watch::watch(&inputs, &command.app, &walker, |traversal| {
+					commands::check::run(traversal, &command);
+				})
+			} else {
+				exit_code
+			};
+			if exit_code != commands::EXIT_SUCCESS {
+				std::process::exit(exit_code);
+			}
+		}
+		Some(("parse", matches)) => {
+			let command = ParseCommand {
+				fs: Box::new(OsFileSystem),
+				app: create_app(),
+				working_directory: current_dir(),
+				// The value has a default, and the validator checked it
+				emit: Emit::from_str(matches.value_of("emit").unwrap()).unwrap(),
+			};
+			let path = Path::new(matches.value_of_os("input").unwrap());
+			let exit_code = commands::parse::run(path, &command);
+			if exit_code != commands::EXIT_SUCCESS {
+				std::process::exit(exit_code);
+			}
+		}
+		Some(("lsp", _)) => std::process::exit(commands::lsp::run()),
+		Some((command @ ("start" | "stop"), _)) => {
+			let working_directory = current_dir();
+			let project = load_project_configuration(&OsFileSystem, &working_directory);
+			let root = project_root(project.as_ref(), &working_directory);
+			let exit_code = if command == "start" {
+				commands::daemon::start(root)
+			} else {
+				commands::daemon::stop(root)
+			};
+			std::process::exit(exit_code)
+		}
+		Some((RUN_DAEMON, matches)) => {
+			let socket = PathBuf::from(matches.value_of_os("socket").unwrap());
+			std::process::exit(commands::daemon::run(&socket))
 		}
 		// Thanks to the settings AppSettings::SubcommandRequiredElseHelp we should not be there
 		_ => clap::Error::with_description(
@@ -81,3 +382,47 @@ pub fn run_cli() {
 		.exit(),
 	}
 }
+
+/// The working directory of the process. Exits the process if it can't be read
+fn current_dir() -> PathBuf {
+	match env::current_dir() {
+		Ok(directory) => directory,
+		Err(error) => {
+			eprintln!("error: cannot read the working directory: {}", error);
+			std::process::exit(commands::EXIT_ERROR);
+		}
+	}
+}
+
+/// The root of the project, where the cache and the socket of the daemon are: the directory of
+/// `rome.json`, or the working directory without one
+fn project_root<'a>(
+	project: Option<&'a ProjectConfiguration>,
+	working_directory: &'a Path,
+) -> &'a Path {
+	project.map_or(working_directory, ProjectConfiguration::root)
+}
+
+/// Loads the `rome.json` file of the project that contains `working_directory`, if there's one.
+///
+/// Exits the process if the file can't be read or isn't valid.
+fn load_project_configuration(
+	fs: &dyn FileSystem,
+	working_directory: &Path,
+) -> Option<ProjectConfiguration> {
+	match load_configuration(fs, working_directory) {
+		Ok(project) => project,
+		Err(ConfigurationError::Io { path, error }) => {
+			eprintln!("error: cannot read {}: {}", path.display(), error);
+			std::process::exit(commands::EXIT_ERROR);
+		}
+		Err(ConfigurationError::Invalid {
+			path,
+			source,
+			diagnostics,
+		}) => {
+			commands::emit_diagnostics(&path.to_string_lossy(), &source, &diagnostics);
+			std::process::exit(commands::EXIT_ERROR);
+		}
+	}
+}
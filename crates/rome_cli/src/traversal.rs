@@ -0,0 +1,363 @@
+//! Expands the inputs given on the command line into the list of files to process.
+//!
+//! An input can be:
+//! - a file, which is always processed, even when Rome doesn't know how to handle it
+//! - a directory, which is walked recursively. Only the files that have a handler are kept, by
+//!   their name, their extension or the shebang of the scripts without extension
+//! - a glob like `packages/**/*.ts`. The directory before the first wildcard is walked and every
+//!   file matching the glob is kept
+//!
+//! Directories are walked by the [FileWalker] of `rome_core`, which skips the ignored, hidden
+//! and excluded files.
+//!
+//! With `--changed` or `--staged`, the files come from git instead, and the inputs only narrow
+//! them down.
+use globset::{GlobBuilder, GlobMatcher};
+use rome_core::{
+	configuration::FilesMatcher,
+	file_handlers::Language,
+	walker::{FileWalker, WalkError},
+	App,
+};
+use rome_path::RomePath;
+use std::{
+	collections::BTreeSet,
+	env,
+	fs::File,
+	io::{BufRead, BufReader, Read},
+	path::{Component, Path, PathBuf},
+};
+
+/// The characters that make an input a glob instead of a path
+const GLOB_CHARACTERS: &[char] = &['*', '?', '[', ']', '{', '}'];
+
+/// Returns `true` if `input` contains glob characters. The inputs that name an existing path are
+/// still paths, like a `pages/[id].js` file, so check that first
+pub(crate) fn is_glob(input: &str) -> bool {
+	input.contains(GLOB_CHARACTERS)
+}
+
+/// Checks that `input` is an existing path or a valid glob
+pub(crate) fn validate_input(input: &str) -> Result<(), String> {
+	if Path::new(input).exists() {
+		Ok(())
+	} else if is_glob(input) {
+		compile_glob(input)
+			.map(|_| ())
+			.map_err(|error| format!("The glob \"{}\" is invalid: {}", input, error))
+	} else {
+		Err(format!("The file \"{}\" doesn't exist.", input))
+	}
+}
+
+/// The files matched by the inputs of the command line
+#[derive(Debug, Default)]
+pub(crate) struct Traversal {
+	/// The files to process, sorted and without duplicates
+	pub(crate) files: BTreeSet<PathBuf>,
+	/// The inputs that didn't match any file
+	pub(crate) unmatched: Vec<String>,
+	/// The errors found while walking the directories
+	pub(crate) errors: Vec<WalkError>,
+}
+
+/// Collects the files matched by `inputs`
+pub(crate) fn traverse(inputs: &[&str], app: &App, walker: &FileWalker) -> Traversal {
+	let mut traversal = Traversal::default();
+
+	for input in inputs {
+		let count = traversal.files.len();
+		let path = Path::new(input);
+
+		if path.is_dir() {
+			traverse_directory(path, app, walker, &mut traversal);
+		} else if !path.exists() && is_glob(input) {
+			if let Ok(matcher) = compile_glob(input) {
+				traverse_glob(&matcher, input, walker, &mut traversal);
+			}
+		} else {
+			traversal.files.insert(path.to_path_buf());
+		}
+
+		if traversal.files.len() == count && !path.is_file() {
+			traversal.unmatched.push(input.to_string());
+		}
+	}
+
+	traversal
+}
+
+/// Keeps the `changed` files that Rome knows how to handle and that belong to the project. When
+/// there are `inputs`, the files must also be inside one of them or match one of them.
+pub(crate) fn filter_changed(
+	changed: Vec<PathBuf>,
+	inputs: &[&str],
+	app: &App,
+	files: &FilesMatcher,
+) -> Traversal {
+	let filters: Vec<InputFilter> = inputs.iter().map(|input| InputFilter::new(input)).collect();
+
+	let files = changed
+		.into_iter()
+		.filter(|path| {
+			path.is_file()
+				&& has_known_language(path, app)
+				&& files.is_included(path)
+				&& (filters.is_empty() || filters.iter().any(|filter| filter.matches(path)))
+		})
+		.collect();
+
+	Traversal {
+		files,
+		..Traversal::default()
+	}
+}
+
+/// An input of the command line, used to narrow down the changed files
+enum InputFilter {
+	Glob(GlobMatcher),
+	Path(PathBuf),
+}
+
+impl InputFilter {
+	fn new(input: &str) -> Self {
+		let path = Path::new(input);
+		match compile_glob(input) {
+			Ok(matcher) if !path.exists() && is_glob(input) => InputFilter::Glob(matcher),
+			_ => InputFilter::Path(absolute(path)),
+		}
+	}
+
+	fn matches(&self, path: &Path) -> bool {
+		match self {
+			InputFilter::Glob(matcher) => matcher.is_match(path),
+			InputFilter::Path(input) => absolute(path).starts_with(input),
+		}
+	}
+}
+
+/// Resolves `path` from the working directory and removes its `.` components, so that "./src",
+/// "src" and "/project/src" are the same path
+fn absolute(path: &Path) -> PathBuf {
+	env::current_dir()
+		.unwrap_or_default()
+		.join(path)
+		.components()
+		.filter(|component| !matches!(component, Component::CurDir))
+		.collect()
+}
+
+/// Walks `directory` and keeps the files that Rome knows how to handle
+fn traverse_directory(directory: &Path, app: &App, walker: &FileWalker, traversal: &mut Traversal) {
+	for result in walker.walk(directory) {
+		match result {
+			Ok(path) if has_known_language(&path, app) => {
+				traversal.files.insert(path);
+			}
+			Ok(_) => {}
+			Err(error) => traversal.errors.push(error),
+		}
+	}
+}
+
+/// Walks the directory at the start of `glob` and keeps the files it matches
+fn traverse_glob(
+	matcher: &GlobMatcher,
+	glob: &str,
+	walker: &FileWalker,
+	traversal: &mut Traversal,
+) {
+	let base = glob_base(glob);
+	let walk_root = if base.as_os_str().is_empty() {
+		Path::new(".")
+	} else {
+		base.as_path()
+	};
+
+	for result in walker.walk(walk_root) {
+		let path = match result {
+			// Walking "." yields paths like "./src/index.js", which a glob like "src/*.js" doesn't match
+			Ok(path) if base.as_os_str().is_empty() => match path.strip_prefix(".") {
+				Ok(stripped) => stripped.to_path_buf(),
+				Err(_) => path,
+			},
+			Ok(path) => path,
+			Err(error) => {
+				traversal.errors.push(error);
+				continue;
+			}
+		};
+
+		if matcher.is_match(&path) {
+			traversal.files.insert(path);
+		}
+	}
+}
+
+/// Returns the longest leading part of `glob` without wildcards
+pub(crate) fn glob_base(glob: &str) -> PathBuf {
+	let mut base = PathBuf::new();
+
+	for component in Path::new(glob).components() {
+		if let Component::Normal(name) = component {
+			if name.to_str().map_or(true, is_glob) {
+				break;
+			}
+		}
+		base.push(component);
+	}
+
+	// The last component could be a file name, for example when the glob is "src/{a,b}.js"
+	if base == Path::new(glob) {
+		base.pop();
+	}
+
+	base
+}
+
+fn compile_glob(glob: &str) -> Result<GlobMatcher, globset::Error> {
+	GlobBuilder::new(glob)
+		.literal_separator(true)
+		.build()
+		.map(|glob| glob.compile_matcher())
+}
+
+fn has_known_language(path: &Path, app: &App) -> bool {
+	let path_name = path.to_string_lossy();
+	let mut rome_path = RomePath::new(&path_name).deduce_handler(app);
+	if rome_path.get_handler().is_none() {
+		if let Some(first_line) = read_first_line(path) {
+			rome_path = rome_path.sniff_handler(app, &first_line);
+		}
+	}
+
+	rome_path.get_handler().map_or(false, |handler| {
+		!matches!(handler.language(), Language::Unknown)
+	})
+}
+
+/// Reads the first line of the file at `path`, where the shebang of a script is. Binary files
+/// aren't read further than a shebang could be
+fn read_first_line(path: &Path) -> Option<String> {
+	let file = File::open(path).ok()?;
+	let mut line = String::new();
+	BufReader::new(file.take(256)).read_line(&mut line).ok()?;
+	Some(line)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{filter_changed, glob_base, is_glob, traverse};
+	use rome_core::{
+		configuration::FilesMatcher,
+		walker::{FileWalker, WalkOptions},
+	};
+	use rome_formatter::create_app;
+	use std::{fs, path::PathBuf};
+
+	#[test]
+	fn detects_globs() {
+		assert!(is_glob("src/**/*.js"));
+		assert!(is_glob("src/{a,b}.js"));
+		assert!(!is_glob("src/index.js"));
+	}
+
+	#[test]
+	fn glob_base_stops_at_the_first_wildcard() {
+		assert_eq!(glob_base("packages/**/*.ts"), PathBuf::from("packages"));
+		assert_eq!(glob_base("src/lib/*.js"), PathBuf::from("src/lib"));
+		assert_eq!(glob_base("*.js"), PathBuf::new());
+		assert_eq!(glob_base("**/*.json"), PathBuf::new());
+	}
+
+	#[test]
+	fn traverses_directories_and_globs() {
+		let root = std::env::temp_dir().join(format!("rome_cli_traversal_{}", std::process::id()));
+		let nested = root.join("packages").join("a");
+		fs::create_dir_all(&nested).unwrap();
+		fs::write(root.join("index.js"), "").unwrap();
+		fs::write(root.join("README.md"), "").unwrap();
+		fs::write(nested.join("lib.ts"), "").unwrap();
+		fs::write(nested.join("lib.js"), "").unwrap();
+
+		let app = create_app();
+		let root_str = root.to_str().unwrap().to_string();
+		let glob = format!("{}/packages/**/*.ts", root_str);
+		let missing = format!("{}/missing/*.js", root_str);
+
+		let walker = FileWalker::new(&FilesMatcher::default(), WalkOptions::default());
+
+		let directory = traverse(&[root_str.as_str()], &app, &walker);
+		assert_eq!(
+			directory.files.into_iter().collect::<Vec<_>>(),
+			vec![
+				root.join("index.js"),
+				nested.join("lib.js"),
+				nested.join("lib.ts")
+			]
+		);
+
+		let globbed = traverse(&[glob.as_str(), missing.as_str()], &app, &walker);
+		assert_eq!(
+			globbed.files.into_iter().collect::<Vec<_>>(),
+			vec![nested.join("lib.ts")]
+		);
+		assert_eq!(globbed.unmatched, vec![missing]);
+
+		let changed = vec![
+			root.join("README.md"),
+			root.join("deleted.js"),
+			root.join("index.js"),
+			nested.join("lib.ts"),
+		];
+		let packages = format!("{}/./packages", root_str);
+		let filtered = filter_changed(
+			changed.clone(),
+			&[packages.as_str()],
+			&app,
+			&FilesMatcher::default(),
+		);
+		assert_eq!(
+			filtered.files.into_iter().collect::<Vec<_>>(),
+			vec![nested.join("lib.ts")]
+		);
+		let filtered = filter_changed(changed, &[], &app, &FilesMatcher::default());
+		assert_eq!(
+			filtered.files.into_iter().collect::<Vec<_>>(),
+			vec![root.join("index.js"), nested.join("lib.ts")]
+		);
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+
+	#[test]
+	fn prefers_the_existing_paths_to_globs() {
+		let root = std::env::temp_dir().join(format!("rome_cli_brackets_{}", std::process::id()));
+		let pages = root.join("pages");
+		fs::create_dir_all(&pages).unwrap();
+		fs::write(pages.join("[id].js"), "").unwrap();
+		fs::write(pages.join("d.js"), "").unwrap();
+
+		let app = create_app();
+		let walker = FileWalker::new(&FilesMatcher::default(), WalkOptions::default());
+		let input = format!("{}/pages/[id].js", root.to_str().unwrap());
+
+		let traversal = traverse(&[input.as_str()], &app, &walker);
+		assert_eq!(
+			traversal.files.into_iter().collect::<Vec<_>>(),
+			vec![pages.join("[id].js")]
+		);
+		assert!(traversal.unmatched.is_empty());
+
+		let changed = vec![pages.join("[id].js"), pages.join("d.js")];
+		let filtered = filter_changed(changed, &[input.as_str()], &app, &FilesMatcher::default());
+		assert_eq!(
+			filtered.files.into_iter().collect::<Vec<_>>(),
+			vec![pages.join("[id].js")]
+		);
+
+		fs::remove_dir_all(&root).unwrap();
+		let globbed = traverse(&[input.as_str()], &app, &walker);
+		assert_eq!(globbed.unmatched, vec![input]);
+	}
+}
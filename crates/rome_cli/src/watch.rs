@@ -0,0 +1,155 @@
+//! Runs a command again on the files that change, until the process is interrupted.
+//!
+//! The directories of the inputs are watched recursively, and the changes are filtered with the
+//! same rules as the traversal: ignored, hidden and excluded files don't trigger a run. The
+//! events are debounced, so saving several files at once leads to a single run.
+use crate::{
+	commands::EXIT_ERROR,
+	traversal::{filter_changed, glob_base, is_glob, Traversal},
+};
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use rome_core::{walker::FileWalker, App};
+use std::{
+	collections::HashMap,
+	env, fs,
+	path::{Path, PathBuf},
+	sync::mpsc::channel,
+	time::{Duration, SystemTime},
+};
+
+/// How long the file system must be quiet before the command runs
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the files matched by `inputs` and calls `run` with the ones that change.
+///
+/// It only returns if the files can't be watched, with [EXIT_ERROR].
+pub(crate) fn watch(
+	inputs: &[&str],
+	app: &App,
+	walker: &FileWalker,
+	mut run: impl FnMut(Traversal),
+) -> i32 {
+	let working_directory = match env::current_dir() {
+		Ok(directory) => directory,
+		Err(error) => {
+			eprintln!("error: cannot read the working directory: {}", error);
+			return EXIT_ERROR;
+		}
+	};
+
+	let (tx, rx) = channel();
+	let mut watcher = match notify::watcher(tx, DEBOUNCE) {
+		Ok(watcher) => watcher,
+		Err(error) => {
+			eprintln!("error: cannot watch the files: {}", error);
+			return EXIT_ERROR;
+		}
+	};
+
+	let roots: Vec<PathBuf> = inputs
+		.iter()
+		.map(|input| working_directory.join(watch_root(input)))
+		.collect();
+	for root in &roots {
+		if let Err(error) = watcher.watch(root, RecursiveMode::Recursive) {
+			eprintln!("error: cannot watch {}: {}", root.display(), error);
+			return EXIT_ERROR;
+		}
+	}
+
+	eprintln!("Watching for changes, press Ctrl-C to stop");
+
+	// The modification time of the files when the command last ran, to ignore the events caused
+	// by the command itself, like a file written by the formatter
+	let mut processed: HashMap<PathBuf, SystemTime> = HashMap::new();
+
+	while let Ok(event) = rx.recv() {
+		let mut changed = Vec::new();
+		changed.extend(changed_path(event));
+		changed.extend(rx.try_iter().filter_map(changed_path));
+
+		let changed: Vec<PathBuf> = changed
+			.into_iter()
+			.filter(|path| {
+				roots.iter().any(|root| walker.is_walked(root, path))
+					&& modified(path) != processed.get(path).copied()
+			})
+			.map(|path| relative_to(&working_directory, path))
+			.collect();
+
+		let traversal = filter_changed(changed, inputs, app, walker.files());
+		if traversal.files.is_empty() {
+			continue;
+		}
+
+		let files: Vec<PathBuf> = traversal.files.iter().cloned().collect();
+		run(traversal);
+
+		for file in files {
+			let path = working_directory.join(&file);
+			if let Some(modified) = modified(&path) {
+				processed.insert(path, modified);
+			}
+		}
+	}
+
+	eprintln!("error: the file watcher stopped");
+	EXIT_ERROR
+}
+
+/// The directory to watch for `input`: the input itself, or the directory at the start of a glob
+fn watch_root(input: &str) -> PathBuf {
+	if Path::new(input).exists() || !is_glob(input) {
+		return PathBuf::from(input);
+	}
+
+	let base = glob_base(input);
+	if base.as_os_str().is_empty() {
+		PathBuf::from(".")
+	} else {
+		base
+	}
+}
+
+/// Returns the file created or modified by `event`
+fn changed_path(event: DebouncedEvent) -> Option<PathBuf> {
+	match event {
+		DebouncedEvent::Create(path)
+		| DebouncedEvent::Write(path)
+		| DebouncedEvent::Rename(_, path) => Some(path),
+		DebouncedEvent::Error(error, path) => {
+			let path = path.map_or_else(String::new, |path| format!("{}: ", path.display()));
+			eprintln!("warning: {}{}", path, error);
+			None
+		}
+		_ => None,
+	}
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+	fs::metadata(path)
+		.and_then(|metadata| metadata.modified())
+		.ok()
+}
+
+/// Returns `path` relative to `working_directory` when it's inside it, like the paths of the
+/// traversal
+fn relative_to(working_directory: &Path, path: PathBuf) -> PathBuf {
+	match path.strip_prefix(working_directory) {
+		Ok(relative) => relative.to_path_buf(),
+		Err(_) => path,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::watch_root;
+	use std::path::PathBuf;
+
+	#[test]
+	fn watches_the_directory_at_the_start_of_globs() {
+		assert_eq!(watch_root("src"), PathBuf::from("src"));
+		assert_eq!(watch_root("packages/**/*.ts"), PathBuf::from("packages"));
+		assert_eq!(watch_root("*.json"), PathBuf::from("."));
+	}
+}
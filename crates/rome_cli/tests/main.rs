@@ -1,4 +1,13 @@
-use std::{env, path::PathBuf, process::Command};
+use std::{
+	env, fs,
+	io::{BufRead, BufReader, Write},
+	ops::Deref,
+	path::{Path, PathBuf},
+	process::{Child, Command, Output, Stdio},
+	sync::mpsc,
+	thread,
+	time::Duration,
+};
 
 fn cargo_bin(name: &str) -> PathBuf {
 	env::current_exe()
@@ -13,6 +22,56 @@ fn cargo_bin(name: &str) -> PathBuf {
 		.expect("cannot get current exe")
 }
 
+/// Runs the CLI with `args` in the directory `current_dir`
+fn run_cli(current_dir: &Path, args: &[&str]) -> Output {
+	Command::new(cargo_bin("cli"))
+		.current_dir(current_dir)
+		.args(args)
+		.output()
+		.expect("fail to run cli")
+}
+
+/// A directory of the temporary directory of the system, removed when the test ends, even when
+/// an assertion fails
+struct TempDir(PathBuf);
+
+impl TempDir {
+	/// Creates an empty directory, `name` tells the tests apart
+	fn new(name: &str) -> Self {
+		let path = env::temp_dir().join(format!("rome_cli_{}_{}", name, std::process::id()));
+		// The directory of a previous run that couldn't be removed
+		let _ = fs::remove_dir_all(&path);
+		fs::create_dir_all(&path).unwrap();
+		Self(path)
+	}
+
+	/// Writes the file at `path`, relative to the directory, and creates its parent directories
+	fn write(&self, path: &str, content: &str) {
+		let path = self.join(path);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, content).unwrap();
+	}
+
+	/// Reads the file at `path`, relative to the directory
+	fn read(&self, path: &str) -> String {
+		fs::read_to_string(self.join(path)).unwrap()
+	}
+}
+
+impl Deref for TempDir {
+	type Target = Path;
+
+	fn deref(&self) -> &Path {
+		&self.0
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
+
 #[test]
 #[ignore = "The CLI for now, doesn't print anything"]
 fn test_format_cli() {
@@ -34,3 +93,464 @@ fn test_format_cli() {
 "#
 	);
 }
+
+#[test]
+fn test_format_check_exit_codes() {
+	let root = TempDir::new("check_exit");
+	let formatted = root.join("formatted.json");
+	let unformatted = root.join("unformatted.json");
+	let broken = root.join("broken.json");
+	root.write("formatted.json", "{\"a\": 1}\n");
+	root.write("unformatted.json", "{ \"a\":1 }\n");
+	root.write("broken.json", "{ /* comment */ }\n");
+
+	let check = |path: &PathBuf| run_cli(&root, &["format", "--check", path.to_str().unwrap()]);
+
+	let res = check(&formatted);
+	assert_eq!(res.status.code(), Some(0));
+	assert!(res.stdout.is_empty());
+
+	let res = check(&unformatted);
+	assert_eq!(res.status.code(), Some(1));
+	assert_eq!(
+		String::from_utf8(res.stdout).unwrap(),
+		format!("{}\n", unformatted.display())
+	);
+	assert_eq!(
+		root.read("unformatted.json"),
+		"{ \"a\":1 }\n",
+		"--check doesn't write the files"
+	);
+
+	let res = check(&broken);
+	assert_eq!(res.status.code(), Some(2));
+}
+
+#[test]
+fn test_format_stdin() {
+	let format_stdin = |file_path: &str, source: &str| {
+		let mut child = Command::new(cargo_bin("cli"))
+			.args(&["format", "--stdin-file-path", file_path])
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()
+			.expect("fail to run cli format");
+		child
+			.stdin
+			.take()
+			.unwrap()
+			.write_all(source.as_bytes())
+			.unwrap();
+		child.wait_with_output().unwrap()
+	};
+
+	let res = format_stdin("does/not/exist/foo.ts", "let  a=1");
+	assert!(res.status.success(), "cli format command failed");
+	assert_eq!(String::from_utf8(res.stdout).unwrap(), "let a = 1;\n");
+	assert!(!PathBuf::from("does/not/exist/foo.ts").exists());
+
+	let res = format_stdin("foo.json", "{ /* comment */ }");
+	assert_eq!(res.status.code(), Some(2));
+	assert!(res.stdout.is_empty());
+
+	let res = format_stdin("notes.txt", "some notes");
+	assert_eq!(res.status.code(), Some(2));
+}
+
+#[test]
+fn test_format_configuration() {
+	let root = TempDir::new("configuration");
+	root.write(
+		"rome.json",
+		r#"{ "format": { "indentStyle": "space", "indentSize": 4, "lineWidth": 10 }, "files": { "exclude": ["dist"] } }"#,
+	);
+	let source = "{\"a\": [1, 2, 3]}\n";
+	root.write("src/file.json", source);
+	root.write("dist/file.json", source);
+
+	let format = |args: &[&str]| {
+		let src = root.join("src");
+		run_cli(
+			&src,
+			&[&["format"], args, &[root.to_str().unwrap()]].concat(),
+		)
+	};
+
+	let res = format(&["--indent-size", "2"]);
+	assert!(res.status.success(), "cli format command failed");
+	assert_eq!(
+		root.read("src/file.json"),
+		"{\n  \"a\": [\n    1,\n    2,\n    3\n  ]\n}\n",
+		"the flags of the command line override the configuration"
+	);
+	assert_eq!(
+		root.read("dist/file.json"),
+		source,
+		"excluded files aren't formatted"
+	);
+
+	root.write("rome.json", r#"{ "format": { "indentsize": 4 } }"#);
+	let res = format(&[]);
+	assert_eq!(res.status.code(), Some(2));
+	let stderr = String::from_utf8(res.stderr).unwrap();
+	assert!(stderr.contains("unknown field `indentsize`"), "{}", stderr);
+	assert!(stderr.contains("rome.json"), "{}", stderr);
+}
+
+#[test]
+fn test_format_editorconfig() {
+	let root = TempDir::new("editorconfig");
+	root.write(
+		".editorconfig",
+		"root = true\n\n[*]\nindent_style = space\nindent_size = 4\nend_of_line = crlf\ninsert_final_newline = false\n",
+	);
+	root.write("rome.json", r#"{ "format": { "indentSize": 2 } }"#);
+	root.write("index.js", "function f() { return 1 }");
+
+	let res = run_cli(&root, &["format", "index.js"]);
+	assert!(res.status.success(), "cli format command failed");
+	assert_eq!(
+		root.read("index.js"),
+		"function f() {\r\n  return 1;\r\n}",
+		"rome.json takes precedence over .editorconfig"
+	);
+}
+
+#[test]
+fn test_check() {
+	let root = TempDir::new("check");
+	root.write("formatted.js", "let a = 1;\n");
+	root.write("unformatted.json", "{ \"a\":1 }");
+	root.write("notes.txt", "some notes");
+
+	let check = |args: &[&str]| {
+		let res = run_cli(&root, &[&["check"], args].concat());
+		let stderr = String::from_utf8(res.stderr).unwrap();
+		(res.status.code(), stderr)
+	};
+
+	let (code, stderr) = check(&["formatted.js", "notes.txt"]);
+	assert_eq!(code, Some(0), "{}", stderr);
+
+	let (code, stderr) = check(&["."]);
+	assert_eq!(code, Some(1), "{}", stderr);
+	assert!(stderr.contains("unformatted.json"), "{}", stderr);
+	assert!(stderr.contains("The file isn't formatted"), "{}", stderr);
+	assert_eq!(
+		root.read("unformatted.json"),
+		"{ \"a\":1 }",
+		"the files aren't written"
+	);
+
+	root.write("invalid.js", "let a = ;\nlet b = ;\n");
+	let (code, stderr) = check(&["."]);
+	assert_eq!(code, Some(2), "{}", stderr);
+	assert!(stderr.contains("invalid.js"), "{}", stderr);
+
+	let (code, stderr) = check(&["--max-diagnostics", "1", "."]);
+	assert_eq!(code, Some(2), "{}", stderr);
+	assert!(
+		stderr.contains("2 more diagnostics weren't printed"),
+		"{}",
+		stderr
+	);
+	assert!(!stderr.contains("unformatted.json"), "{}", stderr);
+}
+
+#[test]
+fn test_check_watch() {
+	let root = TempDir::new("check_watch");
+	root.write("formatted.js", "let a = 1;\n");
+
+	/// Stops the watcher even if an assertion fails
+	struct Kill(Child);
+	impl Drop for Kill {
+		fn drop(&mut self) {
+			let _ = self.0.kill();
+			let _ = self.0.wait();
+		}
+	}
+	let mut watcher = Kill(
+		Command::new(cargo_bin("cli"))
+			.current_dir(&*root)
+			.args(&["check", "--watch", "."])
+			.stdout(Stdio::null())
+			.stderr(Stdio::piped())
+			.spawn()
+			.expect("fail to run cli check"),
+	);
+
+	// The lines of stderr are read on another thread, to wait for them with a timeout
+	let stderr = BufReader::new(watcher.0.stderr.take().unwrap());
+	let (tx, rx) = mpsc::channel();
+	thread::spawn(move || {
+		for line in stderr.lines().map_while(Result::ok) {
+			if tx.send(line).is_err() {
+				break;
+			}
+		}
+	});
+	let wait_for = |text: &str| loop {
+		match rx.recv_timeout(Duration::from_secs(10)) {
+			Ok(line) if line.contains(text) => break,
+			Ok(_) => {}
+			Err(_) => panic!("the watcher didn't print {:?}", text),
+		}
+	};
+
+	wait_for("Watching for changes");
+	root.write("invalid.js", "let a = ;\n");
+	wait_for("invalid.js");
+}
+
+#[test]
+fn test_parse() {
+	let root = TempDir::new("parse");
+	root.write("valid.js", "let a = 1;\n");
+	root.write("invalid.js", "let a = ;\n");
+
+	let parse = |args: &[&str]| {
+		let res = run_cli(&root, &[&["parse"], args].concat());
+		let stdout = String::from_utf8(res.stdout).unwrap();
+		let stderr = String::from_utf8(res.stderr).unwrap();
+		(res.status.code(), stdout, stderr)
+	};
+
+	let (code, stdout, stderr) = parse(&["valid.js"]);
+	assert_eq!(code, Some(0), "{}", stderr);
+	assert!(stdout.starts_with("JS_ROOT@0..11\n"), "{}", stdout);
+	assert!(stdout.contains("LET_KW@0..3 \"let\""), "{}", stdout);
+
+	let (code, stdout, stderr) = parse(&["--emit", "tokens", "valid.js"]);
+	assert_eq!(code, Some(0), "{}", stderr);
+	// The lexer doesn't know about the contextual keywords
+	assert!(stdout.starts_with("IDENT@0..3 \"let\"\n"), "{}", stdout);
+
+	let (code, stdout, stderr) = parse(&["--emit", "json", "valid.js"]);
+	assert_eq!(code, Some(0), "{}", stderr);
+	assert!(stdout.contains("\"kind\": \"JS_ROOT\""), "{}", stdout);
+
+	let (code, stdout, stderr) = parse(&["--emit", "ast", "invalid.js"]);
+	assert_eq!(code, Some(2), "{}", stderr);
+	assert!(stdout.starts_with("JS_ROOT@0..10\n"), "{}", stdout);
+	assert!(stderr.contains("invalid.js"), "{}", stderr);
+
+	let (code, _, stderr) = parse(&["--emit", "hir", "valid.js"]);
+	assert_eq!(code, Some(2), "{}", stderr);
+}
+
+#[test]
+fn test_format_changed() {
+	let root = TempDir::new("changed");
+	let git = |args: &[&str]| {
+		let res = Command::new("git")
+			.current_dir(&*root)
+			.args(&["-c", "user.name=rome", "-c", "user.email=rome@example.com"])
+			.args(args)
+			.output()
+			.expect("fail to run git");
+		assert!(res.status.success(), "git {:?} failed", args);
+	};
+	let unformatted = "{ \"a\":1 }\n";
+	root.write("committed.json", unformatted);
+	root.write("modified.json", "{\"a\": 1}\n");
+	git(&["init", "--quiet"]);
+	git(&["add", "."]);
+	git(&["commit", "--quiet", "-m", "initial"]);
+	root.write("modified.json", unformatted);
+	root.write("staged.json", unformatted);
+	root.write("untracked.json", unformatted);
+	git(&["add", "staged.json"]);
+
+	let check = |args: &[&str]| {
+		let res = run_cli(&root, &[&["format", "--check"], args].concat());
+		assert_eq!(res.status.code(), Some(1));
+		String::from_utf8(res.stdout).unwrap()
+	};
+
+	assert_eq!(
+		check(&["--changed"]),
+		"modified.json\nstaged.json\nuntracked.json\n"
+	);
+	assert_eq!(check(&["--staged"]), "staged.json\n");
+	assert_eq!(check(&["--changed", "un*.json"]), "untracked.json\n");
+}
+
+#[test]
+fn test_format_cache() {
+	let root = TempDir::new("cache");
+	root.write("formatted.json", "{\"a\": 1}\n");
+
+	let check = |args: &[&str]| run_cli(&root, &[&["format", "--check", "."], args].concat());
+
+	let res = check(&["--no-cache"]);
+	assert!(res.status.success());
+	assert!(!root.join(".rome").exists());
+
+	let res = check(&[]);
+	assert!(res.status.success());
+	assert!(root.join(".rome").join("cache").is_file());
+
+	root.write("formatted.json", "{ \"a\":1 }\n");
+	let res = check(&[]);
+	assert_eq!(
+		res.status.code(),
+		Some(1),
+		"a file whose content changed is checked again"
+	);
+
+	// The module becomes a script, which formats the imports differently
+	root.write("formatted.json", "{\"a\": 1}\n");
+	root.write("package.json", "{\"type\": \"module\"}\n");
+	root.write("index.js", "import a from \"a\";\n");
+	let res = check(&[]);
+	assert!(res.status.success());
+	fs::remove_file(root.join("package.json")).unwrap();
+	let res = check(&[]);
+	assert_eq!(
+		String::from_utf8(res.stdout).unwrap(),
+		"./index.js\n",
+		"a file whose syntax changed is checked again"
+	);
+}
+
+#[test]
+fn test_format_resolves_the_handlers() {
+	let root = TempDir::new("handlers");
+	root.write("bin/run", "#!/usr/bin/env node\nlet   a=1;\n");
+	root.write("bin/build.sh", "echo   1\n");
+	root.write("Makefile", "all:\n\techo   1\n");
+	root.write("tsconfig.json", "{ \"compilerOptions\":{} }\n");
+
+	let res = run_cli(&root, &["format", "--check", "."]);
+
+	assert_eq!(res.status.code(), Some(1));
+	let mut unformatted: Vec<_> = String::from_utf8(res.stdout)
+		.unwrap()
+		.lines()
+		.map(|line| line.replace('\\', "/"))
+		.collect();
+	unformatted.sort();
+	assert_eq!(unformatted, ["./bin/run", "./tsconfig.json"]);
+}
+
+#[test]
+fn test_format_module_packages() {
+	let root = TempDir::new("modules");
+	root.write("package.json", "{\"type\": \"module\"}\n");
+	root.write(
+		"src/index.js",
+		"import a from \"a\";\nlet   b=a\nexport default b;\n",
+	);
+
+	// The package.json above the working directory applies too
+	let res = run_cli(&root.join("src"), &["format", "--verify", "index.js"]);
+
+	assert!(
+		res.status.success(),
+		"{}",
+		String::from_utf8_lossy(&res.stderr)
+	);
+	assert_eq!(
+		root.read("src/index.js"),
+		"import a from \"a\";\nlet b = a;\nexport default b;\n"
+	);
+}
+
+#[test]
+fn test_lsp() {
+	let mut child = Command::new(cargo_bin("cli"))
+		.arg("lsp")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.spawn()
+		.expect("fail to run cli lsp");
+
+	let messages = [
+		r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#,
+		r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+		r#"{"jsonrpc":"2.0","method":"exit"}"#,
+	];
+	let mut stdin = child.stdin.take().unwrap();
+	for message in messages {
+		write!(
+			stdin,
+			"Content-Length: {}\r\n\r\n{}",
+			message.len(),
+			message
+		)
+		.unwrap();
+	}
+	drop(stdin);
+
+	let res = child.wait_with_output().unwrap();
+	assert!(res.status.success(), "the server didn't shut down cleanly");
+
+	let stdout = String::from_utf8(res.stdout).unwrap();
+	assert!(stdout.contains(r#""documentFormattingProvider":true"#));
+	assert!(stdout.contains(r#""id":2,"jsonrpc":"2.0","result":null"#));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_daemon() {
+	let root = TempDir::new("daemon");
+	root.write("file.json", "{ \"a\":1 }\n");
+
+	let rome = |args: &[&str]| run_cli(&root, args);
+
+	/// Stops the daemon even if an assertion fails, before its directory is removed
+	struct Stop<'a>(&'a Path);
+	impl Drop for Stop<'_> {
+		fn drop(&mut self) {
+			let _ = run_cli(self.0, &["stop"]);
+		}
+	}
+
+	let res = rome(&["start"]);
+	let _stop = Stop(&root);
+	assert!(res.status.success());
+	assert!(root.join(".rome").join("daemon.sock").exists());
+	let res = rome(&["start"]);
+	assert!(res.status.success());
+	assert!(String::from_utf8(res.stdout)
+		.unwrap()
+		.contains("already running"));
+
+	let res = rome(&["format", "--no-cache", "--check", "file.json"]);
+	assert_eq!(res.status.code(), Some(1));
+	let res = rome(&["format", "--no-cache", "file.json"]);
+	assert!(res.status.success());
+	assert_eq!(root.read("file.json"), "{\"a\": 1}\n");
+
+	root.write("file.json", "{ /* comment */ }\n");
+	let res = rome(&["format", "--no-cache", "file.json"]);
+	assert_eq!(
+		res.status.code(),
+		Some(2),
+		"the errors are reported like without the daemon"
+	);
+
+	root.write("unformatted.json", "{ \"a\":1 }\n");
+	let res = rome(&["check", "unformatted.json"]);
+	assert_eq!(res.status.code(), Some(1));
+	root.write("invalid.js", "let a = ;\n");
+	let res = rome(&["check", "invalid.js"]);
+	assert_eq!(res.status.code(), Some(2));
+	let stderr = String::from_utf8(res.stderr).unwrap();
+	assert!(
+		stderr.contains("invalid.js"),
+		"the syntax errors are reported like without the daemon: {}",
+		stderr
+	);
+
+	let res = rome(&["stop"]);
+	assert!(res.status.success());
+	assert!(!root.join(".rome").join("daemon.sock").exists());
+	let res = rome(&["stop"]);
+	assert!(res.status.success());
+	assert!(String::from_utf8(res.stdout)
+		.unwrap()
+		.contains("isn't running"));
+}
@@ -0,0 +1,261 @@
+//! A persistent cache of the files known to be formatted or free of lint errors, so that the
+//! commands can skip them when they run again.
+//!
+//! An entry is the hash of the kind of check, the path of the file, its content, the syntax it's
+//! parsed with and the options the check used: changing any of them makes the entry miss. The cache is stored in
+//! `.rome/cache` at the root of the project, and it's discarded when it was written by another
+//! version of Rome.
+use rslint_parser::Syntax;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashSet,
+	fs,
+	hash::{Hash, Hasher},
+	io,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+/// The directory of the cache, relative to the root of the project
+pub const CACHE_DIRECTORY: &str = ".rome";
+const CACHE_FILE: &str = "cache";
+
+/// The number of entries kept when they weren't used by the last run. Past this limit, the
+/// cache keeps only the entries of the files the last run checked
+const MAX_ENTRIES: usize = 200_000;
+
+/// What the cache knows about a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+	/// The file is formatted
+	Formatted,
+	/// The file is formatted, and formatting it was verified to keep its meaning. The entries of
+	/// the runs that don't verify the files don't count for the ones that do
+	Verified,
+	/// The linter didn't find any error in the file
+	LintClean,
+}
+
+/// Identifies a file with a given content, parsed with a given syntax and checked with given
+/// options
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey(u64);
+
+impl CacheKey {
+	/// Creates the key of the file at `path` whose content is `content`. `syntax` is the syntax
+	/// the file is parsed with, as resolved from its package, and `options` are the effective
+	/// options of the check, they're hashed through their JSON serialization.
+	pub fn new(
+		kind: CacheKind,
+		path: &Path,
+		content: &str,
+		syntax: Option<Syntax>,
+		options: &impl Serialize,
+	) -> Self {
+		let mut hasher = StableHasher::default();
+		kind.hash(&mut hasher);
+		path.hash(&mut hasher);
+		content.hash(&mut hasher);
+		syntax.hash(&mut hasher);
+		// The options are plain data, their serialization can't fail
+		serde_json::to_string(options)
+			.expect("cannot serialize the options of the cache key")
+			.hash(&mut hasher);
+		Self(hasher.finish())
+	}
+}
+
+/// The content of the cache file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+	version: String,
+	entries: Vec<u64>,
+}
+
+/// The cache of a project. It can be shared between threads
+#[derive(Debug)]
+pub struct Cache {
+	path: PathBuf,
+	version: String,
+	/// The entries loaded from the cache file
+	previous: HashSet<u64>,
+	/// The entries used or added by this run
+	current: Mutex<HashSet<u64>>,
+}
+
+impl Cache {
+	/// Loads the cache of the project at `root`. The cache is empty when the file doesn't exist,
+	/// can't be read, or was written by a version of Rome other than `version`.
+	pub fn load(root: &Path, version: &str) -> Self {
+		let path = root.join(CACHE_DIRECTORY).join(CACHE_FILE);
+		let previous = fs::read_to_string(&path)
+			.ok()
+			.and_then(|content| serde_json::from_str::<CacheFile>(&content).ok())
+			.filter(|file| file.version == version)
+			.map(|file| file.entries.into_iter().collect())
+			.unwrap_or_default();
+
+		Self {
+			path,
+			version: version.to_string(),
+			previous,
+			current: Mutex::new(HashSet::new()),
+		}
+	}
+
+	/// The file the cache is saved to
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Returns `true` if the file identified by `key` is known to pass the check
+	pub fn contains(&self, key: CacheKey) -> bool {
+		let mut current = self.current.lock().unwrap();
+		if current.contains(&key.0) {
+			return true;
+		}
+		if self.previous.contains(&key.0) {
+			current.insert(key.0);
+			return true;
+		}
+		false
+	}
+
+	/// Records that the file identified by `key` passes the check
+	pub fn insert(&self, key: CacheKey) {
+		self.current.lock().unwrap().insert(key.0);
+	}
+
+	/// Writes the cache file, creating its directory if needed
+	pub fn save(&self) -> io::Result<()> {
+		let current = self.current.lock().unwrap();
+		let mut entries: Vec<u64> = current.iter().copied().collect();
+		let remaining = MAX_ENTRIES.saturating_sub(entries.len());
+		entries.extend(
+			self.previous
+				.iter()
+				.filter(|entry| !current.contains(entry))
+				.take(remaining),
+		);
+
+		let file = CacheFile {
+			version: self.version.clone(),
+			entries,
+		};
+		let content = serde_json::to_string(&file)?;
+
+		if let Some(directory) = self.path.parent() {
+			fs::create_dir_all(directory)?;
+		}
+		fs::write(&self.path, content)
+	}
+}
+
+/// A 64 bits FNV-1a hasher. Unlike the hasher of the standard library, its output doesn't change
+/// between versions of Rust, so the keys stay valid
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+	fn default() -> Self {
+		Self(0xcbf2_9ce4_8422_2325)
+	}
+}
+
+impl Hasher for StableHasher {
+	fn finish(&self) -> u64 {
+		self.0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.0 ^= u64::from(*byte);
+			self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Cache, CacheKey, CacheKind};
+	use crate::configuration::FormatConfiguration;
+	use rslint_parser::{FileKind, Syntax};
+	use std::{fs, path::Path};
+
+	#[test]
+	fn keys_depend_on_the_content_the_syntax_and_the_options() {
+		let path = Path::new("src/index.js");
+		let script = Some(Syntax::default());
+		let options = FormatConfiguration::default();
+		let key = |kind, path, content, syntax, options: &FormatConfiguration| {
+			CacheKey::new(kind, path, content, syntax, options)
+		};
+		let formatted = key(CacheKind::Formatted, path, "let a;", script, &options);
+
+		assert_eq!(
+			formatted,
+			key(CacheKind::Formatted, path, "let a;", script, &options)
+		);
+		assert_ne!(
+			formatted,
+			key(CacheKind::LintClean, path, "let a;", script, &options)
+		);
+		assert_ne!(
+			formatted,
+			key(CacheKind::Formatted, path, "let b;", script, &options)
+		);
+		assert_ne!(
+			formatted,
+			key(
+				CacheKind::Formatted,
+				Path::new("index.js"),
+				"let a;",
+				script,
+				&options
+			)
+		);
+		assert_ne!(
+			formatted,
+			key(
+				CacheKind::Formatted,
+				path,
+				"let a;",
+				Some(Syntax::new(FileKind::Module)),
+				&options
+			),
+			"a script that became a module is checked again"
+		);
+		let wider = FormatConfiguration {
+			line_width: 120,
+			..FormatConfiguration::default()
+		};
+		assert_ne!(
+			formatted,
+			key(CacheKind::Formatted, path, "let a;", script, &wider)
+		);
+	}
+
+	#[test]
+	fn persists_the_entries_of_the_same_version() {
+		let root = std::env::temp_dir().join(format!("rome_core_cache_{}", std::process::id()));
+		let formatted = CacheKey::new(CacheKind::Formatted, Path::new("a.js"), "", None, &());
+		let clean = CacheKey::new(CacheKind::LintClean, Path::new("a.js"), "", None, &());
+
+		let cache = Cache::load(&root, "1.0.0");
+		assert!(!cache.contains(formatted));
+		cache.insert(formatted);
+		assert!(cache.contains(formatted));
+		cache.save().unwrap();
+
+		let cache = Cache::load(&root, "1.0.0");
+		assert!(cache.contains(formatted));
+		assert!(!cache.contains(clean));
+
+		let cache = Cache::load(&root, "1.1.0");
+		assert!(
+			!cache.contains(formatted),
+			"the cache of another version is discarded"
+		);
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+}
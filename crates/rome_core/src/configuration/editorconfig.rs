@@ -0,0 +1,349 @@
+//! The `.editorconfig` files, whose properties are mapped onto the settings of the formatter.
+//!
+//! Like editors do, the properties of a file are the ones of the sections that match it, in the
+//! `.editorconfig` files of its directory and of its ancestors up to the first one with
+//! `root = true`. The nearest files and the last sections win. The supported properties are:
+//!
+//! - `indent_style`: `tab` or `space`
+//! - `indent_size`: a number, or `tab` to use `tab_width`
+//! - `tab_width`: a number, `indent_size` when it isn't set
+//! - `max_line_length`: a number, `off` is ignored
+//! - `end_of_line`: `lf`, `crlf` or `cr`
+//! - `insert_final_newline`: `true` or `false`
+//!
+//! The other properties, and the values that aren't supported, are ignored. `unset` removes a
+//! property set by a previous section.
+use super::{FormatOverride, IndentStyle};
+use crate::fs::FileSystem;
+use globset::{GlobBuilder, GlobMatcher};
+use std::{
+	collections::HashMap,
+	fmt,
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::{Arc, RwLock},
+};
+
+const EDITORCONFIG: &str = ".editorconfig";
+
+/// The properties of a file, by their lowercase name
+type Properties = HashMap<String, String>;
+
+/// A parsed `.editorconfig` file
+#[derive(Debug, Default)]
+pub struct EditorConfig {
+	/// `root = true`: the files in the parent directories don't apply
+	root: bool,
+	sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+struct Section {
+	/// The files of the section, [None] if its glob isn't valid
+	glob: Option<GlobMatcher>,
+	properties: Vec<(String, String)>,
+}
+
+impl Section {
+	fn new(name: &str) -> Self {
+		// Like in `rome.json`, a glob without separator matches at any depth
+		let glob = match name.strip_prefix('/') {
+			Some(anchored) => anchored.to_string(),
+			None if name.contains('/') => name.to_string(),
+			None => format!("**/{}", name),
+		};
+
+		let glob = GlobBuilder::new(&glob)
+			.literal_separator(true)
+			.build()
+			.ok()
+			.map(|glob| glob.compile_matcher());
+
+		Self {
+			glob,
+			properties: Vec::new(),
+		}
+	}
+}
+
+impl EditorConfig {
+	/// Parses the content of a `.editorconfig` file. The lines that can't be parsed are ignored
+	pub fn parse(source: &str) -> Self {
+		let mut config = EditorConfig::default();
+
+		for line in source.lines() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+				continue;
+			}
+
+			if let Some(name) = line.strip_prefix('[') {
+				if let Some(end) = name.rfind(']') {
+					config.sections.push(Section::new(&name[..end]));
+				}
+				continue;
+			}
+
+			let (key, value) = match line.split_once('=') {
+				Some((key, value)) => (key.trim().to_lowercase(), value.trim().to_lowercase()),
+				None => continue,
+			};
+
+			match config.sections.last_mut() {
+				Some(section) => section.properties.push((key, value)),
+				// The preamble can only tell whether the file is the root one
+				None if key == "root" => config.root = value == "true",
+				None => {}
+			}
+		}
+
+		config
+	}
+
+	/// Whether the `.editorconfig` files of the parent directories apply too
+	pub fn is_root(&self) -> bool {
+		self.root
+	}
+
+	/// Sets the properties of the sections that match `path`, relative to the directory of the
+	/// file
+	fn apply_to(&self, path: &Path, properties: &mut Properties) {
+		let sections = self.sections.iter().filter(|section| {
+			section
+				.glob
+				.as_ref()
+				.map_or(false, |glob| glob.is_match(path))
+		});
+
+		for section in sections {
+			for (key, value) in &section.properties {
+				if value == "unset" {
+					properties.remove(key);
+				} else {
+					properties.insert(key.clone(), value.clone());
+				}
+			}
+		}
+	}
+
+	/// The settings of the formatter for the file at `path`, relative to the directory of the
+	/// `.editorconfig` file. It doesn't take the other files into account, see [EditorConfigs]
+	pub fn format_override(&self, path: &Path) -> FormatOverride {
+		let mut properties = Properties::new();
+		self.apply_to(path, &mut properties);
+		format_override(&properties)
+	}
+}
+
+/// Maps the properties of a file onto the settings of the formatter
+fn format_override(properties: &Properties) -> FormatOverride {
+	let property = |key: &str| properties.get(key).map(String::as_str);
+
+	let indent_style = match property("indent_style") {
+		Some("tab") => Some(IndentStyle::Tab),
+		Some("space") => Some(IndentStyle::Space),
+		_ => None,
+	};
+
+	let tab_width = number(properties, "tab_width").or_else(|| number(properties, "indent_size"));
+	let indent_size = match property("indent_size") {
+		Some("tab") => tab_width,
+		_ => number(properties, "indent_size"),
+	};
+
+	let line_ending = property("end_of_line").and_then(|value| value.parse().ok());
+	let final_newline = match property("insert_final_newline") {
+		Some("true") => Some(true),
+		Some("false") => Some(false),
+		_ => None,
+	};
+
+	FormatOverride {
+		indent_style,
+		indent_size,
+		line_width: number(properties, "max_line_length"),
+		tab_width,
+		line_ending,
+		final_newline,
+		..FormatOverride::default()
+	}
+}
+
+/// The numeric value of a property, [None] if it isn't a number, like `tab` or `off`
+fn number<T: FromStr>(properties: &Properties, key: &str) -> Option<T> {
+	properties.get(key).and_then(|value| value.parse().ok())
+}
+
+/// The `.editorconfig` files of a project, read when the settings of a file are resolved and
+/// cached by directory.
+///
+/// Like [PackageTypes](crate::package::PackageTypes), the cache isn't invalidated.
+#[derive(Default)]
+pub struct EditorConfigs {
+	/// The file system the files are read from. There's none in the default settings, which
+	/// don't read any file
+	fs: Option<Box<dyn FileSystem>>,
+	/// The directory the relative paths are resolved from
+	working_directory: PathBuf,
+	/// The file of each directory, [None] if there's no file
+	directories: RwLock<HashMap<PathBuf, Option<Arc<EditorConfig>>>>,
+}
+
+impl EditorConfigs {
+	/// Reads the files from `fs`. Relative paths are resolved from `working_directory`
+	pub fn new(fs: Box<dyn FileSystem>, working_directory: &Path) -> Self {
+		Self {
+			fs: Some(fs),
+			working_directory: working_directory.to_path_buf(),
+			directories: RwLock::default(),
+		}
+	}
+
+	/// The settings of the formatter for the file at `path`, according to the `.editorconfig`
+	/// files of its directory and its ancestors
+	pub fn format_override(&self, path: &Path) -> FormatOverride {
+		let fs = match &self.fs {
+			Some(fs) => fs.as_ref(),
+			None => return FormatOverride::default(),
+		};
+		let path = self.working_directory.join(path);
+
+		let mut configs = Vec::new();
+		for directory in path.ancestors().skip(1) {
+			if let Some(config) = self.load(fs, directory) {
+				let root = config.is_root();
+				configs.push((directory, config));
+				if root {
+					break;
+				}
+			}
+		}
+
+		// The nearest files are applied last, so that they win
+		let mut properties = Properties::new();
+		for (directory, config) in configs.iter().rev() {
+			if let Ok(relative) = path.strip_prefix(directory) {
+				config.apply_to(relative, &mut properties);
+			}
+		}
+		format_override(&properties)
+	}
+
+	/// Returns the `.editorconfig` file of `directory`, reading it if it isn't cached yet
+	fn load(&self, fs: &dyn FileSystem, directory: &Path) -> Option<Arc<EditorConfig>> {
+		if let Some(cached) = self.directories.read().unwrap().get(directory) {
+			return cached.clone();
+		}
+
+		let path = directory.join(EDITORCONFIG);
+		let config = if fs.is_file(&path) {
+			// A file that can't be read doesn't apply, as editors would ignore it too
+			fs.read(&path)
+				.ok()
+				.map(|source| Arc::new(EditorConfig::parse(&source)))
+		} else {
+			None
+		};
+
+		self.directories
+			.write()
+			.unwrap()
+			.insert(directory.to_path_buf(), config.clone());
+		config
+	}
+}
+
+impl fmt::Debug for EditorConfigs {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("EditorConfigs")
+			.field("working_directory", &self.working_directory)
+			.field("directories", &self.directories)
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{EditorConfig, EditorConfigs};
+	use crate::configuration::{IndentStyle, LineEnding};
+	use crate::fs::MemoryFileSystem;
+	use std::path::Path;
+
+	#[test]
+	fn maps_the_properties_of_the_matching_sections() {
+		let config = EditorConfig::parse(
+			"root = true\n\
+			\n\
+			[*]\n\
+			indent_style = space\n\
+			indent_size = 4\n\
+			end_of_line = CRLF\n\
+			insert_final_newline = false\n\
+			max_line_length = off\n\
+			\n\
+			# Makefiles need tabs\n\
+			[{*.json,Makefile}]\n\
+			indent_style = tab\n\
+			indent_size = tab\n\
+			tab_width = 8\n\
+			\n\
+			[src/legacy/*.js]\n\
+			max_line_length = 120\n\
+			end_of_line = unset\n",
+		);
+		assert!(config.is_root());
+
+		let settings = config.format_override(Path::new("src/index.js"));
+		assert_eq!(settings.indent_style, Some(IndentStyle::Space));
+		assert_eq!(settings.indent_size, Some(4));
+		assert_eq!(settings.tab_width, Some(4));
+		assert_eq!(settings.line_ending, Some(LineEnding::Crlf));
+		assert_eq!(settings.final_newline, Some(false));
+		assert_eq!(settings.line_width, None);
+
+		let settings = config.format_override(Path::new("data/file.json"));
+		assert_eq!(settings.indent_style, Some(IndentStyle::Tab));
+		assert_eq!(settings.indent_size, Some(8));
+		assert_eq!(settings.tab_width, Some(8));
+
+		let settings = config.format_override(Path::new("src/legacy/index.js"));
+		assert_eq!(settings.line_width, Some(120));
+		assert_eq!(settings.line_ending, None);
+
+		let settings = config.format_override(Path::new("lib/src/legacy/index.js"));
+		assert_eq!(
+			settings.line_width, None,
+			"the glob is anchored to the directory"
+		);
+	}
+
+	#[test]
+	fn merges_the_files_up_to_the_root_one() {
+		let fs = MemoryFileSystem::default();
+		fs.insert("/.editorconfig", "[*]\nmax_line_length = 60\n");
+		fs.insert(
+			"/project/.editorconfig",
+			"root = true\n[*]\nindent_style = space\nindent_size = 2\n",
+		);
+		fs.insert(
+			"/project/packages/a/.editorconfig",
+			"[*.js]\nindent_size = 4\n",
+		);
+
+		let configs = EditorConfigs::new(Box::new(fs), Path::new("/project/packages"));
+
+		let settings = configs.format_override(Path::new("a/src/index.js"));
+		assert_eq!(settings.indent_style, Some(IndentStyle::Space));
+		assert_eq!(settings.indent_size, Some(4));
+		assert_eq!(
+			settings.line_width, None,
+			"the files above the root one don't apply"
+		);
+
+		let settings = configs.format_override(Path::new("/project/index.js"));
+		assert_eq!(settings.indent_size, Some(2));
+
+		let settings = configs.format_override(Path::new("/elsewhere/index.js"));
+		assert_eq!(settings.line_width, Some(60));
+	}
+}
@@ -0,0 +1,210 @@
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{de, Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+
+/// A glob of the configuration, matched against paths relative to the configuration file.
+///
+/// `*` doesn't match the path separator, `**` matches any number of directories. Like in a
+/// `.gitignore` file, a glob without separator matches at any depth: `*.json` is `**/*.json`.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+	glob: String,
+	compiled: Glob,
+}
+
+impl Pattern {
+	pub fn new(glob: &str) -> Result<Self, globset::Error> {
+		let anchored = if glob.contains('/') {
+			glob.to_string()
+		} else {
+			format!("**/{}", glob)
+		};
+
+		let compiled = GlobBuilder::new(&anchored)
+			.literal_separator(true)
+			.build()?;
+
+		Ok(Self {
+			glob: glob.to_string(),
+			compiled,
+		})
+	}
+
+	/// The glob as written in the configuration
+	pub fn glob(&self) -> &str {
+		&self.glob
+	}
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let glob = String::deserialize(deserializer)?;
+		Pattern::new(&glob).map_err(de::Error::custom)
+	}
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesConfiguration {
+	/// When it isn't empty, only the files matched by one of these patterns belong to the project.
+	/// A pattern that matches a directory includes all its files.
+	pub include: Vec<Pattern>,
+	/// The files matched by these patterns, or inside a directory they match, don't belong to
+	/// the project
+	pub exclude: Vec<Pattern>,
+}
+
+/// A set of patterns, that matches a path if it matches a path or one of the directories that
+/// contain it
+#[derive(Debug, Clone)]
+pub(crate) struct PatternSet(GlobSet);
+
+impl PatternSet {
+	pub(crate) fn new(patterns: &[Pattern]) -> Self {
+		let mut builder = GlobSetBuilder::new();
+		for pattern in patterns {
+			builder.add(pattern.compiled.clone());
+		}
+		// The patterns have been validated already
+		Self(
+			builder
+				.build()
+				.expect("Could not build the set of patterns"),
+		)
+	}
+
+	/// Returns `true` if one of the patterns matches `path`, relative to the root of the project,
+	/// or one of its ancestors
+	pub(crate) fn matches(&self, path: &Path) -> bool {
+		path.ancestors()
+			.filter(|ancestor| !ancestor.as_os_str().is_empty())
+			.any(|ancestor| self.0.is_match(ancestor))
+	}
+}
+
+impl Default for PatternSet {
+	fn default() -> Self {
+		Self(GlobSet::empty())
+	}
+}
+
+/// Decides which files belong to a project, according to its [FilesConfiguration]
+#[derive(Debug, Default, Clone)]
+pub struct FilesMatcher {
+	/// The directory the patterns are relative to, [None] when there's no configuration
+	root: Option<PathBuf>,
+	/// The directory relative paths are resolved from
+	working_directory: PathBuf,
+	include: Option<PatternSet>,
+	exclude: PatternSet,
+}
+
+impl FilesMatcher {
+	/// Creates a matcher for the patterns of `files`, relative to `root`. Relative paths are
+	/// resolved from `working_directory`.
+	pub fn new(root: &Path, working_directory: &Path, files: &FilesConfiguration) -> Self {
+		let include = if files.include.is_empty() {
+			None
+		} else {
+			Some(PatternSet::new(&files.include))
+		};
+
+		Self {
+			root: Some(root.to_path_buf()),
+			working_directory: working_directory.to_path_buf(),
+			include,
+			exclude: PatternSet::new(&files.exclude),
+		}
+	}
+
+	/// Returns `path` relative to the root of the project, or [None] if it's outside of the
+	/// project or there's no configuration
+	pub fn relative_path(&self, path: &Path) -> Option<PathBuf> {
+		let root = self.root.as_ref()?;
+		let path = self.working_directory.join(path);
+		path.strip_prefix(root).ok().map(Path::to_path_buf)
+	}
+
+	/// Returns `true` if the file at `path` belongs to the project. Files outside of the root of
+	/// the project always do, the configuration doesn't apply to them.
+	pub fn is_included(&self, path: &Path) -> bool {
+		let relative = match self.relative_path(path) {
+			Some(relative) => relative,
+			None => return true,
+		};
+
+		match &self.include {
+			Some(include) if !include.matches(&relative) => false,
+			_ => !self.exclude.matches(&relative),
+		}
+	}
+
+	/// Returns `true` if `path` is matched by one of the `exclude` patterns. Unlike
+	/// [FilesMatcher::is_included], it ignores `include`, so it can tell whether a directory is
+	/// worth walking.
+	pub fn is_excluded(&self, path: &Path) -> bool {
+		self.relative_path(path)
+			.map_or(false, |relative| self.exclude.matches(&relative))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{FilesConfiguration, FilesMatcher, Pattern};
+	use std::path::Path;
+
+	fn files(include: &[&str], exclude: &[&str]) -> FilesConfiguration {
+		let patterns = |globs: &[&str]| {
+			globs
+				.iter()
+				.map(|glob| Pattern::new(glob).unwrap())
+				.collect()
+		};
+
+		FilesConfiguration {
+			include: patterns(include),
+			exclude: patterns(exclude),
+		}
+	}
+
+	#[test]
+	fn matches_paths_relative_to_the_root() {
+		let matcher = FilesMatcher::new(
+			Path::new("/project"),
+			Path::new("/project/packages"),
+			&files(&["packages"], &["dist", "packages/*/legacy.js"]),
+		);
+
+		assert!(!matcher.is_included(Path::new("/project/src/index.js")));
+		assert!(matcher.is_included(Path::new("a/index.js")));
+		assert!(!matcher.is_included(Path::new("a/legacy.js")));
+		assert!(!matcher.is_included(Path::new("a/dist/index.js")));
+		assert!(!matcher.is_included(Path::new("a/b/dist")));
+		assert!(matcher.is_excluded(Path::new("a/b/dist")));
+		assert!(!matcher.is_excluded(Path::new("/project/src")));
+		assert!(
+			matcher.is_included(Path::new("/elsewhere/dist/index.js")),
+			"the configuration doesn't apply outside of the project"
+		);
+	}
+
+	#[test]
+	fn globs_without_separator_match_at_any_depth() {
+		let matcher = FilesMatcher::new(
+			Path::new("/project"),
+			Path::new("/project"),
+			&files(&["src/*.js", "*.json"], &[]),
+		);
+
+		assert!(matcher.is_included(Path::new("src/index.js")));
+		assert!(!matcher.is_included(Path::new("src/nested/index.js")));
+		assert!(matcher.is_included(Path::new("package.json")));
+		assert!(matcher.is_included(Path::new("src/nested/data.json")));
+	}
+
+	#[test]
+	fn default_includes_everything() {
+		let matcher = FilesMatcher::default();
+		assert!(matcher.is_included(Path::new("dist/index.js")));
+	}
+}
@@ -0,0 +1,537 @@
+//! The configuration of a project, read from a `rome.json` file.
+//!
+//! The file is searched from the working directory up to the root of the file system, the first
+//! one found is used. All the properties are optional:
+//!
+//! ```json
+//! {
+//!     "format": {
+//!         "enabled": true,
+//!         "indentStyle": "space",
+//!         "indentSize": 4,
+//!         "lineWidth": 100,
+//!         "lineEnding": "crlf",
+//!         "json": { "sortKeys": true, "objectWrap": "collapse" }
+//!     },
+//!     "lint": { "enabled": false },
+//!     "files": { "include": ["src"], "exclude": ["*.min.js"] },
+//!     "overrides": [
+//!         { "include": ["*.test.ts"], "format": { "lineWidth": 120 } },
+//!         { "include": ["legacy"], "format": { "enabled": false } }
+//!     ]
+//! }
+//! ```
+//!
+//! The settings that apply to a file, once the overrides that match it are applied, are resolved
+//! by [ProjectSettings]. The formatter also follows the `.editorconfig` files of the project, for
+//! the settings that `rome.json` doesn't set, see [EditorConfigs].
+mod editorconfig;
+mod files;
+mod overrides;
+mod settings;
+
+pub use editorconfig::{EditorConfig, EditorConfigs};
+pub use files::{FilesConfiguration, FilesMatcher, Pattern};
+pub use overrides::{FormatOverride, JsonOverride, LintOverride, Override};
+pub use settings::ProjectSettings;
+
+use crate::fs::{FileSystem, FileSystemError};
+use rslint_errors::Diagnostic;
+use serde::{Deserialize, Serialize};
+use serde_json::error::Category;
+use std::{
+	io::ErrorKind,
+	ops::Range,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+/// The name of the configuration file of a project
+pub const CONFIGURATION_FILE: &str = "rome.json";
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Configuration {
+	/// The settings of the formatter
+	pub format: FormatConfiguration,
+	/// The settings of the linter
+	pub lint: LintConfiguration,
+	/// The files that belong to the project
+	pub files: FilesConfiguration,
+	/// Settings that only apply to some of the files
+	pub overrides: Vec<Override>,
+	/// The settings of the formatter that are written in the configuration file, which take
+	/// precedence over the ones of the `.editorconfig` files
+	#[serde(skip)]
+	pub(crate) explicit_format: FormatOverride,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct FormatConfiguration {
+	/// Whether Rome formats the files of the project
+	pub enabled: bool,
+	/// The style of indentation
+	pub indent_style: IndentStyle,
+	/// The number of spaces of an indentation level, when indenting with spaces
+	pub indent_size: u8,
+	/// The width after which the formatter tries to break lines
+	pub line_width: u16,
+	/// The width of a tab character, to measure the width of the lines indented with tabs
+	pub tab_width: u8,
+	/// The line breaks of the formatted files
+	pub line_ending: LineEnding,
+	/// Whether the formatted files end with a line break
+	pub final_newline: bool,
+	/// Options that only apply to JSON files
+	pub json: JsonConfiguration,
+}
+
+impl Default for FormatConfiguration {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			indent_style: IndentStyle::default(),
+			indent_size: 2,
+			line_width: 80,
+			tab_width: 2,
+			line_ending: LineEnding::default(),
+			final_newline: true,
+			json: JsonConfiguration::default(),
+		}
+	}
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct JsonConfiguration {
+	/// Sort the keys of objects, `package.json` files use the conventional order of their fields
+	pub sort_keys: bool,
+	/// Whether objects and arrays written over multiple lines stay expanded
+	pub object_wrap: ObjectWrap,
+}
+
+/// The indentation of the formatted code, the number of spaces is given by
+/// [FormatConfiguration::indent_size]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+	Tab,
+	Space,
+}
+
+impl Default for IndentStyle {
+	fn default() -> Self {
+		Self::Tab
+	}
+}
+
+impl FromStr for IndentStyle {
+	type Err = &'static str;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"tab" => Ok(Self::Tab),
+			"space" => Ok(Self::Space),
+			_ => Err("Value not supported for IndentStyle"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+	/// Line Feed only (`\n`), common on Linux and macOS as well as inside git repos
+	Lf,
+	/// Carriage Return + Line Feed characters (`\r\n`), common on Windows
+	Crlf,
+	/// Carriage Return character only (`\r`), used very rarely
+	Cr,
+}
+
+impl LineEnding {
+	#[inline]
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			LineEnding::Lf => "\n",
+			LineEnding::Crlf => "\r\n",
+			LineEnding::Cr => "\r",
+		}
+	}
+}
+
+impl Default for LineEnding {
+	fn default() -> Self {
+		Self::Lf
+	}
+}
+
+impl FromStr for LineEnding {
+	type Err = &'static str;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"lf" => Ok(Self::Lf),
+			"crlf" => Ok(Self::Crlf),
+			"cr" => Ok(Self::Cr),
+			_ => Err("Value not supported for LineEnding"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectWrap {
+	/// Objects and arrays that fit on a single line are only collapsed if they have been
+	/// written on a single line, meaning there's no line break between the opening bracket
+	/// and the first element
+	Preserve,
+	/// Objects and arrays are collapsed whenever they fit on a single line
+	Collapse,
+}
+
+impl Default for ObjectWrap {
+	fn default() -> Self {
+		Self::Preserve
+	}
+}
+
+impl FromStr for ObjectWrap {
+	type Err = &'static str;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"preserve" => Ok(Self::Preserve),
+			"collapse" => Ok(Self::Collapse),
+			_ => Err("Value not supported for ObjectWrap"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfiguration {
+	/// Whether Rome lints the files of the project
+	pub enabled: bool,
+}
+
+impl Default for LintConfiguration {
+	fn default() -> Self {
+		Self { enabled: true }
+	}
+}
+
+/// A configuration file and its settings
+#[derive(Debug, Clone)]
+pub struct ProjectConfiguration {
+	/// The path of the `rome.json` file
+	pub path: PathBuf,
+	pub configuration: Configuration,
+}
+
+impl ProjectConfiguration {
+	/// The directory of the configuration file. The paths of the configuration are relative to it
+	pub fn root(&self) -> &Path {
+		self.path.parent().unwrap_or_else(|| Path::new(""))
+	}
+}
+
+#[derive(Debug)]
+pub enum ConfigurationError {
+	/// The configuration file couldn't be read
+	Io {
+		path: PathBuf,
+		error: std::io::Error,
+	},
+	/// The configuration file isn't valid. The diagnostics point into `source`
+	Invalid {
+		path: PathBuf,
+		source: String,
+		diagnostics: Vec<Diagnostic>,
+	},
+}
+
+/// Returns the path of the first `rome.json` file of `fs` found in `directory` or one of its
+/// ancestors
+pub fn find_configuration(fs: &dyn FileSystem, directory: &Path) -> Option<PathBuf> {
+	directory
+		.ancestors()
+		.map(|directory| directory.join(CONFIGURATION_FILE))
+		.find(|path| fs.is_file(path))
+}
+
+/// Loads from `fs` the configuration of the project that contains `working_directory`.
+///
+/// Returns [None] if there's no configuration file, in which case the default settings apply.
+pub fn load_configuration(
+	fs: &dyn FileSystem,
+	working_directory: &Path,
+) -> Result<Option<ProjectConfiguration>, ConfigurationError> {
+	let path = match find_configuration(fs, working_directory) {
+		Some(path) => path,
+		None => return Ok(None),
+	};
+
+	let source = match fs.read(&path) {
+		Ok(source) => source,
+		// The file could have been removed since it was found
+		Err(FileSystemError { error, .. }) if error.kind() == ErrorKind::NotFound => {
+			return Ok(None)
+		}
+		Err(FileSystemError { error, .. }) => return Err(ConfigurationError::Io { path, error }),
+	};
+
+	match parse_configuration(&source) {
+		Ok(configuration) => Ok(Some(ProjectConfiguration {
+			path,
+			configuration,
+		})),
+		Err(diagnostics) => Err(ConfigurationError::Invalid {
+			path,
+			source,
+			diagnostics,
+		}),
+	}
+}
+
+/// The settings of a configuration file that are written in it, rather than defaulted
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ExplicitSettings {
+	format: FormatOverride,
+}
+
+/// Parses the content of a configuration file.
+///
+/// Syntax errors, unknown keys and values of the wrong type are returned as diagnostics that point
+/// at the offending part of `source`.
+pub fn parse_configuration(source: &str) -> Result<Configuration, Vec<Diagnostic>> {
+	let mut configuration: Configuration = serde_json::from_str(source).map_err(|error| {
+		let offset = error_offset(source, error.line(), error.column());
+		let range = error_range(source, offset, error.classify());
+
+		// The message ends with the position of the error, the diagnostic shows it already
+		let message = error.to_string();
+		let position = format!(" at line {} column {}", error.line(), error.column());
+		let title = message.strip_suffix(&position).unwrap_or(&message);
+
+		vec![Diagnostic::error(0, "InvalidConfiguration", title).primary(range, "")]
+	})?;
+
+	// The source is valid, so are the settings of the formatter it sets
+	let explicit: ExplicitSettings = serde_json::from_str(source).unwrap_or_default();
+	configuration.explicit_format = explicit.format;
+	Ok(configuration)
+}
+
+/// Converts the one-based line and column reported by serde to an offset in `source`
+fn error_offset(source: &str, line: usize, column: usize) -> usize {
+	let line_start: usize = source
+		.split_inclusive('\n')
+		.take(line.saturating_sub(1))
+		.map(str::len)
+		.sum();
+
+	let mut offset = (line_start + column).min(source.len());
+	while !source.is_char_boundary(offset) {
+		offset -= 1;
+	}
+	offset
+}
+
+/// Returns the range of `source` to highlight for an error that serde reports at `offset`
+fn error_range(source: &str, offset: usize, category: Category) -> Range<usize> {
+	let before = &source[..offset];
+	// Syntax errors are reported right after the unexpected character
+	let previous_char = before
+		.chars()
+		.next_back()
+		.map_or(offset, |c| offset - c.len_utf8());
+
+	let start = match category {
+		Category::Syntax | Category::Eof | Category::Io => previous_char,
+		// Data errors are reported at the end of the value or key that caused them
+		Category::Data if before.ends_with('"') => string_start(source, offset - 1),
+		// Errors raised while deserializing the elements of an array or object are reported at the
+		// end of the array or object
+		Category::Data if before.ends_with(&[']', '}'][..]) => {
+			container_start(source, offset - 1).unwrap_or(previous_char)
+		}
+		Category::Data => {
+			let start = before
+				.trim_end_matches(|c: char| {
+					c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')
+				})
+				.len();
+			if start == offset {
+				previous_char
+			} else {
+				start
+			}
+		}
+	};
+
+	start..offset
+}
+
+/// Returns the position of the opening quote of the string that ends with the quote at `end`
+fn string_start(source: &str, end: usize) -> usize {
+	let mut start = end;
+	while let Some(quote) = source[..start].rfind('"') {
+		start = quote;
+		if !source[..quote].ends_with('\\') {
+			break;
+		}
+	}
+	start
+}
+
+/// Returns the position of the bracket that opens the array or object closed at `end`
+fn container_start(source: &str, end: usize) -> Option<usize> {
+	let mut openings = Vec::new();
+	let mut in_string = false;
+	let mut escaped = false;
+
+	for (position, c) in source[..end].char_indices() {
+		match c {
+			_ if escaped => escaped = false,
+			'\\' if in_string => escaped = true,
+			'"' => in_string = !in_string,
+			'[' | '{' if !in_string => openings.push(position),
+			']' | '}' if !in_string => {
+				openings.pop();
+			}
+			_ => {}
+		}
+	}
+
+	openings.pop()
+}
+
+#[cfg(test)]
+mod test {
+	use super::{
+		find_configuration, load_configuration, parse_configuration, ConfigurationError,
+		IndentStyle, ObjectWrap,
+	};
+	use crate::fs::MemoryFileSystem;
+	use std::path::Path;
+
+	fn parse_error(source: &str) -> (String, &str) {
+		let diagnostics = parse_configuration(source).unwrap_err();
+		assert_eq!(diagnostics.len(), 1);
+		let diagnostic = &diagnostics[0];
+		let range = diagnostic.primary.as_ref().unwrap().span.range.clone();
+		(diagnostic.title.clone(), &source[range])
+	}
+
+	#[test]
+	fn parses_the_settings() {
+		let configuration = parse_configuration(
+			r#"{
+				"format": {
+					"indentStyle": "space",
+					"indentSize": 4,
+					"json": { "objectWrap": "collapse" }
+				},
+				"lint": { "enabled": false },
+				"files": { "exclude": ["dist"] }
+			}"#,
+		)
+		.unwrap();
+
+		assert!(configuration.format.enabled);
+		assert_eq!(configuration.format.indent_style, IndentStyle::Space);
+		assert_eq!(configuration.format.indent_size, 4);
+		assert_eq!(configuration.format.line_width, 80);
+		assert_eq!(configuration.format.json.object_wrap, ObjectWrap::Collapse);
+		assert!(!configuration.format.json.sort_keys);
+		assert!(!configuration.lint.enabled);
+		assert_eq!(configuration.files.exclude.len(), 1);
+	}
+
+	#[test]
+	fn empty_configuration() {
+		let configuration = parse_configuration("{}").unwrap();
+		assert!(configuration.format.enabled);
+		assert_eq!(configuration.format.json.object_wrap, ObjectWrap::Preserve);
+		assert!(configuration.lint.enabled);
+	}
+
+	#[test]
+	fn reports_unknown_keys() {
+		let (title, span) = parse_error("{\n\t\"format\": { \"indentsize\": 4 }\n}");
+		assert!(title.starts_with("unknown field `indentsize`, expected one of"));
+		assert_eq!(span, "\"indentsize\"");
+	}
+
+	#[test]
+	fn reports_wrong_types() {
+		let (title, span) = parse_error(r#"{ "format": { "indentSize": "four" } }"#);
+		assert_eq!(title, "invalid type: string \"four\", expected u8");
+		assert_eq!(span, "\"four\"");
+
+		let (title, span) = parse_error(r#"{ "format": { "enabled": 1 } }"#);
+		assert_eq!(title, "invalid type: integer `1`, expected a boolean");
+		assert_eq!(span, "1");
+
+		let (title, span) = parse_error(r#"{ "format": { "indentStyle": "tabs" } }"#);
+		assert_eq!(title, "unknown variant `tabs`, expected `tab` or `space`");
+		assert_eq!(span, "\"tabs\"");
+	}
+
+	#[test]
+	fn reports_invalid_globs() {
+		let (title, span) = parse_error(r#"{ "files": { "include": ["src", "src/[a"] } }"#);
+		assert!(title.contains("unclosed character class"), "{}", title);
+		assert_eq!(span, r#"["src", "src/[a"]"#);
+	}
+
+	#[test]
+	fn reports_syntax_errors() {
+		let (title, span) = parse_error("{ \"lint\": {} \"format\": {} }");
+		assert_eq!(title, "expected `,` or `}`");
+		assert_eq!(span, "\"");
+	}
+
+	#[test]
+	fn finds_the_configuration_in_ancestors() {
+		let fs = MemoryFileSystem::default();
+		let root = Path::new("/project");
+		let nested = root.join("packages").join("a");
+
+		assert_eq!(find_configuration(&fs, &nested), None);
+
+		fs.insert(root.join("rome.json"), "{}");
+		assert_eq!(
+			find_configuration(&fs, &nested),
+			Some(root.join("rome.json"))
+		);
+	}
+
+	#[test]
+	fn loads_the_configuration_of_the_file_system() {
+		let fs = MemoryFileSystem::default();
+		let working_directory = Path::new("/project/src");
+		assert!(load_configuration(&fs, working_directory)
+			.unwrap()
+			.is_none());
+
+		fs.insert(
+			"/project/rome.json",
+			r#"{ "format": { "lineWidth": 120 } }"#,
+		);
+		let project = load_configuration(&fs, working_directory).unwrap().unwrap();
+		assert_eq!(project.root(), Path::new("/project"));
+		assert_eq!(project.configuration.format.line_width, 120);
+
+		fs.insert(
+			"/project/rome.json",
+			r#"{ "format": { "lineWidth": "wide" } }"#,
+		);
+		assert!(matches!(
+			load_configuration(&fs, working_directory),
+			Err(ConfigurationError::Invalid { .. })
+		));
+	}
+}
@@ -0,0 +1,132 @@
+use super::{
+	files::PatternSet, FormatConfiguration, IndentStyle, LineEnding, LintConfiguration, ObjectWrap,
+	Pattern,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Settings that replace the ones of the project for the files matched by `include`.
+///
+/// When several overrides match a file, they're applied in order, so the last one wins.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Override {
+	/// The files the override applies to. A pattern that matches a directory applies to all its files
+	pub include: Vec<Pattern>,
+	pub format: FormatOverride,
+	pub lint: LintOverride,
+}
+
+/// The settings of the formatter to replace, the ones that are [None] are left as they are
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct FormatOverride {
+	pub enabled: Option<bool>,
+	pub indent_style: Option<IndentStyle>,
+	pub indent_size: Option<u8>,
+	pub line_width: Option<u16>,
+	pub tab_width: Option<u8>,
+	pub line_ending: Option<LineEnding>,
+	pub final_newline: Option<bool>,
+	pub json: JsonOverride,
+}
+
+impl FormatOverride {
+	pub fn apply_to(&self, configuration: &mut FormatConfiguration) {
+		if let Some(enabled) = self.enabled {
+			configuration.enabled = enabled;
+		}
+		if let Some(indent_style) = self.indent_style {
+			configuration.indent_style = indent_style;
+		}
+		if let Some(indent_size) = self.indent_size {
+			configuration.indent_size = indent_size;
+		}
+		if let Some(line_width) = self.line_width {
+			configuration.line_width = line_width;
+		}
+		if let Some(tab_width) = self.tab_width {
+			configuration.tab_width = tab_width;
+		}
+		if let Some(line_ending) = self.line_ending {
+			configuration.line_ending = line_ending;
+		}
+		if let Some(final_newline) = self.final_newline {
+			configuration.final_newline = final_newline;
+		}
+		if let Some(sort_keys) = self.json.sort_keys {
+			configuration.json.sort_keys = sort_keys;
+		}
+		if let Some(object_wrap) = self.json.object_wrap {
+			configuration.json.object_wrap = object_wrap;
+		}
+	}
+
+	/// Leaves out the settings that `other` replaces
+	pub(crate) fn unless_set_by(self, other: &FormatOverride) -> Self {
+		fn unless<T>(value: Option<T>, other: &Option<T>) -> Option<T> {
+			value.filter(|_| other.is_none())
+		}
+
+		Self {
+			enabled: unless(self.enabled, &other.enabled),
+			indent_style: unless(self.indent_style, &other.indent_style),
+			indent_size: unless(self.indent_size, &other.indent_size),
+			line_width: unless(self.line_width, &other.line_width),
+			tab_width: unless(self.tab_width, &other.tab_width),
+			line_ending: unless(self.line_ending, &other.line_ending),
+			final_newline: unless(self.final_newline, &other.final_newline),
+			json: JsonOverride {
+				sort_keys: unless(self.json.sort_keys, &other.json.sort_keys),
+				object_wrap: unless(self.json.object_wrap, &other.json.object_wrap),
+			},
+		}
+	}
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct JsonOverride {
+	pub sort_keys: Option<bool>,
+	pub object_wrap: Option<ObjectWrap>,
+}
+
+/// The settings of the linter to replace, the ones that are [None] are left as they are
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintOverride {
+	pub enabled: Option<bool>,
+}
+
+impl LintOverride {
+	pub fn apply_to(&self, configuration: &mut LintConfiguration) {
+		if let Some(enabled) = self.enabled {
+			configuration.enabled = enabled;
+		}
+	}
+}
+
+/// An [Override] with its patterns compiled
+#[derive(Debug)]
+pub(super) struct CompiledOverride {
+	include: PatternSet,
+	settings: Override,
+}
+
+impl CompiledOverride {
+	pub(super) fn new(settings: &Override) -> Self {
+		Self {
+			include: PatternSet::new(&settings.include),
+			settings: settings.clone(),
+		}
+	}
+
+	/// Returns the override if it applies to `path`, relative to the root of the project
+	pub(super) fn matching(&self, path: &Path) -> Option<&Override> {
+		if self.include.matches(path) {
+			Some(&self.settings)
+		} else {
+			None
+		}
+	}
+}
@@ -0,0 +1,201 @@
+use super::{
+	overrides::CompiledOverride, Configuration, EditorConfigs, FilesMatcher, FormatConfiguration,
+	LintConfiguration, Override, ProjectConfiguration,
+};
+use crate::fs::{FileSystem, OsFileSystem};
+use std::path::Path;
+
+/// The settings of a project, ready to be resolved for each of its files.
+///
+/// The default settings don't read the `.editorconfig` files.
+#[derive(Debug, Default)]
+pub struct ProjectSettings {
+	configuration: Configuration,
+	files: FilesMatcher,
+	overrides: Vec<CompiledOverride>,
+	editorconfigs: EditorConfigs,
+}
+
+impl ProjectSettings {
+	/// Creates the settings of `project`, or the default ones if there's no configuration file.
+	/// Relative paths are resolved from `working_directory`.
+	pub fn new(project: Option<&ProjectConfiguration>, working_directory: &Path) -> Self {
+		Self::with_file_system(project, working_directory, Box::new(OsFileSystem))
+	}
+
+	/// Like [ProjectSettings::new], reading the `.editorconfig` files from `fs`
+	pub fn with_file_system(
+		project: Option<&ProjectConfiguration>,
+		working_directory: &Path,
+		fs: Box<dyn FileSystem>,
+	) -> Self {
+		let editorconfigs = EditorConfigs::new(fs, working_directory);
+		match project {
+			Some(project) => {
+				let configuration = project.configuration.clone();
+				Self {
+					files: FilesMatcher::new(
+						project.root(),
+						working_directory,
+						&configuration.files,
+					),
+					overrides: configuration
+						.overrides
+						.iter()
+						.map(CompiledOverride::new)
+						.collect(),
+					configuration,
+					editorconfigs,
+				}
+			}
+			None => Self {
+				editorconfigs,
+				..Self::default()
+			},
+		}
+	}
+
+	/// Decides which files belong to the project
+	pub fn files(&self) -> &FilesMatcher {
+		&self.files
+	}
+
+	/// The configuration of the formatter for the file at `path`. The `.editorconfig` files
+	/// apply to the settings that the configuration file doesn't set, the overrides apply last.
+	pub fn format_configuration(&self, path: &Path) -> FormatConfiguration {
+		let mut configuration = self.configuration.format.clone();
+		self.editorconfigs
+			.format_override(path)
+			.unless_set_by(&self.configuration.explicit_format)
+			.apply_to(&mut configuration);
+		for settings in self.matching_overrides(path) {
+			settings.format.apply_to(&mut configuration);
+		}
+		configuration
+	}
+
+	/// The configuration of the linter for the file at `path`
+	pub fn lint_configuration(&self, path: &Path) -> LintConfiguration {
+		let mut configuration = self.configuration.lint.clone();
+		for settings in self.matching_overrides(path) {
+			settings.lint.apply_to(&mut configuration);
+		}
+		configuration
+	}
+
+	fn matching_overrides<'a>(&'a self, path: &Path) -> impl Iterator<Item = &'a Override> + 'a {
+		let relative = self.files.relative_path(path);
+		self.overrides
+			.iter()
+			.filter_map(move |compiled| compiled.matching(relative.as_deref()?))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::ProjectSettings;
+	use crate::configuration::{
+		parse_configuration, IndentStyle, LineEnding, ProjectConfiguration,
+	};
+	use crate::fs::MemoryFileSystem;
+	use std::path::{Path, PathBuf};
+
+	#[test]
+	fn applies_the_matching_overrides_in_order() {
+		let configuration = parse_configuration(
+			r#"{
+				"format": { "lineWidth": 80 },
+				"overrides": [
+					{ "include": ["**/*.test.ts"], "format": { "lineWidth": 120 } },
+					{ "include": ["legacy"], "format": { "enabled": false }, "lint": { "enabled": false } },
+					{ "include": ["*.json"], "format": { "indentStyle": "space" } },
+					{ "include": ["legacy/*.json"], "format": { "enabled": true } }
+				]
+			}"#,
+		)
+		.unwrap();
+		let project = ProjectConfiguration {
+			path: PathBuf::from("/project/rome.json"),
+			configuration,
+		};
+		let settings = ProjectSettings::new(Some(&project), Path::new("/project"));
+
+		let format = settings.format_configuration(Path::new("src/index.ts"));
+		assert!(format.enabled);
+		assert_eq!(format.line_width, 80);
+		assert_eq!(format.indent_style, IndentStyle::Tab);
+
+		let format = settings.format_configuration(Path::new("src/index.test.ts"));
+		assert_eq!(format.line_width, 120);
+
+		let format = settings.format_configuration(Path::new("/project/legacy/a/index.js"));
+		assert!(!format.enabled);
+		assert!(
+			!settings
+				.lint_configuration(Path::new("legacy/index.js"))
+				.enabled
+		);
+
+		let format = settings.format_configuration(Path::new("src/data/file.json"));
+		assert_eq!(format.indent_style, IndentStyle::Space);
+
+		let format = settings.format_configuration(Path::new("legacy/file.json"));
+		assert!(format.enabled);
+		assert_eq!(format.indent_style, IndentStyle::Space);
+
+		let format = settings.format_configuration(Path::new("/elsewhere/legacy/index.js"));
+		assert!(
+			format.enabled,
+			"overrides don't apply outside of the project"
+		);
+	}
+
+	#[test]
+	fn the_configuration_file_takes_precedence_over_editorconfig() {
+		let fs = MemoryFileSystem::default();
+		fs.insert(
+			"/project/.editorconfig",
+			"[*]\nindent_style = space\nindent_size = 4\nend_of_line = crlf\n\
+			[*.md]\nmax_line_length = 100\n",
+		);
+		let configuration = parse_configuration(
+			r#"{
+				"format": { "indentSize": 2 },
+				"overrides": [{ "include": ["*.json"], "format": { "indentStyle": "tab" } }]
+			}"#,
+		)
+		.unwrap();
+		let project = ProjectConfiguration {
+			path: PathBuf::from("/project/rome.json"),
+			configuration,
+		};
+		let settings =
+			ProjectSettings::with_file_system(Some(&project), Path::new("/project"), Box::new(fs));
+
+		let format = settings.format_configuration(Path::new("src/index.js"));
+		assert_eq!(format.indent_style, IndentStyle::Space);
+		assert_eq!(format.indent_size, 2);
+		assert_eq!(format.tab_width, 4);
+		assert_eq!(format.line_ending, LineEnding::Crlf);
+		assert_eq!(format.line_width, 80);
+
+		let format = settings.format_configuration(Path::new("data.json"));
+		assert_eq!(format.indent_style, IndentStyle::Tab);
+	}
+
+	#[test]
+	fn editorconfig_applies_without_configuration_file() {
+		let fs = MemoryFileSystem::default();
+		fs.insert("/project/.editorconfig", "[*]\nindent_style = space\n");
+		let settings = ProjectSettings::with_file_system(None, Path::new("/project"), Box::new(fs));
+
+		let format = settings.format_configuration(Path::new("src/index.js"));
+		assert_eq!(format.indent_style, IndentStyle::Space);
+		assert_eq!(
+			ProjectSettings::default()
+				.format_configuration(Path::new("/project/src/index.js"))
+				.indent_style,
+			IndentStyle::Tab
+		);
+	}
+}
@@ -1,24 +1,40 @@
-use super::{ExtensionHandler, Mime};
-#[derive(Debug, PartialEq, Eq)]
-pub struct JsonFileHandler {}
+use std::fmt;
 
-impl ExtensionHandler for JsonFileHandler {
-	fn capabilities(&self) -> super::Capabilities {
-		super::Capabilities {
-			format: true,
-			lint: true,
-		}
+/// The flavours of JSON that Rome knows how to parse and format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonDialect {
+	/// Plain JSON, as described by [RFC 8259](https://datatracker.ietf.org/doc/html/rfc8259)
+	Json,
+	/// JSON with comments and trailing commas, as used by `tsconfig.json` or the VS Code settings
+	Jsonc,
+	/// [JSON5](https://json5.org/): unquoted keys, single quoted strings, hexadecimal numbers and so on
+	Json5,
+}
+
+impl JsonDialect {
+	/// Whether line and block comments are allowed
+	pub fn allows_comments(&self) -> bool {
+		matches!(self, JsonDialect::Jsonc | JsonDialect::Json5)
 	}
 
-	fn language(&self) -> super::Language {
-		super::Language::Json
+	/// Whether the last element of an object or array can be followed by a comma
+	pub fn allows_trailing_commas(&self) -> bool {
+		matches!(self, JsonDialect::Jsonc | JsonDialect::Json5)
 	}
 
-	fn mime(&self) -> super::Mime {
-		Mime::Json
+	/// Whether the ECMAScript 5.1 extensions of JSON5 are allowed (unquoted keys,
+	/// single quoted strings, hexadecimal numbers, `Infinity`, ...)
+	pub fn allows_json5_syntax(&self) -> bool {
+		matches!(self, JsonDialect::Json5)
 	}
+}
 
-	fn may_use_tabs(&self) -> bool {
-		true
+impl fmt::Display for JsonDialect {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			JsonDialect::Json => write!(f, "JSON"),
+			JsonDialect::Jsonc => write!(f, "JSONC"),
+			JsonDialect::Json5 => write!(f, "JSON5"),
+		}
 	}
 }
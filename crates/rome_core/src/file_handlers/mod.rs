@@ -1,17 +1,46 @@
-pub mod javascript;
+//! The handlers of the files, which tell what Rome knows about a file and how to parse and format
+//! it. The handlers of the languages Rome supports are registered by the crates that implement
+//! them, and the embedders of Rome can register their own, see [App](crate::App).
 pub mod json;
 pub mod unknown;
 
+use crate::{
+	configuration::FormatConfiguration,
+	format::{FormatError, FormatResult, Formatted},
+	package::PackageType,
+	workspace::Parsed,
+};
+use json::JsonDialect;
+use rslint_parser::Syntax;
+use std::path::Path;
+
 pub enum Language {
 	Js,
 	Json,
+	Jsonc,
+	Json5,
 	Ts,
+	/// A language Rome doesn't know, supported by a handler of an embedder, with its name
+	Other(&'static str),
 	Unknown,
 }
 
+impl Language {
+	/// The JSON dialect of the language, if the language is a flavour of JSON
+	pub fn json_dialect(&self) -> Option<JsonDialect> {
+		match self {
+			Language::Json => Some(JsonDialect::Json),
+			Language::Jsonc => Some(JsonDialect::Jsonc),
+			Language::Json5 => Some(JsonDialect::Json5),
+			Language::Js | Language::Ts | Language::Other(_) | Language::Unknown => None,
+		}
+	}
+}
+
 pub enum Mime {
 	Javascript,
 	Json,
+	Json5,
 	Css,
 	Text,
 }
@@ -21,6 +50,7 @@ impl std::fmt::Display for Mime {
 		match self {
 			Mime::Css => write!(f, "text/css"),
 			Mime::Json => write!(f, "application/json"),
+			Mime::Json5 => write!(f, "application/json5"),
 			Mime::Javascript => write!(f, "application/javascript"),
 			Mime::Text => write!(f, "text/plain"),
 		}
@@ -33,8 +63,13 @@ pub struct Capabilities {
 	pub format: bool,
 }
 
-/// Main trait to use to add a new language to Rome
-pub trait ExtensionHandler {
+/// Main trait to use to add a new language to Rome. The handlers are shared by the threads that
+/// process the files.
+///
+/// Besides describing the files, a handler provides the entry points of the language: how to
+/// parse the files and how to format them. The entry points of the handlers that don't support
+/// an operation return `None` or [FormatError::UnsupportedLanguage].
+pub trait ExtensionHandler: Send + Sync {
 	/// The language of the file. It can be a super language.
 	/// For example, a ".js" file can have [Language::Ts]
 	fn language(&self) -> Language;
@@ -55,6 +90,43 @@ pub trait ExtensionHandler {
 		}
 	}
 
+	/// The syntax the parser accepts in the files of [Language::Js] and [Language::Ts]
+	fn syntax(&self) -> Syntax {
+		Syntax::default()
+	}
+
+	/// The syntax of the files that belong to a package of type `package_type`, see
+	/// [ExtensionHandler::depends_on_package_type]
+	fn syntax_in_package(&self, _package_type: PackageType) -> Syntax {
+		self.syntax()
+	}
+
+	/// The syntax of the files depends on the `type` of the nearest `package.json`, like `.js`
+	/// files which are ES modules in the packages of type `module`
+	fn depends_on_package_type(&self) -> bool {
+		false
+	}
+
+	/// Parses `content`, the content of a file of this handler. The JavaScript dialects are
+	/// parsed with `syntax`, see [ExtensionHandler::syntax_in_package].
+	///
+	/// Returns `None` if the handler can't parse its files.
+	fn parse(&self, _content: &str, _syntax: Syntax) -> Option<Parsed> {
+		None
+	}
+
+	/// Formats the file at `path`, parsed by [ExtensionHandler::parse], with `configuration`.
+	///
+	/// The caller checks the capabilities of the handler first.
+	fn format(
+		&self,
+		_path: &Path,
+		_parsed: &Parsed,
+		_configuration: &FormatConfiguration,
+	) -> FormatResult<Formatted> {
+		Err(FormatError::UnsupportedLanguage)
+	}
+
 	/// How a file should be treated. Usually an asset doesn't posses a parser.
 	///
 	/// An image should me parked as asset.
@@ -0,0 +1,197 @@
+//! The results of formatting a file, shared by the [ExtensionHandler](crate::file_handlers::ExtensionHandler)s
+//! that format their files and the consumers of the formatted code.
+use crate::workspace::WorkspaceError;
+use rslint_errors::Diagnostic;
+use rslint_parser::{SyntaxError, TextRange, TextSize};
+use std::fmt::{self, Display};
+
+/// The result of formatting a file
+pub type FormatResult<F> = Result<F, FormatError>;
+
+#[derive(Debug, PartialEq)]
+/// Series of errors encountered during formatting
+pub enum FormatError {
+	/// Node is missing and it should be required for a correct formatting
+	MissingRequiredChild,
+
+	/// In case our formatter doesn't know how to format a certain language
+	UnsupportedLanguage,
+
+	/// When the ability to format the current file has been turned off on purpose
+	CapabilityDisabled,
+
+	/// The source contains syntax errors that prevent formatting it, for example
+	/// JSON using a feature that isn't supported by its dialect
+	SyntaxError(Vec<Diagnostic>),
+
+	/// The formatted code doesn't have the same meaning as the original code, or it changes
+	/// when it's formatted again
+	VerificationFailed(Diagnostic),
+
+	/// The file couldn't be read or written
+	Io(Diagnostic),
+
+	/// The document couldn't be found in the [Workspace](crate::workspace::Workspace)
+	Workspace(WorkspaceError),
+}
+
+impl Display for FormatError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			FormatError::MissingRequiredChild => write!(f, "a node is missing a required child"),
+			FormatError::UnsupportedLanguage => write!(f, "the language isn't supported"),
+			FormatError::CapabilityDisabled => write!(f, "the formatting is disabled"),
+			FormatError::SyntaxError(diagnostics) => match diagnostics.as_slice() {
+				[diagnostic] => write!(f, "{}", diagnostic.title),
+				diagnostics => write!(f, "{} syntax errors", diagnostics.len()),
+			},
+			FormatError::VerificationFailed(diagnostic) | FormatError::Io(diagnostic) => {
+				write!(f, "{}", diagnostic.title)
+			}
+			FormatError::Workspace(error) => write!(f, "{}", error),
+		}
+	}
+}
+
+impl From<WorkspaceError> for FormatError {
+	fn from(error: WorkspaceError) -> Self {
+		match error {
+			WorkspaceError::Unsupported(_) => FormatError::UnsupportedLanguage,
+			error => FormatError::Workspace(error),
+		}
+	}
+}
+
+impl From<SyntaxError> for FormatError {
+	fn from(syntax_error: SyntaxError) -> Self {
+		match syntax_error {
+			SyntaxError::MissingRequiredChild(_node) => FormatError::MissingRequiredChild,
+		}
+	}
+}
+
+/// Records where a token of the source code has been printed in the formatted code, or where a
+/// node starts or ends, in which case both ranges are empty
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SourceMarker {
+	/// The range of the token in the source code
+	pub source: TextRange,
+	/// The range of the token in the formatted code
+	pub dest: TextRange,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Formatted {
+	code: String,
+	sourcemap: Vec<SourceMarker>,
+}
+
+impl Formatted {
+	pub fn new(code: &str) -> Self {
+		Self {
+			code: String::from(code),
+			sourcemap: Vec::new(),
+		}
+	}
+
+	/// The formatted code, with the markers of the tokens and nodes of the source code it contains
+	pub fn with_sourcemap(code: &str, mut sourcemap: Vec<SourceMarker>) -> Self {
+		sourcemap.sort_by_key(|marker| marker.source.start());
+		Self {
+			code: String::from(code),
+			sourcemap,
+		}
+	}
+
+	pub fn code(&self) -> &String {
+		&self.code
+	}
+
+	/// The markers of the tokens and nodes of the source code, in the order of the source code
+	pub fn sourcemap(&self) -> &[SourceMarker] {
+		&self.sourcemap
+	}
+
+	/// Returns the formatted code of the lines of `source` that `range` touches, along with the
+	/// range of `source` it replaces. `source` is the code that has been formatted.
+	///
+	/// The lines are widened until the tokens they contain are printed on the same lines of the
+	/// formatted code, for example when formatting joins two lines. Returns `None` if there's no
+	/// token in the range. The whole code is replaced when there's no source map, like for JSON.
+	pub fn format_range(&self, source: &str, range: TextRange) -> Option<(TextRange, String)> {
+		let code = self.code.as_str();
+		if self.sourcemap.is_empty() {
+			return Some((TextRange::up_to(TextSize::of(source)), code.to_string()));
+		}
+
+		let mut source_range = line_range(source, range);
+		loop {
+			let selected = self
+				.sourcemap
+				.iter()
+				.filter(|marker| source_range.contains_range(marker.source))
+				.map(|marker| marker.dest);
+			let dest_range = line_range(code, covering_range(selected)?);
+
+			// The tokens printed on the lines of the formatted code, which can come from other lines
+			let printed = self
+				.sourcemap
+				.iter()
+				.filter(|marker| dest_range.contains_range(marker.dest))
+				.map(|marker| marker.source);
+			let widened = match covering_range(printed) {
+				Some(printed) => line_range(source, printed).cover(source_range),
+				None => source_range,
+			};
+
+			if widened == source_range {
+				return Some((source_range, code[dest_range].to_string()));
+			}
+			source_range = widened;
+		}
+	}
+
+	/// Translates an offset of the source code to the matching offset of the formatted code.
+	///
+	/// An offset inside of a token keeps its position relative to the token. An offset in the
+	/// whitespace or comments between two tokens moves to the start of the next token or node, or to
+	/// the end of the node it's in, because the whitespace has likely been rewritten. The nodes
+	/// matter when their tokens have been rewritten and don't have markers of their own.
+	pub fn translate_offset(&self, source_offset: TextSize) -> TextSize {
+		for marker in &self.sourcemap {
+			if source_offset < marker.source.start() {
+				return marker.dest.start();
+			}
+
+			if source_offset <= marker.source.end() {
+				let offset_in_token =
+					(source_offset - marker.source.start()).min(marker.dest.len());
+				return marker.dest.start() + offset_in_token;
+			}
+		}
+
+		// After the last token
+		self.sourcemap.last().map_or_else(
+			|| TextSize::of(self.code.as_str()),
+			|marker| marker.dest.end(),
+		)
+	}
+}
+
+/// The smallest range that contains all the `ranges`
+fn covering_range(ranges: impl Iterator<Item = TextRange>) -> Option<TextRange> {
+	ranges.reduce(|covering, range| covering.cover(range))
+}
+
+/// Widens `range` to the start of its first line and to the end of its last line, without the
+/// line break
+fn line_range(text: &str, range: TextRange) -> TextRange {
+	let start = text[..usize::from(range.start())]
+		.rfind('\n')
+		.map_or(0, |position| position + 1);
+	let end = text[usize::from(range.end())..]
+		.find('\n')
+		.map_or(text.len(), |position| usize::from(range.end()) + position);
+
+	TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32))
+}
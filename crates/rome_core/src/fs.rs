@@ -0,0 +1,175 @@
+//! The file system Rome reads and writes the files through.
+//!
+//! [OsFileSystem] is the file system of the operating system. [MemoryFileSystem] keeps the files
+//! in memory, for the tests and for the buffers of an editor that aren't saved on disk.
+//!
+//! The operations that fail return a [FileSystemError], which can be turned into a [Diagnostic]
+//! to be reported like the other errors of a file.
+use rslint_errors::Diagnostic;
+use std::{
+	collections::BTreeMap,
+	fmt::{self, Display},
+	fs, io,
+	path::{Path, PathBuf},
+	sync::RwLock,
+};
+
+pub trait FileSystem: Send + Sync {
+	/// Returns the content of the file at `path`
+	fn read(&self, path: &Path) -> Result<String, FileSystemError>;
+
+	/// Replaces the content of the file at `path` with `content`, creating the file if needed
+	fn write(&self, path: &Path, content: &str) -> Result<(), FileSystemError>;
+
+	/// Returns `true` if there's a file at `path`
+	fn is_file(&self, path: &Path) -> bool;
+}
+
+/// What the file system was doing when it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+	Read,
+	Write,
+}
+
+/// An operation of a [FileSystem] that failed
+#[derive(Debug)]
+pub struct FileSystemError {
+	pub path: PathBuf,
+	pub operation: Operation,
+	pub error: io::Error,
+}
+
+impl FileSystemError {
+	fn new(path: &Path, operation: Operation, error: io::Error) -> Self {
+		Self {
+			path: path.to_path_buf(),
+			operation,
+			error,
+		}
+	}
+
+	/// Returns the error as a diagnostic of the file `file_id`
+	pub fn to_diagnostic(&self, file_id: usize) -> Diagnostic {
+		Diagnostic::error(file_id, "IO", self.to_string())
+	}
+}
+
+impl Display for FileSystemError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let operation = match self.operation {
+			Operation::Read => "read",
+			Operation::Write => "write",
+		};
+		write!(
+			f,
+			"cannot {} {}: {}",
+			operation,
+			self.path.display(),
+			self.error
+		)
+	}
+}
+
+/// The file system of the operating system
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+	fn read(&self, path: &Path) -> Result<String, FileSystemError> {
+		fs::read_to_string(path).map_err(|error| FileSystemError::new(path, Operation::Read, error))
+	}
+
+	fn write(&self, path: &Path, content: &str) -> Result<(), FileSystemError> {
+		fs::write(path, content)
+			.map_err(|error| FileSystemError::new(path, Operation::Write, error))
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		path.is_file()
+	}
+}
+
+/// A file system that keeps the files in memory
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+	files: RwLock<BTreeMap<PathBuf, String>>,
+}
+
+impl MemoryFileSystem {
+	/// Adds the file at `path`, or replaces its content
+	pub fn insert(&self, path: impl Into<PathBuf>, content: impl Into<String>) {
+		self.files
+			.write()
+			.unwrap()
+			.insert(path.into(), content.into());
+	}
+
+	/// Removes the file at `path`, returning its content
+	pub fn remove(&self, path: &Path) -> Option<String> {
+		self.files.write().unwrap().remove(path)
+	}
+}
+
+impl FileSystem for MemoryFileSystem {
+	fn read(&self, path: &Path) -> Result<String, FileSystemError> {
+		self.files
+			.read()
+			.unwrap()
+			.get(path)
+			.cloned()
+			.ok_or_else(|| {
+				let error = io::Error::new(io::ErrorKind::NotFound, "the file doesn't exist");
+				FileSystemError::new(path, Operation::Read, error)
+			})
+	}
+
+	fn write(&self, path: &Path, content: &str) -> Result<(), FileSystemError> {
+		self.insert(path, content);
+		Ok(())
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		self.files.read().unwrap().contains_key(path)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{FileSystem, MemoryFileSystem, OsFileSystem};
+	use std::path::Path;
+
+	#[test]
+	fn memory_file_system() {
+		let fs = MemoryFileSystem::default();
+		let path = Path::new("src/index.js");
+		assert!(!fs.is_file(path));
+		assert!(fs.read(path).is_err());
+
+		fs.write(path, "let a;").unwrap();
+		assert!(fs.is_file(path));
+		assert_eq!(fs.read(path).unwrap(), "let a;");
+
+		assert_eq!(fs.remove(path).as_deref(), Some("let a;"));
+		assert!(!fs.is_file(path));
+	}
+
+	#[test]
+	fn errors_name_the_file() {
+		let error = OsFileSystem
+			.read(Path::new("/missing/index.js"))
+			.unwrap_err();
+		assert!(
+			error
+				.to_string()
+				.starts_with("cannot read /missing/index.js: "),
+			"{}",
+			error
+		);
+		assert_eq!(
+			error.to_diagnostic(0).title,
+			error.to_string(),
+			"the diagnostic holds the message"
+		);
+	}
+}
@@ -1,26 +1,46 @@
-use crate::file_handlers::{javascript::JsFileHandler, unknown::UnknownFileHandler};
-use file_handlers::{json::JsonFileHandler, ExtensionHandler};
-use std::collections::HashMap;
+use crate::file_handlers::unknown::UnknownFileHandler;
+use file_handlers::ExtensionHandler;
+use fs::FileSystem;
+use package::PackageTypes;
+use rslint_parser::Syntax;
+use std::{collections::HashMap, path::Path, sync::Arc};
 
+pub mod cache;
+pub mod configuration;
 pub mod file_handlers;
+pub mod format;
+pub mod fs;
+pub mod package;
+pub mod walker;
+pub mod workspace;
 
-// these strings will live for the whole App, so it makes sense to have them as static
-pub type Handlers = HashMap<&'static str, Box<dyn ExtensionHandler>>;
+/// The handlers, by the extension, the file name or the interpreter they handle. They're shared
+/// with the files they parse, see [Parsed::handler](workspace::Parsed::handler)
+pub type Handlers = HashMap<String, Arc<dyn ExtensionHandler>>;
 
+/// The registry of the handlers of the files. It starts empty: the crates that implement the
+/// languages register their handlers, like `rome_formatter::create_app`, and so can the embedders
+/// of Rome for their own languages.
 pub struct App {
+	/// The handlers of the extensions, which can have several parts like `d.ts`
 	handlers: Handlers,
-	unknown_handler: Box<dyn ExtensionHandler>,
+	/// The handlers of the well-known files whose extension doesn't tell their language
+	file_names: Handlers,
+	/// The handlers of the scripts without extension, by the interpreter of their shebang
+	interpreters: Handlers,
+	unknown_handler: Arc<dyn ExtensionHandler>,
+	/// The types of the packages of the files whose syntax depends on it
+	packages: PackageTypes,
 }
 
 impl Default for App {
 	fn default() -> Self {
-		let mut map: Handlers = HashMap::new();
-		map.insert("js", Box::new(JsFileHandler {}));
-		map.insert("ts", Box::new(JsFileHandler {}));
-		map.insert("json", Box::new(JsonFileHandler {}));
 		Self {
-			handlers: map,
-			unknown_handler: Box::new(UnknownFileHandler {}),
+			handlers: HashMap::new(),
+			file_names: HashMap::new(),
+			interpreters: HashMap::new(),
+			unknown_handler: Arc::new(UnknownFileHandler {}),
+			packages: PackageTypes::default(),
 		}
 	}
 }
@@ -30,6 +50,37 @@ impl App {
 		Default::default()
 	}
 
+	/// Registers the handler of the files with the given `extension`, without the leading dot,
+	/// like `js` or `d.ts`. It replaces the handler registered before for the extension, if any.
+	pub fn register_extension(
+		&mut self,
+		extension: impl Into<String>,
+		handler: Box<dyn ExtensionHandler>,
+	) {
+		self.handlers.insert(extension.into(), Arc::from(handler));
+	}
+
+	/// Registers the handler of the files named `file_name`, like `tsconfig.json`. It takes
+	/// precedence over the handler of their extension
+	pub fn register_file_name(
+		&mut self,
+		file_name: impl Into<String>,
+		handler: Box<dyn ExtensionHandler>,
+	) {
+		self.file_names.insert(file_name.into(), Arc::from(handler));
+	}
+
+	/// Registers the handler of the scripts without extension whose shebang runs `interpreter`,
+	/// like `node`
+	pub fn register_interpreter(
+		&mut self,
+		interpreter: impl Into<String>,
+		handler: Box<dyn ExtensionHandler>,
+	) {
+		self.interpreters
+			.insert(interpreter.into(), Arc::from(handler));
+	}
+
 	pub fn get_handler<'a>(&self, file_extension: &'a str) -> Option<&dyn ExtensionHandler> {
 		let handler = if self.handlers.contains_key(file_extension) {
 			self.handlers.get(file_extension)
@@ -38,8 +89,129 @@ impl App {
 		};
 		handler.map(|handler| handler.as_ref())
 	}
+
+	/// Returns the handler of the file at `path`: the handler of its name if it's a well-known
+	/// file, otherwise the handler of its longest registered extension, so that `index.d.ts`
+	/// isn't handled as `index.ts`.
+	///
+	/// The files whose extension isn't registered get the unknown handler. The files without
+	/// extension don't get any, see [App::get_handler_for_content].
+	pub fn get_handler_for_path(&self, path: &Path) -> Option<&Arc<dyn ExtensionHandler>> {
+		let file_name = path.file_name()?.to_str()?;
+		if let Some(handler) = self.file_names.get(file_name) {
+			return Some(handler);
+		}
+
+		// The leading dot of a hidden file doesn't start an extension
+		let extensions = file_name
+			.char_indices()
+			.skip(1)
+			.filter(|(_, character)| *character == '.')
+			.map(|(index, _)| &file_name[index + 1..]);
+
+		let mut has_extension = false;
+		for extension in extensions {
+			has_extension = true;
+			if let Some(handler) = self.handlers.get(extension) {
+				return Some(handler);
+			}
+		}
+
+		if has_extension {
+			Some(&self.unknown_handler)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the handler of a script without extension from its `content`, by the interpreter of
+	/// its shebang, like `#!/usr/bin/env node`
+	pub fn get_handler_for_content(&self, content: &str) -> Option<&Arc<dyn ExtensionHandler>> {
+		let shebang = content.strip_prefix("#!")?.lines().next()?;
+		let mut words = shebang.split_whitespace();
+
+		let mut program = words.next()?;
+		if program.rsplit('/').next() == Some("env") {
+			// The options of env, like -S, and the variables it sets come before the program
+			program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+		}
+
+		let program = program.rsplit('/').next()?;
+		self.interpreters.get(program)
+	}
+
+	/// Returns the syntax of the file at `path`, handled by `handler`. For the handlers whose
+	/// syntax depends on the package of the file, it's read from the nearest `package.json`
+	/// in `fs`, see [PackageTypes::package_type]
+	pub fn get_syntax(
+		&self,
+		handler: &dyn ExtensionHandler,
+		fs: &dyn FileSystem,
+		path: &Path,
+	) -> Syntax {
+		if handler.depends_on_package_type() {
+			handler.syntax_in_package(self.packages.package_type(fs, path))
+		} else {
+			handler.syntax()
+		}
+	}
 }
 
-pub fn create_app() -> App {
-	App::new()
+#[cfg(test)]
+mod test {
+	use crate::{
+		file_handlers::{ExtensionHandler, Language, Mime},
+		App,
+	};
+	use std::path::Path;
+
+	struct DslFileHandler {
+		name: &'static str,
+	}
+
+	impl ExtensionHandler for DslFileHandler {
+		fn language(&self) -> Language {
+			Language::Other(self.name)
+		}
+
+		fn mime(&self) -> Mime {
+			Mime::Text
+		}
+	}
+
+	fn language_name(app: &App, path: &str) -> Option<&'static str> {
+		match app.get_handler_for_path(Path::new(path))?.language() {
+			Language::Other(name) => Some(name),
+			Language::Unknown => Some("unknown"),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn registers_handlers_at_runtime() {
+		let mut app = App::new();
+		assert_eq!(language_name(&app, "query.dsl"), Some("unknown"));
+
+		app.register_extension("dsl", Box::new(DslFileHandler { name: "dsl" }));
+		app.register_extension(
+			String::from("test.dsl"),
+			Box::new(DslFileHandler { name: "dsl test" }),
+		);
+		app.register_file_name(".dslrc", Box::new(DslFileHandler { name: "dslrc" }));
+		app.register_interpreter("dsl-run", Box::new(DslFileHandler { name: "script" }));
+
+		assert_eq!(language_name(&app, "src/query.dsl"), Some("dsl"));
+		assert_eq!(language_name(&app, "src/query.test.dsl"), Some("dsl test"));
+		assert_eq!(language_name(&app, "project/.dslrc"), Some("dslrc"));
+		assert_eq!(language_name(&app, ".hidden.dsl"), Some("dsl"));
+		assert_eq!(language_name(&app, "notes.txt"), Some("unknown"));
+		assert_eq!(language_name(&app, "Makefile"), None);
+
+		let script = app
+			.get_handler_for_content("#!/usr/bin/env -S DEBUG=1 dsl-run --fast\nquery")
+			.unwrap();
+		assert!(matches!(script.language(), Language::Other("script")));
+		assert!(app.get_handler_for_content("#!/bin/sh\necho 1").is_none());
+		assert!(app.get_handler_for_content("query").is_none());
+	}
 }
@@ -0,0 +1,155 @@
+//! The `type` of the packages, which tells whether their `.js` files are ES modules or CommonJS
+//! scripts.
+//!
+//! Like Node.js, the type of a file is the `type` field of the nearest `package.json` above it:
+//! `"module"` makes it a module, anything else, or no `package.json` at all, makes it a script.
+//! The lookups are cached per directory, so the files of a package read its `package.json` once.
+use crate::fs::FileSystem;
+use serde_json::Value;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::RwLock,
+};
+
+const PACKAGE_JSON: &str = "package.json";
+
+/// The kind of the `.js` files of a package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageType {
+	/// `"type": "commonjs"`, or no `type`
+	CommonJs,
+	/// `"type": "module"`
+	Module,
+}
+
+impl Default for PackageType {
+	fn default() -> Self {
+		PackageType::CommonJs
+	}
+}
+
+impl PackageType {
+	/// The type set by the `package.json` with the given content. A manifest that can't be parsed
+	/// doesn't set it, as Node.js would refuse to run the package anyway
+	fn from_manifest(content: &str) -> Self {
+		let manifest: Value = match serde_json::from_str(content) {
+			Ok(manifest) => manifest,
+			Err(_) => return PackageType::default(),
+		};
+		match manifest.get("type").and_then(Value::as_str) {
+			Some("module") => PackageType::Module,
+			_ => PackageType::CommonJs,
+		}
+	}
+}
+
+/// The types of the packages the files belong to, cached by directory.
+///
+/// The cache isn't invalidated: a `package.json` that changes is only read again by a new
+/// [PackageTypes].
+#[derive(Debug, Default)]
+pub struct PackageTypes {
+	directories: RwLock<HashMap<PathBuf, PackageType>>,
+}
+
+impl PackageTypes {
+	/// Returns the type of the package of the file at `path`, reading the `package.json` files
+	/// from `fs`.
+	///
+	/// The lookup doesn't go above the first component of a relative path: the packages above the
+	/// working directory are only found for absolute paths.
+	pub fn package_type(&self, fs: &dyn FileSystem, path: &Path) -> PackageType {
+		let directory = match path.parent() {
+			Some(directory) => directory,
+			None => return PackageType::default(),
+		};
+
+		// The directories without a package.json get the type of their parent
+		let mut visited = Vec::new();
+		let mut package_type = PackageType::default();
+		for ancestor in directory.ancestors() {
+			if let Some(cached) = self.directories.read().unwrap().get(ancestor) {
+				package_type = *cached;
+				break;
+			}
+
+			visited.push(ancestor);
+			let manifest = ancestor.join(PACKAGE_JSON);
+			if fs.is_file(&manifest) {
+				package_type = fs.read(&manifest).map_or_else(
+					|_| PackageType::default(),
+					|content| PackageType::from_manifest(&content),
+				);
+				break;
+			}
+		}
+
+		let mut directories = self.directories.write().unwrap();
+		for directory in visited {
+			directories.insert(directory.to_path_buf(), package_type);
+		}
+		package_type
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{PackageType, PackageTypes};
+	use crate::fs::{FileSystem, MemoryFileSystem};
+	use std::path::Path;
+
+	#[test]
+	fn the_nearest_package_json_decides() {
+		let fs = MemoryFileSystem::default();
+		fs.insert("/project/package.json", r#"{ "type": "module" }"#);
+		fs.insert("/project/legacy/package.json", r#"{ "name": "legacy" }"#);
+		fs.insert("/project/cjs/package.json", r#"{ "type": "commonjs" }"#);
+
+		let packages = PackageTypes::default();
+		let package_type = |path: &str| packages.package_type(&fs, Path::new(path));
+
+		assert_eq!(package_type("/project/src/a/index.js"), PackageType::Module);
+		assert_eq!(package_type("/project/index.js"), PackageType::Module);
+		assert_eq!(
+			package_type("/project/legacy/index.js"),
+			PackageType::CommonJs
+		);
+		assert_eq!(package_type("/project/cjs/index.js"), PackageType::CommonJs);
+		assert_eq!(package_type("/other/index.js"), PackageType::CommonJs);
+		assert_eq!(package_type("index.js"), PackageType::CommonJs);
+	}
+
+	#[test]
+	fn the_lookups_are_cached_per_directory() {
+		let fs = MemoryFileSystem::default();
+		fs.insert("/project/package.json", r#"{ "type": "module" }"#);
+
+		let packages = PackageTypes::default();
+		let path = Path::new("/project/src/index.js");
+		assert_eq!(packages.package_type(&fs, path), PackageType::Module);
+
+		fs.write(Path::new("/project/package.json"), "{}").unwrap();
+		assert_eq!(packages.package_type(&fs, path), PackageType::Module);
+		assert_eq!(
+			packages.package_type(&fs, Path::new("/project/src/other.js")),
+			PackageType::Module
+		);
+		assert_eq!(
+			PackageTypes::default().package_type(&fs, path),
+			PackageType::CommonJs
+		);
+	}
+
+	#[test]
+	fn invalid_manifests_dont_set_the_type() {
+		let fs = MemoryFileSystem::default();
+		fs.insert("/project/package.json", r#"{ "type": "module", "#);
+
+		let packages = PackageTypes::default();
+		assert_eq!(
+			packages.package_type(&fs, Path::new("/project/index.js")),
+			PackageType::CommonJs
+		);
+	}
+}
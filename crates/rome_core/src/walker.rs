@@ -0,0 +1,369 @@
+//! Walks directories to find the files of a project.
+//!
+//! The walker skips:
+//! - the files ignored by `.gitignore` and `.ignore` files, even outside of a git repository
+//! - the hidden files and directories, whose name starts with a dot
+//! - the `node_modules` directories
+//! - the files that don't belong to the project, according to the `files` section of its
+//!   configuration
+//!
+//! Symbolic links are followed. A link to one of the directories that contain it is reported as
+//! an error instead of being walked again, and a file reached through several links is only
+//! returned once.
+use crate::configuration::FilesMatcher;
+use ignore::{
+	gitignore::{Gitignore, GitignoreBuilder},
+	DirEntry, WalkBuilder,
+};
+use std::{
+	collections::HashSet,
+	fmt::{self, Display},
+	fs,
+	path::{Component, Path, PathBuf},
+};
+
+/// The directories that are never walked, unless they're the root of the walk
+const SKIPPED_DIRECTORIES: &[&str] = &["node_modules"];
+
+/// What the walker skips or follows
+#[derive(Debug, Clone, Copy)]
+pub struct WalkOptions {
+	/// Respect the `.gitignore` and `.ignore` files
+	pub ignore_files: bool,
+	/// Walk the hidden files and directories
+	pub hidden: bool,
+	/// Follow the symbolic links
+	pub follow_symlinks: bool,
+}
+
+impl Default for WalkOptions {
+	fn default() -> Self {
+		Self {
+			ignore_files: true,
+			hidden: false,
+			follow_symlinks: true,
+		}
+	}
+}
+
+/// An error found while walking, it doesn't stop the walk
+#[derive(Debug)]
+pub enum WalkError {
+	/// The symbolic link `child` points to `ancestor`, one of the directories that contain it
+	Loop { ancestor: PathBuf, child: PathBuf },
+	/// A directory or an ignore file couldn't be read
+	Io {
+		path: Option<PathBuf>,
+		message: String,
+	},
+}
+
+impl Display for WalkError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			WalkError::Loop { ancestor, child } => write!(
+				f,
+				"{}: the symbolic link points to {}, which contains it",
+				child.display(),
+				ancestor.display()
+			),
+			WalkError::Io {
+				path: Some(path),
+				message,
+			} => write!(f, "{}: {}", path.display(), message),
+			WalkError::Io {
+				path: None,
+				message,
+			} => write!(f, "{}", message),
+		}
+	}
+}
+
+impl From<ignore::Error> for WalkError {
+	fn from(error: ignore::Error) -> Self {
+		fn convert(error: ignore::Error, path: Option<PathBuf>) -> WalkError {
+			match error {
+				ignore::Error::WithPath { path, err } => convert(*err, Some(path)),
+				ignore::Error::WithDepth { err, .. }
+				| ignore::Error::WithLineNumber { err, .. } => convert(*err, path),
+				ignore::Error::Loop { ancestor, child } => WalkError::Loop { ancestor, child },
+				error => WalkError::Io {
+					path,
+					message: error.to_string(),
+				},
+			}
+		}
+
+		convert(error, None)
+	}
+}
+
+/// Finds the files of a project, shared by all the commands that process directories
+#[derive(Debug, Clone)]
+pub struct FileWalker {
+	files: FilesMatcher,
+	options: WalkOptions,
+}
+
+impl FileWalker {
+	pub fn new(files: &FilesMatcher, options: WalkOptions) -> Self {
+		Self {
+			files: files.clone(),
+			options,
+		}
+	}
+
+	/// Decides which files belong to the project
+	pub fn files(&self) -> &FilesMatcher {
+		&self.files
+	}
+
+	/// Returns the files found by walking `root`, or `root` itself if it's a file
+	pub fn walk(&self, root: &Path) -> impl Iterator<Item = Result<PathBuf, WalkError>> {
+		let files = self.files.clone();
+		let walk = WalkBuilder::new(root)
+			.standard_filters(false)
+			.git_ignore(self.options.ignore_files)
+			.git_exclude(self.options.ignore_files)
+			.ignore(self.options.ignore_files)
+			.parents(self.options.ignore_files)
+			.require_git(false)
+			.hidden(!self.options.hidden)
+			.follow_links(self.options.follow_symlinks)
+			.filter_entry(move |entry| !is_skipped_directory(entry, &files))
+			.build();
+
+		let files = self.files.clone();
+		let mut visited = HashSet::new();
+
+		walk.filter_map(move |entry| {
+			let entry = match entry {
+				Ok(entry) => entry,
+				Err(error) => return Some(Err(WalkError::from(error))),
+			};
+
+			if !entry.file_type().map_or(false, |kind| kind.is_file()) {
+				return None;
+			}
+
+			let path = entry.into_path();
+			if !files.is_included(&path) {
+				return None;
+			}
+
+			// The same file can be reached through several symbolic links
+			let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+			if visited.insert(canonical) {
+				Some(Ok(path))
+			} else {
+				None
+			}
+		})
+	}
+
+	/// Returns `true` if walking `root` would return `path`, a file inside it. It applies the same
+	/// rules as [FileWalker::walk] to a single path, for example to filter the events of a file
+	/// watcher.
+	pub fn is_walked(&self, root: &Path, path: &Path) -> bool {
+		let relative = match path.strip_prefix(root) {
+			Ok(relative) => relative,
+			Err(_) => return false,
+		};
+		if !self.files.is_included(path) {
+			return false;
+		}
+
+		// The ignore files of the parents of the root apply too, the deepest one wins
+		let mut ignores: Vec<Gitignore> = if self.options.ignore_files {
+			let mut ignores: Vec<Gitignore> = root.ancestors().skip(1).map(ignore_files).collect();
+			ignores.reverse();
+			ignores
+		} else {
+			Vec::new()
+		};
+
+		let mut directory = root.to_path_buf();
+		let mut components = relative
+			.components()
+			.filter(|component| matches!(component, Component::Normal(_)))
+			.peekable();
+
+		while let Some(component) = components.next() {
+			if self.options.ignore_files {
+				ignores.push(ignore_files(&directory));
+			}
+
+			let entry = directory.join(component);
+			let is_dir = components.peek().is_some();
+			let name = component.as_os_str().to_string_lossy();
+
+			if !self.options.hidden && name.starts_with('.') {
+				return false;
+			}
+			if is_dir && (SKIPPED_DIRECTORIES.contains(&&*name) || self.files.is_excluded(&entry)) {
+				return false;
+			}
+
+			let ignored = ignores
+				.iter()
+				.rev()
+				.map(|ignore| ignore.matched(&entry, is_dir))
+				.find(|matched| !matched.is_none())
+				.map_or(false, |matched| matched.is_ignore());
+			if ignored {
+				return false;
+			}
+
+			directory = entry;
+		}
+
+		true
+	}
+}
+
+/// Reads the `.gitignore` and `.ignore` files of `directory`. The patterns of `.ignore` take
+/// precedence
+fn ignore_files(directory: &Path) -> Gitignore {
+	let mut builder = GitignoreBuilder::new(directory);
+	for name in [".gitignore", ".ignore"] {
+		let path = directory.join(name);
+		if path.is_file() {
+			builder.add(path);
+		}
+	}
+	builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+fn is_skipped_directory(entry: &DirEntry, files: &FilesMatcher) -> bool {
+	if entry.depth() == 0 || !entry.file_type().map_or(false, |kind| kind.is_dir()) {
+		return false;
+	}
+
+	let is_skipped_name = entry
+		.file_name()
+		.to_str()
+		.map_or(false, |name| SKIPPED_DIRECTORIES.contains(&name));
+
+	is_skipped_name || files.is_excluded(entry.path())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{FileWalker, WalkError, WalkOptions};
+	use crate::configuration::{FilesConfiguration, FilesMatcher, Pattern};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	fn walk(walker: &FileWalker, root: &Path) -> (Vec<PathBuf>, Vec<WalkError>) {
+		let mut files = Vec::new();
+		let mut errors = Vec::new();
+		for result in walker.walk(root) {
+			match result {
+				Ok(path) => files.push(path.strip_prefix(root).unwrap().to_path_buf()),
+				Err(error) => errors.push(error),
+			}
+		}
+		files.sort();
+		(files, errors)
+	}
+
+	#[test]
+	fn skips_ignored_hidden_and_excluded_files() {
+		let root = std::env::temp_dir().join(format!("rome_core_walker_{}", std::process::id()));
+		for directory in ["src", "dist", "node_modules/lib", ".cache", "generated"] {
+			fs::create_dir_all(root.join(directory)).unwrap();
+		}
+		for file in [
+			"src/index.js",
+			"src/debug.log",
+			"dist/index.js",
+			"node_modules/lib/index.js",
+			".cache/index.js",
+			".eslintrc.json",
+			"generated/index.js",
+		] {
+			fs::write(root.join(file), "").unwrap();
+		}
+		fs::write(root.join(".gitignore"), "dist\n").unwrap();
+		fs::write(root.join(".ignore"), "*.log\n").unwrap();
+
+		let configuration = FilesConfiguration {
+			include: Vec::new(),
+			exclude: vec![Pattern::new("generated").unwrap()],
+		};
+		let files = FilesMatcher::new(&root, &root, &configuration);
+
+		let (found, errors) = walk(&FileWalker::new(&files, WalkOptions::default()), &root);
+		assert!(errors.is_empty());
+		assert_eq!(found, vec![PathBuf::from("src/index.js")]);
+
+		let everything = WalkOptions {
+			ignore_files: false,
+			hidden: true,
+			follow_symlinks: true,
+		};
+		let (found, _) = walk(&FileWalker::new(&files, everything), &root);
+		assert_eq!(
+			found,
+			vec![
+				PathBuf::from(".cache/index.js"),
+				PathBuf::from(".eslintrc.json"),
+				PathBuf::from(".gitignore"),
+				PathBuf::from(".ignore"),
+				PathBuf::from("dist/index.js"),
+				PathBuf::from("src/debug.log"),
+				PathBuf::from("src/index.js"),
+			]
+		);
+
+		let (found, _) = walk(
+			&FileWalker::new(&files, WalkOptions::default()),
+			&root.join("node_modules"),
+		);
+		assert_eq!(found, vec![PathBuf::from("lib/index.js")]);
+
+		let walker = FileWalker::new(&files, WalkOptions::default());
+		assert!(walker.is_walked(&root, &root.join("src/index.js")));
+		for file in [
+			"src/debug.log",
+			"dist/index.js",
+			"node_modules/lib/index.js",
+			".cache/index.js",
+			"generated/index.js",
+		] {
+			assert!(!walker.is_walked(&root, &root.join(file)), "{}", file);
+		}
+		assert!(walker.is_walked(
+			&root.join("node_modules"),
+			&root.join("node_modules/lib/index.js")
+		));
+		assert!(!walker.is_walked(&root.join("src"), &root.join("dist/index.js")));
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn follows_symlinks_without_looping() {
+		use std::os::unix::fs::symlink;
+
+		let root =
+			std::env::temp_dir().join(format!("rome_core_walker_links_{}", std::process::id()));
+		fs::create_dir_all(root.join("src")).unwrap();
+		fs::write(root.join("src/index.js"), "").unwrap();
+		symlink(&root, root.join("src/root")).unwrap();
+		symlink(root.join("src"), root.join("alias")).unwrap();
+
+		let walker = FileWalker::new(&FilesMatcher::default(), WalkOptions::default());
+		let (found, errors) = walk(&walker, &root);
+		assert_eq!(found.len(), 1, "{:?}", found);
+		assert!(!errors.is_empty());
+		assert!(errors
+			.iter()
+			.all(|error| matches!(error, WalkError::Loop { .. })));
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+}
@@ -0,0 +1,387 @@
+//! The documents that Rome works on, for the editors, the CLI and the tests.
+//!
+//! A [Workspace] keeps the content of the open documents in memory, so that the changes that
+//! aren't saved are taken into account, and it caches the syntax tree of each document until it
+//! changes. The operations that depend on the language of a document, parsing and formatting,
+//! are provided by the [Services] of the workspace.
+use crate::{
+	configuration::{FormatConfiguration, ProjectSettings},
+	file_handlers::ExtensionHandler,
+};
+use rslint_errors::Diagnostic;
+use rslint_parser::{SyntaxNode, TextRange, TextSize};
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	fmt::{self, Debug, Display},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+/// Parses and formats the documents of a [Workspace].
+///
+/// It's implemented by the crates that know the languages, like `rome_formatter`, as rome_core
+/// can't depend on them.
+pub trait Services {
+	/// The result of formatting a document
+	type Formatted;
+	/// The error of formatting a document
+	type FormatError: From<WorkspaceError>;
+
+	/// Parses `content`, the content of the file at `path`. Returns `None` if the language of
+	/// the file can't be parsed.
+	fn parse(&self, path: &Path, content: &str) -> Option<Parsed>;
+
+	/// Formats the document at `path`, parsed by [Services::parse], with `configuration`
+	fn format(
+		&self,
+		path: &Path,
+		parsed: &Parsed,
+		configuration: &FormatConfiguration,
+	) -> Result<Self::Formatted, Self::FormatError>;
+}
+
+/// The syntax tree of a document and its syntax errors
+#[derive(Clone)]
+pub struct Parsed {
+	root: SyntaxNode,
+	diagnostics: Vec<Diagnostic>,
+	/// The handler that parsed the document, see [Parsed::handler]
+	handler: Option<Arc<dyn ExtensionHandler>>,
+}
+
+impl Debug for Parsed {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Parsed")
+			.field("root", &self.root)
+			.field("diagnostics", &self.diagnostics)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Parsed {
+	pub fn new(root: SyntaxNode, diagnostics: Vec<Diagnostic>) -> Self {
+		Self {
+			root,
+			diagnostics,
+			handler: None,
+		}
+	}
+
+	/// Records that `handler` parsed the document
+	pub fn with_handler(mut self, handler: Arc<dyn ExtensionHandler>) -> Self {
+		self.handler = Some(handler);
+		self
+	}
+
+	/// The handler that parsed the document, which formats it too. It's resolved once, when the
+	/// document is parsed: a script without extension isn't sniffed again from its tree
+	pub fn handler(&self) -> Option<&dyn ExtensionHandler> {
+		self.handler.as_deref()
+	}
+
+	pub fn root(&self) -> &SyntaxNode {
+		&self.root
+	}
+
+	/// The syntax errors of the document. Their ranges point into the content of the document
+	pub fn diagnostics(&self) -> &[Diagnostic] {
+		&self.diagnostics
+	}
+}
+
+/// A change of the content of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentChange {
+	/// The range of the content that `text` replaces, or `None` to replace the whole content
+	pub range: Option<TextRange>,
+	pub text: String,
+}
+
+/// An operation of the workspace that failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkspaceError {
+	/// The document isn't open in the workspace
+	NotOpen(PathBuf),
+	/// The language of the document can't be parsed
+	Unsupported(PathBuf),
+	/// The range of a change isn't inside of the document
+	InvalidChange { path: PathBuf, range: TextRange },
+}
+
+impl Display for WorkspaceError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			WorkspaceError::NotOpen(path) => write!(f, "{} isn't open", path.display()),
+			WorkspaceError::Unsupported(path) => {
+				write!(f, "the language of {} isn't supported", path.display())
+			}
+			WorkspaceError::InvalidChange { path, range } => {
+				write!(f, "the range {:?} is outside of {}", range, path.display())
+			}
+		}
+	}
+}
+
+struct Document {
+	content: String,
+	version: i32,
+	/// The syntax tree of the current content, once it has been parsed. `None` inside of the
+	/// cell if the language can't be parsed
+	parsed: RefCell<Option<Option<Parsed>>>,
+}
+
+pub struct Workspace<S> {
+	services: S,
+	settings: ProjectSettings,
+	documents: HashMap<PathBuf, Document>,
+}
+
+impl<S: Services> Workspace<S> {
+	pub fn new(services: S, settings: ProjectSettings) -> Self {
+		Self {
+			services,
+			settings,
+			documents: HashMap::new(),
+		}
+	}
+
+	pub fn settings(&self) -> &ProjectSettings {
+		&self.settings
+	}
+
+	/// Replaces the settings of the project, like when its configuration file changes
+	pub fn set_settings(&mut self, settings: ProjectSettings) {
+		self.settings = settings;
+	}
+
+	/// Opens the document at `path`, or replaces it if it's already open
+	pub fn open_file(&mut self, path: impl Into<PathBuf>, content: String, version: i32) {
+		self.documents.insert(
+			path.into(),
+			Document {
+				content,
+				version,
+				parsed: RefCell::new(None),
+			},
+		);
+	}
+
+	/// Applies `changes` to the document at `path`, in order: the range of a change points
+	/// into the content left by the previous changes.
+	///
+	/// The document is left untouched if one of the changes is invalid.
+	pub fn change_file(
+		&mut self,
+		path: &Path,
+		version: i32,
+		changes: Vec<DocumentChange>,
+	) -> Result<(), WorkspaceError> {
+		let document = self
+			.documents
+			.get_mut(path)
+			.ok_or_else(|| WorkspaceError::NotOpen(path.to_path_buf()))?;
+
+		let mut content = document.content.clone();
+		for change in changes {
+			match change.range {
+				Some(range) => {
+					let is_valid = range.end() <= TextSize::of(content.as_str())
+						&& content.is_char_boundary(usize::from(range.start()))
+						&& content.is_char_boundary(usize::from(range.end()));
+					if !is_valid {
+						return Err(WorkspaceError::InvalidChange {
+							path: path.to_path_buf(),
+							range,
+						});
+					}
+					content.replace_range(std::ops::Range::<usize>::from(range), &change.text);
+				}
+				None => content = change.text,
+			}
+		}
+
+		document.content = content;
+		document.version = version;
+		document.parsed = RefCell::new(None);
+		Ok(())
+	}
+
+	/// Closes the document at `path`, and forgets its content
+	pub fn close_file(&mut self, path: &Path) -> Result<(), WorkspaceError> {
+		self.documents
+			.remove(path)
+			.map(|_| ())
+			.ok_or_else(|| WorkspaceError::NotOpen(path.to_path_buf()))
+	}
+
+	/// The content of the document at `path`
+	pub fn content(&self, path: &Path) -> Result<&str, WorkspaceError> {
+		Ok(self.document(path)?.content.as_str())
+	}
+
+	/// The version of the document at `path`, as given when it was opened or changed
+	pub fn version(&self, path: &Path) -> Result<i32, WorkspaceError> {
+		Ok(self.document(path)?.version)
+	}
+
+	/// Formats the document at `path` with the settings of the project
+	pub fn format_file(&self, path: &Path) -> Result<S::Formatted, S::FormatError> {
+		let configuration = self.settings.format_configuration(path);
+		self.format_file_with(path, &configuration)
+	}
+
+	/// Formats the document at `path` with `configuration` instead of the settings of the
+	/// project, like when the command line overrides them
+	pub fn format_file_with(
+		&self,
+		path: &Path,
+		configuration: &FormatConfiguration,
+	) -> Result<S::Formatted, S::FormatError> {
+		self.with_parsed(path, |parsed| match parsed {
+			Some(parsed) => self.services.format(path, parsed, configuration),
+			None => Err(WorkspaceError::Unsupported(path.to_path_buf()).into()),
+		})?
+	}
+
+	/// The syntax errors of the document at `path`. The documents whose language can't be
+	/// parsed don't have any
+	pub fn pull_diagnostics(&self, path: &Path) -> Result<Vec<Diagnostic>, WorkspaceError> {
+		self.with_parsed(path, |parsed| {
+			parsed.map_or_else(Vec::new, |parsed| parsed.diagnostics().to_vec())
+		})
+	}
+
+	/// Calls `f` with the syntax tree of the document at `path`, parsing it if it isn't cached
+	fn with_parsed<T>(
+		&self,
+		path: &Path,
+		f: impl FnOnce(Option<&Parsed>) -> T,
+	) -> Result<T, WorkspaceError> {
+		let document = self.document(path)?;
+		let mut parsed = document.parsed.borrow_mut();
+		let parsed = parsed.get_or_insert_with(|| self.services.parse(path, &document.content));
+		Ok(f(parsed.as_ref()))
+	}
+
+	fn document(&self, path: &Path) -> Result<&Document, WorkspaceError> {
+		self.documents
+			.get(path)
+			.ok_or_else(|| WorkspaceError::NotOpen(path.to_path_buf()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{DocumentChange, Parsed, Services, Workspace, WorkspaceError};
+	use crate::configuration::{FormatConfiguration, ProjectSettings};
+	use rslint_parser::{parse_text, TextRange, TextSize};
+	use std::{cell::Cell, path::Path};
+
+	/// Parses JavaScript files and "formats" them by printing their tree
+	#[derive(Default)]
+	struct TestServices {
+		parse_count: Cell<usize>,
+	}
+
+	impl Services for TestServices {
+		type Formatted = String;
+		type FormatError = WorkspaceError;
+
+		fn parse(&self, path: &Path, content: &str) -> Option<Parsed> {
+			if path.extension()? != "js" {
+				return None;
+			}
+			self.parse_count.set(self.parse_count.get() + 1);
+			let parse = parse_text(content, 0);
+			Some(Parsed::new(parse.syntax(), parse.errors().to_vec()))
+		}
+
+		fn format(
+			&self,
+			_path: &Path,
+			parsed: &Parsed,
+			_configuration: &FormatConfiguration,
+		) -> Result<String, WorkspaceError> {
+			Ok(parsed.root().to_string())
+		}
+	}
+
+	fn range(start: u32, end: u32) -> Option<TextRange> {
+		Some(TextRange::new(TextSize::from(start), TextSize::from(end)))
+	}
+
+	#[test]
+	fn applies_the_changes_in_order() {
+		let mut workspace = Workspace::new(TestServices::default(), ProjectSettings::default());
+		let path = Path::new("index.js");
+		workspace.open_file(path, "let a = 1;".to_string(), 1);
+
+		let changes = vec![
+			DocumentChange {
+				range: range(4, 5),
+				text: "value".to_string(),
+			},
+			DocumentChange {
+				range: range(12, 13),
+				text: "2".to_string(),
+			},
+		];
+		workspace.change_file(path, 2, changes).unwrap();
+		assert_eq!(workspace.content(path), Ok("let value = 2;"));
+		assert_eq!(workspace.version(path), Ok(2));
+
+		let invalid = vec![DocumentChange {
+			range: range(10, 20),
+			text: String::new(),
+		}];
+		assert!(workspace.change_file(path, 3, invalid).is_err());
+		assert_eq!(workspace.content(path), Ok("let value = 2;"));
+
+		let replace = vec![DocumentChange {
+			range: None,
+			text: "let b;".to_string(),
+		}];
+		workspace.change_file(path, 4, replace).unwrap();
+		assert_eq!(workspace.content(path), Ok("let b;"));
+
+		workspace.close_file(path).unwrap();
+		assert_eq!(
+			workspace.content(path),
+			Err(WorkspaceError::NotOpen(path.to_path_buf()))
+		);
+	}
+
+	#[test]
+	fn caches_the_parse_until_the_document_changes() {
+		let mut workspace = Workspace::new(TestServices::default(), ProjectSettings::default());
+		let path = Path::new("index.js");
+		workspace.open_file(path, "let a = ;".to_string(), 1);
+
+		assert_eq!(workspace.pull_diagnostics(path).unwrap().len(), 1);
+		assert_eq!(workspace.format_file(path), Ok("let a = ;".to_string()));
+		assert_eq!(workspace.services.parse_count.get(), 1);
+
+		let changes = vec![DocumentChange {
+			range: range(8, 8),
+			text: "1".to_string(),
+		}];
+		workspace.change_file(path, 2, changes).unwrap();
+		assert!(workspace.pull_diagnostics(path).unwrap().is_empty());
+		assert_eq!(workspace.format_file(path), Ok("let a = 1;".to_string()));
+		assert_eq!(workspace.services.parse_count.get(), 2);
+	}
+
+	#[test]
+	fn reports_the_documents_that_cannot_be_parsed() {
+		let mut workspace = Workspace::new(TestServices::default(), ProjectSettings::default());
+		let path = Path::new("notes.txt");
+		workspace.open_file(path, "some notes".to_string(), 1);
+
+		assert!(workspace.pull_diagnostics(path).unwrap().is_empty());
+		assert_eq!(
+			workspace.format_file(path),
+			Err(WorkspaceError::Unsupported(path.to_path_buf()))
+		);
+	}
+}
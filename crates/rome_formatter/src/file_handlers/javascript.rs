@@ -0,0 +1,101 @@
+use crate::{FormatOptions, FormatResult, Formatted, Formatter};
+use rome_core::{
+	configuration::FormatConfiguration,
+	file_handlers::{Capabilities, ExtensionHandler, Language, Mime},
+	package::PackageType,
+	workspace::Parsed,
+};
+use rslint_parser::{parse_with_syntax, FileKind, Syntax};
+use std::path::Path;
+
+/// The handler of the JavaScript and TypeScript files. The syntax of the handler decides how the
+/// files are parsed: as scripts, modules or TypeScript
+#[derive(Debug, PartialEq, Eq)]
+pub struct JsFileHandler {
+	pub syntax: Syntax,
+	/// The files use JSX, which the parser doesn't support yet. They are recognized, but they
+	/// aren't formatted nor linted
+	pub jsx: bool,
+	/// The files are modules or scripts depending on the `type` of their package, like `.js` files
+	pub follows_package_type: bool,
+}
+
+impl JsFileHandler {
+	pub fn new(file_kind: FileKind) -> Self {
+		Self {
+			syntax: Syntax::new(file_kind),
+			jsx: false,
+			follows_package_type: false,
+		}
+	}
+
+	pub fn jsx(mut self) -> Self {
+		self.jsx = true;
+		self
+	}
+
+	pub fn follows_package_type(mut self) -> Self {
+		self.follows_package_type = true;
+		self
+	}
+}
+
+impl Default for JsFileHandler {
+	fn default() -> Self {
+		Self::new(FileKind::Script)
+	}
+}
+
+impl ExtensionHandler for JsFileHandler {
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			format: !self.jsx,
+			lint: !self.jsx,
+		}
+	}
+
+	fn language(&self) -> Language {
+		match self.syntax.file_kind {
+			FileKind::TypeScript => Language::Ts,
+			FileKind::Script | FileKind::Module => Language::Js,
+		}
+	}
+
+	fn mime(&self) -> Mime {
+		Mime::Javascript
+	}
+
+	fn may_use_tabs(&self) -> bool {
+		true
+	}
+
+	fn syntax(&self) -> Syntax {
+		self.syntax
+	}
+
+	fn syntax_in_package(&self, package_type: PackageType) -> Syntax {
+		match package_type {
+			_ if !self.follows_package_type => self.syntax,
+			PackageType::Module => self.syntax.module(),
+			PackageType::CommonJs => self.syntax.script(),
+		}
+	}
+
+	fn depends_on_package_type(&self) -> bool {
+		self.follows_package_type
+	}
+
+	fn parse(&self, content: &str, syntax: Syntax) -> Option<Parsed> {
+		let parse = parse_with_syntax(content, 0, syntax);
+		Some(Parsed::new(parse.syntax(), parse.errors().to_vec()))
+	}
+
+	fn format(
+		&self,
+		_path: &Path,
+		parsed: &Parsed,
+		configuration: &FormatConfiguration,
+	) -> FormatResult<Formatted> {
+		Formatter::new(FormatOptions::from(configuration)).format_root(parsed.root())
+	}
+}
@@ -0,0 +1,80 @@
+use crate::format_json::{parse_json_dialect, tokenize_json_root};
+use crate::{format_element, json_options, FormatError, FormatOptions, FormatResult, Formatted};
+use rome_core::{
+	configuration::FormatConfiguration,
+	file_handlers::{json::JsonDialect, Capabilities, ExtensionHandler, Language, Mime},
+	workspace::Parsed,
+};
+use rslint_parser::Syntax;
+use std::path::Path;
+
+/// The handler of the JSON files. The dialect of the handler decides which syntax the files may
+/// use: plain JSON, JSON with comments or JSON5
+#[derive(Debug, PartialEq, Eq)]
+pub struct JsonFileHandler {
+	pub dialect: JsonDialect,
+}
+
+impl JsonFileHandler {
+	pub fn new(dialect: JsonDialect) -> Self {
+		Self { dialect }
+	}
+}
+
+impl Default for JsonFileHandler {
+	fn default() -> Self {
+		Self::new(JsonDialect::Json)
+	}
+}
+
+impl ExtensionHandler for JsonFileHandler {
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			format: true,
+			lint: true,
+		}
+	}
+
+	fn language(&self) -> Language {
+		match self.dialect {
+			JsonDialect::Json => Language::Json,
+			JsonDialect::Jsonc => Language::Jsonc,
+			JsonDialect::Json5 => Language::Json5,
+		}
+	}
+
+	fn mime(&self) -> Mime {
+		match self.dialect {
+			JsonDialect::Json | JsonDialect::Jsonc => Mime::Json,
+			JsonDialect::Json5 => Mime::Json5,
+		}
+	}
+
+	fn may_use_tabs(&self) -> bool {
+		true
+	}
+
+	/// The diagnostics include the usages of syntax that isn't supported by the dialect, and the
+	/// tree is the one of the JSON value wrapped in parentheses
+	fn parse(&self, content: &str, _syntax: Syntax) -> Option<Parsed> {
+		let (root, diagnostics) = parse_json_dialect(content, self.dialect);
+		Some(Parsed::new(root, diagnostics))
+	}
+
+	/// Formats a JSON file. The files with syntax errors aren't formatted
+	fn format(
+		&self,
+		path: &Path,
+		parsed: &Parsed,
+		configuration: &FormatConfiguration,
+	) -> FormatResult<Formatted> {
+		if !parsed.diagnostics().is_empty() {
+			return Err(FormatError::SyntaxError(parsed.diagnostics().to_vec()));
+		}
+
+		let options = FormatOptions::from(configuration);
+		let json_options = json_options(path, &options);
+		let element = tokenize_json_root(parsed.root(), &json_options);
+		Ok(format_element(&element, options))
+	}
+}
@@ -0,0 +1,139 @@
+//! The handlers of the languages that Rome formats out of the box: JavaScript, TypeScript and the
+//! JSON dialects.
+use javascript::JsFileHandler;
+use json::JsonFileHandler;
+use rome_core::{file_handlers::json::JsonDialect, App};
+use rslint_parser::FileKind;
+
+pub mod javascript;
+pub mod json;
+
+/// Creates an [App] with the handlers of the languages supported by Rome
+pub fn create_app() -> App {
+	let mut app = App::new();
+
+	app.register_extension(
+		"js",
+		Box::new(JsFileHandler::new(FileKind::Script).follows_package_type()),
+	);
+	app.register_extension("cjs", Box::new(JsFileHandler::new(FileKind::Script)));
+	app.register_extension("mjs", Box::new(JsFileHandler::new(FileKind::Module)));
+	app.register_extension("jsx", Box::new(JsFileHandler::new(FileKind::Module).jsx()));
+	app.register_extension("ts", Box::new(JsFileHandler::new(FileKind::TypeScript)));
+	app.register_extension("mts", Box::new(JsFileHandler::new(FileKind::TypeScript)));
+	app.register_extension("cts", Box::new(JsFileHandler::new(FileKind::TypeScript)));
+	app.register_extension("d.ts", Box::new(JsFileHandler::new(FileKind::TypeScript)));
+	app.register_extension(
+		"tsx",
+		Box::new(JsFileHandler::new(FileKind::TypeScript).jsx()),
+	);
+	app.register_extension("json", Box::new(JsonFileHandler::new(JsonDialect::Json)));
+	app.register_extension("jsonc", Box::new(JsonFileHandler::new(JsonDialect::Jsonc)));
+	app.register_extension("json5", Box::new(JsonFileHandler::new(JsonDialect::Json5)));
+
+	app.register_file_name(
+		".eslintrc",
+		Box::new(JsonFileHandler::new(JsonDialect::Jsonc)),
+	);
+	app.register_file_name(
+		".eslintrc.json",
+		Box::new(JsonFileHandler::new(JsonDialect::Jsonc)),
+	);
+	app.register_file_name(
+		".babelrc",
+		Box::new(JsonFileHandler::new(JsonDialect::Json5)),
+	);
+	app.register_file_name(
+		"tsconfig.json",
+		Box::new(JsonFileHandler::new(JsonDialect::Jsonc)),
+	);
+	app.register_file_name(
+		"jsconfig.json",
+		Box::new(JsonFileHandler::new(JsonDialect::Jsonc)),
+	);
+
+	app.register_interpreter("node", Box::new(JsFileHandler::new(FileKind::Script)));
+	app.register_interpreter("nodejs", Box::new(JsFileHandler::new(FileKind::Script)));
+	app.register_interpreter(
+		"ts-node",
+		Box::new(JsFileHandler::new(FileKind::TypeScript)),
+	);
+
+	app
+}
+
+#[cfg(test)]
+mod test {
+	use super::create_app;
+	use rome_core::file_handlers::Language;
+	use rslint_parser::FileKind;
+	use std::path::Path;
+
+	fn file_kind(path: &str) -> Option<FileKind> {
+		let app = create_app();
+		let handler = app.get_handler_for_path(Path::new(path))?;
+		match handler.language() {
+			Language::Js | Language::Ts => Some(handler.syntax().file_kind),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn resolves_the_handlers_of_the_extensions() {
+		assert_eq!(file_kind("src/index.js"), Some(FileKind::Script));
+		assert_eq!(file_kind("src/index.cjs"), Some(FileKind::Script));
+		assert_eq!(file_kind("src/index.mjs"), Some(FileKind::Module));
+		assert_eq!(file_kind("src/index.mts"), Some(FileKind::TypeScript));
+		assert_eq!(file_kind("src/index.test.ts"), Some(FileKind::TypeScript));
+		assert_eq!(file_kind("types/index.d.ts"), Some(FileKind::TypeScript));
+		assert_eq!(file_kind("data.json"), None);
+
+		let app = create_app();
+		let jsx = app.get_handler_for_path(Path::new("App.jsx")).unwrap();
+		assert!(!jsx.capabilities().format, "the parser doesn't support JSX");
+
+		let unknown = app.get_handler_for_path(Path::new("notes.txt")).unwrap();
+		assert!(matches!(unknown.language(), Language::Unknown));
+		assert!(app.get_handler_for_path(Path::new("Makefile")).is_none());
+	}
+
+	#[test]
+	fn resolves_the_handlers_of_the_well_known_files() {
+		let app = create_app();
+		let language = |path: &str| {
+			app.get_handler_for_path(Path::new(path))
+				.unwrap()
+				.language()
+		};
+
+		assert!(matches!(language("project/tsconfig.json"), Language::Jsonc));
+		assert!(matches!(language("project/.eslintrc"), Language::Jsonc));
+		assert!(matches!(language("project/.babelrc"), Language::Json5));
+		assert!(matches!(language("project/package.json"), Language::Json));
+		assert!(matches!(language(".hidden.js"), Language::Js));
+	}
+
+	#[test]
+	fn resolves_the_handlers_of_the_shebangs() {
+		let app = create_app();
+		let language = |content: &str| {
+			app.get_handler_for_content(content)
+				.map(|handler| handler.language())
+		};
+
+		assert!(matches!(
+			language("#!/usr/bin/env node\nconsole.log(1);"),
+			Some(Language::Js)
+		));
+		assert!(matches!(
+			language("#!/usr/local/bin/node"),
+			Some(Language::Js)
+		));
+		assert!(matches!(
+			language("#!/usr/bin/env -S NODE_ENV=production ts-node --esm"),
+			Some(Language::Ts)
+		));
+		assert!(language("#!/bin/sh\necho 1").is_none());
+		assert!(language("console.log(1);").is_none());
+	}
+}
@@ -1,5 +1,6 @@
 use crate::format_elements;
 use crate::intersperse::Intersperse;
+use rslint_parser::TextSize;
 use std::ops::Deref;
 
 type Content = Box<FormatElement>;
@@ -607,6 +608,10 @@ pub enum FormatElement {
 
 	/// A token that should be printed as is, see [token] for documentation and examples.
 	Token(Token),
+
+	/// Marks where a node of the source code starts or ends. It doesn't print anything, the
+	/// [crate::Printer] records where the position ends up in the output, see [crate::SourceMarker].
+	SourcePosition(SourcePosition),
 }
 
 /// Inserts a new line
@@ -708,21 +713,61 @@ impl ConditionalGroupContent {
 }
 
 /// See [token] for documentation
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct Token(String);
+#[derive(Debug, Clone)]
+pub struct Token {
+	text: String,
+
+	/// The offset of the token in the source code, if the token comes from the source code.
+	/// The [Printer] records where these tokens end up in the output, see [crate::SourceMarker].
+	source_position: Option<TextSize>,
+}
 
 impl Token {
 	pub fn new(content: &str) -> Self {
 		debug_assert!(!content.contains('\r'), "The content '{}' contains a carriage return '\\r' character but string tokens must only use line feeds '\\n' as line separator. Use '\\n' instead of '\\r' and '\\r\\n' to insert a line break in strings.", content);
-		Self(String::from(content))
+		Self {
+			text: String::from(content),
+			source_position: None,
+		}
+	}
+
+	/// Creates a token for text that starts at `position` in the source code
+	pub fn new_with_source(content: &str, position: TextSize) -> Self {
+		Self {
+			source_position: Some(position),
+			..Self::new(content)
+		}
+	}
+
+	/// The offset of the token in the source code, if the token comes from the source code
+	pub fn source_position(&self) -> Option<TextSize> {
+		self.source_position
 	}
 }
 
+/// The start or the end of a node in the source code, without its leading or trailing trivia
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SourcePosition {
+	/// Ends up where the next token is printed
+	Start(TextSize),
+	/// Ends up right after the last printed token
+	End(TextSize),
+}
+
+// Two tokens with the same text are equal, wherever they come from in the source code
+impl PartialEq for Token {
+	fn eq(&self, other: &Self) -> bool {
+		self.text == other.text
+	}
+}
+
+impl Eq for Token {}
+
 impl Deref for Token {
 	type Target = String;
 
 	fn deref(&self) -> &Self::Target {
-		&self.0
+		&self.text
 	}
 }
 
@@ -757,6 +802,12 @@ impl From<Line> for FormatElement {
 	}
 }
 
+impl From<SourcePosition> for FormatElement {
+	fn from(position: SourcePosition) -> Self {
+		FormatElement::SourcePosition(position)
+	}
+}
+
 impl From<Indent> for FormatElement {
 	fn from(token: Indent) -> Self {
 		FormatElement::Indent(token)
@@ -1,27 +1,170 @@
-use crate::format_element::{join_elements, soft_line_break_or_space};
+use crate::format_element::{join_elements, soft_line_break_or_space, Token};
 use crate::{
-	format_element::FormatElement, format_elements, group_elements, hard_line_break, soft_indent,
-	space_token, token,
+	block_indent, format_element::FormatElement, format_elements, group_elements, hard_line_break,
+	soft_indent, space_token, token,
 };
+use rome_core::configuration::ObjectWrap;
+use rome_core::file_handlers::json::JsonDialect;
+use rslint_errors::Diagnostic;
 use rslint_parser::ast::{
-	JsArrayExpression, JsBooleanLiteral, JsNullLiteral, JsNumberLiteral, JsParenthesizedExpression,
-	JsStringLiteral, JsUnaryExpression, LiteralProp, ObjectExpr, ObjectProp,
+	JsAnyExpression, JsArrayExpression, JsBooleanLiteral, JsNullLiteral, JsNumberLiteral,
+	JsParenthesizedExpression, JsRoot, JsStringLiteral, JsUnaryExpression, LiteralProp, ObjectExpr,
+	ObjectProp,
 };
-use rslint_parser::{parse_text, AstNode, SyntaxKind, SyntaxNode, SyntaxNodeExt, SyntaxToken};
+use rslint_parser::util::contains_js_linebreak;
+use rslint_parser::{
+	parse_text, AstNode, Parse, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxNodeExt, SyntaxToken,
+	TextSize,
+};
+use std::cmp::Ordering;
+
+/// Options that only apply to the formatting of JSON files
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct JsonFormatOptions {
+	/// In which order the properties of objects are printed
+	pub key_order: JsonKeyOrder,
+
+	/// Whether objects and arrays that fit on a single line are collapsed
+	pub object_wrap: ObjectWrap,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum JsonKeyOrder {
+	/// Keeps the properties in the order in which they're written
+	Preserve,
+	/// Sorts the properties of all objects alphabetically
+	Sorted,
+	/// Sorts the top level properties in the conventional order of a `package.json` file
+	/// (`name`, `version`, `description`, ...). The dependency maps are sorted alphabetically,
+	/// all other nested objects keep their order because it can be significant (e.g. `exports`).
+	PackageJson,
+}
+
+impl Default for JsonKeyOrder {
+	fn default() -> Self {
+		Self::Preserve
+	}
+}
+
+impl JsonKeyOrder {
+	/// The order to use for the value of the property with the given (unquoted) key
+	fn nested(&self, key: &str) -> JsonKeyOrder {
+		match self {
+			JsonKeyOrder::PackageJson if PACKAGE_JSON_SORTED_FIELDS.contains(&key) => {
+				JsonKeyOrder::Sorted
+			}
+			JsonKeyOrder::PackageJson => JsonKeyOrder::Preserve,
+			order => *order,
+		}
+	}
+
+	fn compare(&self, left: &str, right: &str) -> Ordering {
+		match self {
+			JsonKeyOrder::Preserve => Ordering::Equal,
+			JsonKeyOrder::Sorted => left.cmp(right),
+			JsonKeyOrder::PackageJson => {
+				let position = |key: &str| {
+					PACKAGE_JSON_KEY_ORDER
+						.iter()
+						.position(|known| *known == key)
+						.unwrap_or(PACKAGE_JSON_KEY_ORDER.len())
+				};
+
+				position(left)
+					.cmp(&position(right))
+					.then_with(|| left.cmp(right))
+			}
+		}
+	}
+}
+
+/// The conventional order of the top level fields of a `package.json` file.
+/// Fields that aren't part of the list are printed after, in alphabetical order.
+const PACKAGE_JSON_KEY_ORDER: &[&str] = &[
+	"$schema",
+	"name",
+	"displayName",
+	"version",
+	"private",
+	"description",
+	"categories",
+	"keywords",
+	"homepage",
+	"bugs",
+	"repository",
+	"funding",
+	"license",
+	"author",
+	"maintainers",
+	"contributors",
+	"publisher",
+	"sideEffects",
+	"type",
+	"imports",
+	"exports",
+	"main",
+	"module",
+	"browser",
+	"types",
+	"typesVersions",
+	"typings",
+	"bin",
+	"man",
+	"directories",
+	"files",
+	"workspaces",
+	"scripts",
+	"config",
+	"resolutions",
+	"dependencies",
+	"devDependencies",
+	"peerDependencies",
+	"peerDependenciesMeta",
+	"optionalDependencies",
+	"bundledDependencies",
+	"bundleDependencies",
+	"packageManager",
+	"engines",
+	"os",
+	"cpu",
+	"publishConfig",
+];
 
+/// The fields of a `package.json` file whose properties can be sorted alphabetically
+const PACKAGE_JSON_SORTED_FIELDS: &[&str] = &[
+	"resolutions",
+	"dependencies",
+	"devDependencies",
+	"peerDependencies",
+	"peerDependenciesMeta",
+	"optionalDependencies",
+	"engines",
+];
+
+/// Creates a token for a value or key that remembers its position in the source code, without the
+/// opening parenthesis of [parse_json]
 fn tokenize_token(syntax_token: SyntaxToken) -> FormatElement {
 	match syntax_token.kind() {
-		SyntaxKind::NULL_KW => token("null"),
-		SyntaxKind::TRUE_KW => token("true"),
-		SyntaxKind::FALSE_KW => token("false"),
-		SyntaxKind::JS_STRING_LITERAL_TOKEN => token(syntax_token.text()),
-		SyntaxKind::JS_NUMBER_LITERAL_TOKEN => token(syntax_token.text()),
-		SyntaxKind::MINUS => token("-"),
+		SyntaxKind::NULL_KW
+		| SyntaxKind::TRUE_KW
+		| SyntaxKind::FALSE_KW
+		| SyntaxKind::JS_STRING_LITERAL_TOKEN
+		| SyntaxKind::JS_NUMBER_LITERAL_TOKEN
+		| SyntaxKind::IDENT
+		| SyntaxKind::MINUS
+		| SyntaxKind::PLUS => FormatElement::Token(Token::new_with_source(
+			syntax_token.text(),
+			syntax_token.text_range().start() - TextSize::from(1),
+		)),
 		_ => panic!("Unsupported JSON token {:?}", syntax_token),
 	}
 }
 
-fn tokenize_node(node: SyntaxNode) -> FormatElement {
+fn tokenize_node(
+	node: SyntaxNode,
+	options: &JsonFormatOptions,
+	key_order: JsonKeyOrder,
+) -> FormatElement {
 	match node.kind() {
 		SyntaxKind::JS_STRING_LITERAL => {
 			tokenize_token(node.to::<JsStringLiteral>().value_token().unwrap())
@@ -35,88 +178,612 @@ fn tokenize_node(node: SyntaxNode) -> FormatElement {
 		SyntaxKind::JS_NUMBER_LITERAL => {
 			tokenize_token(node.to::<JsNumberLiteral>().value_token().unwrap())
 		}
+		// Unquoted keys and `Infinity`/`NaN` of JSON5
+		SyntaxKind::NAME | SyntaxKind::JS_REFERENCE_IDENTIFIER_EXPRESSION => {
+			tokenize_token(node.first_lossy_token().unwrap())
+		}
 		SyntaxKind::JS_UNARY_EXPRESSION => {
 			let expr = JsUnaryExpression::cast(node).unwrap();
 			format_elements![
 				tokenize_token(expr.operator().unwrap()),
-				tokenize_node(expr.argument().unwrap().syntax().clone())
+				tokenize_node(
+					expr.argument().unwrap().syntax().clone(),
+					options,
+					key_order
+				)
 			]
 		}
 
 		SyntaxKind::LITERAL_PROP => {
 			let prop = LiteralProp::cast(node).unwrap();
+			let key = prop.key().unwrap();
+
+			// Comments between the key and the value are moved after the colon
+			let comments = prop
+				.syntax()
+				.children_with_tokens()
+				.filter_map(|element| element.into_token())
+				.filter(|token| token.kind() == SyntaxKind::COMMENT)
+				.map(|comment| {
+					let separator = if is_line_comment(&comment) {
+						hard_line_break()
+					} else {
+						space_token()
+					};
+					format_elements![tokenize_comment(&comment), separator]
+				});
+
 			format_elements![
-				tokenize_node(prop.key().unwrap().syntax().clone()),
+				tokenize_node(key.syntax().clone(), options, key_order),
 				token(":"),
 				space_token(),
-				tokenize_node(prop.value().unwrap().syntax().clone()),
+				crate::concat_elements(comments),
+				tokenize_node(
+					prop.value().unwrap().syntax().clone(),
+					options,
+					key_order.nested(&key_value(key.syntax()))
+				),
 			]
 		}
 
 		SyntaxKind::OBJECT_EXPR => {
 			let object = ObjectExpr::cast(node).unwrap();
+			tokenize_container(object.syntax(), token("{"), token("}"), options, key_order)
+		}
+		SyntaxKind::JS_ARRAY_EXPRESSION => {
+			let array = JsArrayExpression::cast(node).unwrap();
+			tokenize_container(array.syntax(), token("["), token("]"), options, key_order)
+		}
+		_ => panic!("Unsupported JSON kind: {:?}", node.kind()),
+	}
+}
 
-			let separator = format_elements![token(","), soft_line_break_or_space()];
+/// An element of an object or array together with the comments that belong to it
+struct ContainerEntry {
+	/// Comments on the lines before the element
+	leading_comments: Vec<SyntaxToken>,
+	node: SyntaxNode,
+	/// Comments on the same line after the element
+	trailing_comments: Vec<SyntaxToken>,
+}
 
-			let properties_list: Vec<FormatElement> = object
-				.props()
-				.iter()
-				.map(|prop| match prop {
-					ObjectProp::LiteralProp(prop) => {
-						format_elements![tokenize_node(prop.syntax().clone())]
-					}
-					_ => panic!("Unsupported prop type {:?}", prop),
-				})
-				.collect();
+/// Returns the value of a property key without its quotes
+pub(crate) fn key_value(key: &SyntaxNode) -> String {
+	let text = key.text_trimmed().to_string();
 
-			let properties = join_elements(separator, properties_list);
+	match key.kind() {
+		SyntaxKind::JS_STRING_LITERAL => text[1..text.len() - 1].to_string(),
+		_ => text,
+	}
+}
 
-			group_elements(format_elements![
-				token("{"),
-				soft_indent(properties),
-				token("}"),
-			])
+/// Formats the properties of an object or the elements of an array, preserving
+/// the comments in between them. The properties of objects are sorted according to the `key_order`,
+/// moving the comments of a property together with it.
+///
+/// The container is always expanded if it contains a line comment because the comment
+/// must be followed by a line break.
+fn tokenize_container(
+	node: &SyntaxNode,
+	open: FormatElement,
+	close: FormatElement,
+	options: &JsonFormatOptions,
+	key_order: JsonKeyOrder,
+) -> FormatElement {
+	let mut entries: Vec<ContainerEntry> = Vec::new();
+	let mut pending_comments = Vec::new();
+	let mut new_line_since_last_entry = false;
+	let mut starts_with_line_break = false;
+
+	let children = node.children_with_tokens().flat_map(|child| match child {
+		// The elements are stored in a list, with the comments and separators in between
+		SyntaxElement::Node(list) if list.kind() == SyntaxKind::LIST => {
+			list.children_with_tokens().collect::<Vec<_>>()
 		}
-		SyntaxKind::JS_ARRAY_EXPRESSION => {
-			let array = JsArrayExpression::cast(node).unwrap();
+		child => vec![child],
+	});
+
+	for child in children {
+		match child {
+			SyntaxElement::Node(node) => {
+				entries.push(ContainerEntry {
+					leading_comments: std::mem::take(&mut pending_comments),
+					node,
+					trailing_comments: Vec::new(),
+				});
+				new_line_since_last_entry = false;
+			}
+			SyntaxElement::Token(token) => match token.kind() {
+				SyntaxKind::WHITESPACE => {
+					let is_line_break = contains_js_linebreak(token.text());
+					new_line_since_last_entry |= is_line_break;
+
+					if entries.is_empty() && pending_comments.is_empty() {
+						starts_with_line_break |= is_line_break;
+					}
+				}
+				SyntaxKind::COMMENT => match entries.last_mut() {
+					Some(entry) if !new_line_since_last_entry && pending_comments.is_empty() => {
+						entry.trailing_comments.push(token)
+					}
+					_ => pending_comments.push(token),
+				},
+				// Brackets and commas are created by the formatter
+				_ => {}
+			},
+		}
+	}
+
+	let dangling_comments = pending_comments;
+
+	if node.kind() == SyntaxKind::OBJECT_EXPR && key_order != JsonKeyOrder::Preserve {
+		let mut keyed_entries: Vec<_> = entries
+			.into_iter()
+			.map(|entry| {
+				let key = key_value(entry.node.to::<LiteralProp>().key().unwrap().syntax());
+				(key, entry)
+			})
+			.collect();
 
-			let separator = format_elements![token(","), soft_line_break_or_space(),];
+		keyed_entries.sort_by(|(left, _), (right, _)| key_order.compare(left, right));
+		entries = keyed_entries.into_iter().map(|(_, entry)| entry).collect();
+	}
+
+	let preserve_expanded = options.object_wrap == ObjectWrap::Preserve && starts_with_line_break;
 
-			let elements = join_elements(
-				separator,
-				array
-					.elements()
+	let expanded = preserve_expanded
+		|| entries
+			.iter()
+			.flat_map(|entry| {
+				entry
+					.leading_comments
 					.iter()
-					.map(|element| tokenize_node(element.syntax().clone())),
-			);
+					.chain(&entry.trailing_comments)
+			})
+			.chain(&dangling_comments)
+			.any(is_line_comment);
 
-			group_elements(format_elements![
-				token("["),
-				soft_indent(elements),
-				token("]"),
-			])
-		}
-		_ => panic!("Unsupported JSON kind: {:?}", node.kind()),
+	let separator = if expanded {
+		hard_line_break()
+	} else {
+		soft_line_break_or_space()
+	};
+
+	let entries_count = entries.len();
+	let elements = entries.into_iter().enumerate().map(|(index, entry)| {
+		let leading = entry.leading_comments.iter().map(|comment| {
+			let separator = if is_line_comment(comment) || has_line_break_after(comment) {
+				hard_line_break()
+			} else {
+				space_token()
+			};
+			format_elements![tokenize_comment(comment), separator]
+		});
+
+		let comma = if index + 1 < entries_count {
+			token(",")
+		} else {
+			crate::empty_element()
+		};
+
+		let trailing = entry
+			.trailing_comments
+			.iter()
+			.map(|comment| format_elements![space_token(), tokenize_comment(comment)]);
+
+		format_elements![
+			crate::concat_elements(leading),
+			tokenize_node(entry.node, options, key_order),
+			comma,
+			crate::concat_elements(trailing),
+		]
+	});
+
+	let dangling = dangling_comments.iter().map(tokenize_comment);
+	let content = join_elements(separator, elements.chain(dangling));
+
+	if expanded {
+		format_elements![open, block_indent(content), close]
+	} else {
+		group_elements(format_elements![open, soft_indent(content), close])
 	}
 }
 
-pub fn tokenize_json(content: &str) -> FormatElement {
-	let script = parse_text(format!("({})", content).as_str(), 0);
+fn tokenize_comment(comment: &SyntaxToken) -> FormatElement {
+	token(comment.text().replace("\r\n", "\n").as_str())
+}
 
-	// Unwrap the grouping to get to the JSON content. The grouping is only used as a trick to parse JSON
-	let json_content = JsParenthesizedExpression::cast(
-		script
-			.syntax()
-			.descendants()
+fn is_line_comment(comment: &SyntaxToken) -> bool {
+	comment.text().starts_with("//")
+}
+
+fn has_line_break_after(token: &SyntaxToken) -> bool {
+	token.next_token().map_or(false, |next| {
+		next.kind() == SyntaxKind::WHITESPACE && contains_js_linebreak(next.text())
+	})
+}
+
+fn has_line_break_before(token: &SyntaxToken) -> bool {
+	token.prev_token().map_or(false, |previous| {
+		previous.kind() == SyntaxKind::WHITESPACE && contains_js_linebreak(previous.text())
+	})
+}
+
+/// Parses JSON content as a grouped JS expression.
+///
+/// The ranges of the returned tree are shifted by one compared to `content` because of the opening
+/// parenthesis of the grouping.
+pub(crate) fn parse_json(content: &str) -> Parse<JsRoot> {
+	// The line break makes sure that a line comment at the end of the file doesn't swallow the
+	// closing parenthesis
+	parse_text(format!("({}\n)", content).as_str(), 0)
+}
+
+/// Parses the content of a JSON file, see [parse_json].
+///
+/// Returns the tree along with the parse errors, or the usages of syntax that isn't supported by
+/// the given [JsonDialect] if there are no parse errors. Unlike the tree, the ranges of the
+/// diagnostics point into `content`.
+pub(crate) fn parse_json_dialect(
+	content: &str,
+	dialect: JsonDialect,
+) -> (SyntaxNode, Vec<Diagnostic>) {
+	let script = parse_json(content);
+	let root = script.syntax();
+
+	let diagnostics = if script.errors().is_empty() {
+		check_dialect(json_value(&root).syntax(), dialect)
+	} else {
+		script.errors().to_vec()
+	};
+
+	let diagnostics = diagnostics
+		.into_iter()
+		.map(|diagnostic| unwrap_grouping_offset(diagnostic, content.len()))
+		.collect();
+	(root, diagnostics)
+}
+
+/// The grouping of the tree returned by [parse_json]
+fn json_grouping(root: &SyntaxNode) -> JsParenthesizedExpression {
+	// The grouping is only used as a trick to parse JSON
+	JsParenthesizedExpression::cast(
+		root.descendants()
 			.find(|e| e.kind() == SyntaxKind::JS_PARENTHESIZED_EXPRESSION)
 			.unwrap(),
 	)
-	// TODO: #1725 this should be reviewed for error handling
-	.and_then(|grouping| grouping.expression().ok())
-	.unwrap();
+	.unwrap()
+}
+
+/// The JSON value of the tree returned by [parse_json]
+fn json_value(root: &SyntaxNode) -> JsAnyExpression {
+	json_grouping(root).expression().unwrap()
+}
+
+/// Converts the tree returned by [parse_json_dialect] into the formatter IR. The tree must not
+/// have any diagnostic.
+pub(crate) fn tokenize_json_root(root: &SyntaxNode, options: &JsonFormatOptions) -> FormatElement {
+	// Unwrap the grouping to get to the JSON content
+	let grouping = json_grouping(root);
+	let json_content = json_value(root);
+
+	let mut leading_comments = vec![];
+	let mut trailing_comments = vec![];
+
+	for child in grouping.syntax().children_with_tokens() {
+		if let SyntaxElement::Token(token) = child {
+			if token.kind() == SyntaxKind::COMMENT {
+				if token.text_range().end() <= json_content.syntax().text_range().start() {
+					leading_comments.push(token);
+				} else {
+					trailing_comments.push(token);
+				}
+			}
+		}
+	}
+
+	let leading = leading_comments.iter().map(|comment| {
+		let separator = if is_line_comment(comment) || has_line_break_after(comment) {
+			hard_line_break()
+		} else {
+			space_token()
+		};
+		format_elements![tokenize_comment(comment), separator]
+	});
+
+	let trailing = trailing_comments.iter().map(|comment| {
+		let separator = if has_line_break_before(comment) {
+			hard_line_break()
+		} else {
+			space_token()
+		};
+		format_elements![separator, tokenize_comment(comment)]
+	});
+
+	let tokenized_content =
+		tokenize_node(json_content.syntax().clone(), options, options.key_order);
+	format_elements![
+		crate::concat_elements(leading),
+		tokenized_content,
+		crate::concat_elements(trailing),
+		hard_line_break()
+	]
+}
+
+/// Moves the ranges of a diagnostic created for the grouped source back to the original source
+fn unwrap_grouping_offset(mut diagnostic: Diagnostic, content_len: usize) -> Diagnostic {
+	let unwrap = |range: &mut std::ops::Range<usize>| {
+		range.start = range.start.saturating_sub(1).min(content_len);
+		range.end = range.end.saturating_sub(1).min(content_len);
+	};
+
+	if let Some(primary) = diagnostic.primary.as_mut() {
+		unwrap(&mut primary.span.range);
+	}
+
+	for child in diagnostic.children.iter_mut() {
+		unwrap(&mut child.span.range);
+	}
+
+	diagnostic
+}
+
+/// Returns the diagnostics for every usage of syntax that isn't valid in the given dialect.
+fn check_dialect(value: &SyntaxNode, dialect: JsonDialect) -> Vec<Diagnostic> {
+	let mut diagnostics = vec![];
+
+	if !dialect.allows_comments() {
+		let comments = value
+			.ancestors()
+			.last()
+			.unwrap()
+			.descendants_with_tokens()
+			.filter_map(|element| element.into_token())
+			.filter(|token| token.kind() == SyntaxKind::COMMENT);
+
+		for comment in comments {
+			diagnostics.push(
+				Diagnostic::error(
+					0,
+					"SyntaxError",
+					format!("{} doesn't support comments", dialect),
+				)
+				.primary(comment.text_range(), "")
+				.footer_help("comments are allowed in JSONC and JSON5 files"),
+			);
+		}
+	}
+
+	check_value(value, dialect, &mut diagnostics);
+
+	diagnostics
+}
+
+fn check_value(value: &SyntaxNode, dialect: JsonDialect, diagnostics: &mut Vec<Diagnostic>) {
+	match value.kind() {
+		SyntaxKind::JS_BOOLEAN_LITERAL | SyntaxKind::JS_NULL_LITERAL => {}
+		SyntaxKind::JS_STRING_LITERAL => check_string(value, dialect, diagnostics),
+		SyntaxKind::JS_NUMBER_LITERAL => check_number(value, dialect, diagnostics),
+		SyntaxKind::JS_REFERENCE_IDENTIFIER_EXPRESSION
+			if dialect.allows_json5_syntax()
+				&& matches!(
+					value.text_trimmed().to_string().as_str(),
+					"Infinity" | "NaN"
+				) => {}
+		SyntaxKind::JS_UNARY_EXPRESSION => {
+			let expr = value.to::<JsUnaryExpression>();
+			let operator = expr.operator().unwrap();
+			let argument = expr.argument().unwrap();
+
+			let is_numeric = matches!(
+				argument.syntax().kind(),
+				SyntaxKind::JS_NUMBER_LITERAL | SyntaxKind::JS_REFERENCE_IDENTIFIER_EXPRESSION
+			);
+
+			match operator.kind() {
+				SyntaxKind::MINUS if is_numeric => {
+					check_value(argument.syntax(), dialect, diagnostics)
+				}
+				SyntaxKind::PLUS if is_numeric && dialect.allows_json5_syntax() => {
+					check_value(argument.syntax(), dialect, diagnostics)
+				}
+				SyntaxKind::PLUS if is_numeric => diagnostics.push(
+					Diagnostic::error(
+						0,
+						"SyntaxError",
+						format!("{} doesn't support explicitly positive numbers", dialect),
+					)
+					.primary(operator.text_range(), "")
+					.footer_help("explicitly positive numbers are allowed in JSON5 files"),
+				),
+				_ => diagnostics.push(unsupported_value(value, dialect)),
+			}
+		}
+		SyntaxKind::OBJECT_EXPR => {
+			let object = value.to::<ObjectExpr>();
+			let props = object.props();
+
+			for prop in props.iter() {
+				match prop {
+					ObjectProp::LiteralProp(prop) => {
+						check_key(prop.key().unwrap().syntax(), dialect, diagnostics);
+						check_value(prop.value().unwrap().syntax(), dialect, diagnostics);
+					}
+					prop => diagnostics.push(unsupported_value(prop.syntax(), dialect)),
+				}
+			}
+
+			if let Some(separator) = props.trailing_separator() {
+				check_trailing_comma(&separator, dialect, diagnostics);
+			}
+		}
+		SyntaxKind::JS_ARRAY_EXPRESSION => {
+			let array = value.to::<JsArrayExpression>();
+			let elements = array.elements();
+
+			for element in elements.iter() {
+				check_value(element.syntax(), dialect, diagnostics);
+			}
+
+			if let Some(separator) = elements.trailing_separator() {
+				check_trailing_comma(&separator, dialect, diagnostics);
+			}
+		}
+		_ => diagnostics.push(unsupported_value(value, dialect)),
+	}
+}
+
+fn check_key(key: &SyntaxNode, dialect: JsonDialect, diagnostics: &mut Vec<Diagnostic>) {
+	match key.kind() {
+		SyntaxKind::JS_STRING_LITERAL => check_string(key, dialect, diagnostics),
+		SyntaxKind::NAME if dialect.allows_json5_syntax() => {}
+		SyntaxKind::NAME => diagnostics.push(
+			Diagnostic::error(
+				0,
+				"SyntaxError",
+				format!("{} doesn't support unquoted property keys", dialect),
+			)
+			.primary(key.text_trimmed_range(), "")
+			.footer_help("unquoted property keys are allowed in JSON5 files"),
+		),
+		_ => diagnostics.push(
+			Diagnostic::error(
+				0,
+				"SyntaxError",
+				format!("Property keys must be strings in {}", dialect),
+			)
+			.primary(key.text_trimmed_range(), ""),
+		),
+	}
+}
 
-	let tokenized_content = tokenize_node(json_content.syntax().clone());
-	format_elements![tokenized_content, hard_line_break()]
+fn check_string(string: &SyntaxNode, dialect: JsonDialect, diagnostics: &mut Vec<Diagnostic>) {
+	if !dialect.allows_json5_syntax() && string.text_trimmed().to_string().starts_with('\'') {
+		diagnostics.push(
+			Diagnostic::error(
+				0,
+				"SyntaxError",
+				format!("{} doesn't support single quoted strings", dialect),
+			)
+			.primary(string.text_trimmed_range(), "")
+			.footer_help("single quoted strings are allowed in JSON5 files"),
+		);
+	}
+}
+
+fn check_number(number: &SyntaxNode, dialect: JsonDialect, diagnostics: &mut Vec<Diagnostic>) {
+	let text = number.text_trimmed().to_string();
+
+	if is_json_number(&text) {
+		return;
+	}
+
+	let diagnostic = if is_json5_number(&text) {
+		if dialect.allows_json5_syntax() {
+			return;
+		}
+
+		Diagnostic::error(
+			0,
+			"SyntaxError",
+			format!("{} doesn't support this number notation", dialect),
+		)
+		.primary(number.text_trimmed_range(), "")
+		.footer_help(
+			"hexadecimal numbers and leading or trailing decimal points are allowed in JSON5 files",
+		)
+	} else {
+		Diagnostic::error(0, "SyntaxError", format!("Invalid number in {}", dialect))
+			.primary(number.text_trimmed_range(), "")
+	};
+
+	diagnostics.push(diagnostic);
+}
+
+/// Tests if the text is a number as defined by the JSON grammar: `(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`
+fn is_json_number(text: &str) -> bool {
+	let bytes = text.as_bytes();
+	let mut position = 0;
+
+	let digits = |position: &mut usize| {
+		let start = *position;
+		while *position < bytes.len() && bytes[*position].is_ascii_digit() {
+			*position += 1;
+		}
+		*position - start
+	};
+
+	let integer_start = position;
+	let integer_digits = digits(&mut position);
+	if integer_digits == 0 || (integer_digits > 1 && bytes[integer_start] == b'0') {
+		return false;
+	}
+
+	if bytes.get(position) == Some(&b'.') {
+		position += 1;
+		if digits(&mut position) == 0 {
+			return false;
+		}
+	}
+
+	if matches!(bytes.get(position), Some(b'e') | Some(b'E')) {
+		position += 1;
+		if matches!(bytes.get(position), Some(b'+') | Some(b'-')) {
+			position += 1;
+		}
+		if digits(&mut position) == 0 {
+			return false;
+		}
+	}
+
+	position == bytes.len()
+}
+
+/// Tests if the text uses one of the number notations that JSON5 adds on top of JSON:
+/// hexadecimal numbers and leading or trailing decimal points.
+fn is_json5_number(text: &str) -> bool {
+	if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+		return !hex.is_empty() && hex.bytes().all(|byte| byte.is_ascii_hexdigit());
+	}
+
+	if let Some(fraction) = text.strip_prefix('.') {
+		return is_json_number(&format!("0.{}", fraction));
+	}
+
+	match text.find('.') {
+		Some(dot) => {
+			let (integer, rest) = text.split_at(dot);
+			let rest = &rest[1..];
+			let exponent_only = rest.is_empty() || rest.starts_with('e') || rest.starts_with('E');
+			exponent_only && is_json_number(&format!("{}{}", integer, rest))
+		}
+		None => false,
+	}
+}
+
+fn check_trailing_comma(
+	separator: &SyntaxToken,
+	dialect: JsonDialect,
+	diagnostics: &mut Vec<Diagnostic>,
+) {
+	if !dialect.allows_trailing_commas() {
+		diagnostics.push(
+			Diagnostic::error(
+				0,
+				"SyntaxError",
+				format!("{} doesn't support trailing commas", dialect),
+			)
+			.primary(separator.text_range(), "")
+			.footer_help("trailing commas are allowed in JSONC and JSON5 files"),
+		);
+	}
+}
+
+fn unsupported_value(node: &SyntaxNode, dialect: JsonDialect) -> Diagnostic {
+	Diagnostic::error(0, "SyntaxError", format!("Unsupported {} value", dialect)).primary(
+		node.text_trimmed_range(),
+		format!("this is not a valid {} value", dialect),
+	)
 }
 
 #[cfg(test)]
@@ -126,19 +793,43 @@ mod test {
 		soft_line_break_or_space, space_token, token,
 	};
 
-	use super::tokenize_json;
+	use super::{
+		is_json5_number, is_json_number, parse_json_dialect, tokenize_json_root, JsonFormatOptions,
+		JsonKeyOrder, ObjectWrap,
+	};
 	use crate::format_element::Indent;
+	use crate::FormatElement;
+	use rome_core::file_handlers::json::JsonDialect;
+	use rslint_errors::Diagnostic;
+	use rslint_parser::TextSize;
+
+	/// Parses the content of a JSON file and converts it into the formatter IR, or returns its
+	/// diagnostics
+	fn tokenize_json(
+		content: &str,
+		dialect: JsonDialect,
+		options: &JsonFormatOptions,
+	) -> Result<FormatElement, Vec<Diagnostic>> {
+		let (root, diagnostics) = parse_json_dialect(content, dialect);
+		if !diagnostics.is_empty() {
+			return Err(diagnostics);
+		}
+
+		Ok(tokenize_json_root(&root, options))
+	}
 
 	#[test]
 	fn tokenize_number() {
-		let result = tokenize_json("6.45");
+		let result =
+			tokenize_json("6.45", JsonDialect::Json, &JsonFormatOptions::default()).unwrap();
 
 		assert_eq!(format_elements![token("6.45"), hard_line_break()], result);
 	}
 
 	#[test]
 	fn tokenize_string() {
-		let result = tokenize_json(r#""foo""#);
+		let result =
+			tokenize_json(r#""foo""#, JsonDialect::Json, &JsonFormatOptions::default()).unwrap();
 
 		assert_eq!(
 			format_elements![token(r#""foo""#), hard_line_break()],
@@ -148,21 +839,24 @@ mod test {
 
 	#[test]
 	fn tokenize_boolean_false() {
-		let result = tokenize_json("false");
+		let result =
+			tokenize_json("false", JsonDialect::Json, &JsonFormatOptions::default()).unwrap();
 
 		assert_eq!(format_elements![token("false"), hard_line_break()], result);
 	}
 
 	#[test]
 	fn tokenize_boolean_true() {
-		let result = tokenize_json("true");
+		let result =
+			tokenize_json("true", JsonDialect::Json, &JsonFormatOptions::default()).unwrap();
 
 		assert_eq!(format_elements![token("true"), hard_line_break()], result);
 	}
 
 	#[test]
 	fn tokenize_boolean_null() {
-		let result = tokenize_json("null");
+		let result =
+			tokenize_json("null", JsonDialect::Json, &JsonFormatOptions::default()).unwrap();
 
 		assert_eq!(format_elements![token("null"), hard_line_break()], result);
 	}
@@ -192,7 +886,8 @@ mod test {
 			hard_line_break(),
 		];
 
-		let result = tokenize_json(input);
+		let result =
+			tokenize_json(input, JsonDialect::Json, &JsonFormatOptions::default()).unwrap();
 
 		assert_eq!(expected, result);
 	}
@@ -219,8 +914,224 @@ mod test {
 			hard_line_break(),
 		];
 
-		let result = tokenize_json(input);
+		let result =
+			tokenize_json(input, JsonDialect::Json, &JsonFormatOptions::default()).unwrap();
 
 		assert_eq!(expected, result);
 	}
+
+	#[test]
+	fn tokenize_rejects_comments_in_json() {
+		let input = "{\"a\": 1 // comment\n}";
+
+		let diagnostics =
+			tokenize_json(input, JsonDialect::Json, &JsonFormatOptions::default()).unwrap_err();
+
+		assert_eq!(diagnostics.len(), 1);
+		assert_eq!(diagnostics[0].title, "JSON doesn't support comments");
+		assert_eq!(diagnostics[0].primary.as_ref().unwrap().span.range, 8..18);
+		assert!(tokenize_json(input, JsonDialect::Jsonc, &JsonFormatOptions::default()).is_ok());
+	}
+
+	#[test]
+	fn tokenize_rejects_trailing_commas_in_json() {
+		let input = r#"["a", "b",]"#;
+
+		let diagnostics =
+			tokenize_json(input, JsonDialect::Json, &JsonFormatOptions::default()).unwrap_err();
+
+		assert_eq!(diagnostics.len(), 1);
+		assert_eq!(diagnostics[0].title, "JSON doesn't support trailing commas");
+		assert_eq!(diagnostics[0].primary.as_ref().unwrap().span.range, 9..10);
+		assert!(tokenize_json(input, JsonDialect::Jsonc, &JsonFormatOptions::default()).is_ok());
+	}
+
+	#[test]
+	fn tokenize_rejects_json5_syntax_in_jsonc() {
+		let input = r#"{key: 'value', "hex": 0xFF}"#;
+
+		let diagnostics =
+			tokenize_json(input, JsonDialect::Jsonc, &JsonFormatOptions::default()).unwrap_err();
+		let titles: Vec<_> = diagnostics.iter().map(|d| d.title.as_str()).collect();
+
+		assert_eq!(
+			titles,
+			vec![
+				"JSONC doesn't support unquoted property keys",
+				"JSONC doesn't support single quoted strings",
+				"JSONC doesn't support this number notation",
+			]
+		);
+		assert!(tokenize_json(input, JsonDialect::Json5, &JsonFormatOptions::default()).is_ok());
+	}
+
+	#[test]
+	fn tokenize_rejects_values_that_are_not_json() {
+		let diagnostics = tokenize_json(
+			"[a, `b`]",
+			JsonDialect::Json5,
+			&JsonFormatOptions::default(),
+		)
+		.unwrap_err();
+
+		assert_eq!(diagnostics.len(), 2);
+		assert_eq!(diagnostics[0].primary.as_ref().unwrap().span.range, 1..2);
+		assert_eq!(diagnostics[1].primary.as_ref().unwrap().span.range, 4..7);
+	}
+
+	#[test]
+	fn tokenize_reports_parse_errors() {
+		let diagnostics = tokenize_json(
+			r#"{"a": }"#,
+			JsonDialect::Json,
+			&JsonFormatOptions::default(),
+		)
+		.unwrap_err();
+
+		assert!(!diagnostics.is_empty());
+	}
+
+	#[test]
+	fn json_numbers() {
+		for number in ["0", "-0", "10", "1.5", "1e10", "1.5E-3", "0.001"] {
+			let number = number.trim_start_matches('-');
+			assert!(is_json_number(number), "{}", number);
+		}
+
+		for number in ["01", "1.", ".5", "0x10", "1e", "1_000", "10n", "0o7"] {
+			assert!(!is_json_number(number), "{}", number);
+		}
+
+		for number in ["0xFF", "0Xab", ".5", "5.", "5.e3"] {
+			assert!(is_json5_number(number), "{}", number);
+		}
+
+		for number in ["0x", "0b1", "0o7", "07", "1_000", "."] {
+			assert!(!is_json5_number(number), "{}", number);
+		}
+	}
+
+	fn format_json(input: &str, options: JsonFormatOptions) -> String {
+		let element = tokenize_json(input, JsonDialect::Jsonc, &options).unwrap();
+		crate::format_element(&element, crate::FormatOptions::default())
+			.code()
+			.clone()
+	}
+
+	/// Formats `input` with the cursor at the `|` and returns the formatted code with the new cursor
+	fn format_json_with_cursor(input: &str, options: JsonFormatOptions) -> String {
+		let cursor = input.find('|').unwrap();
+		let input = input.replace('|', "");
+		let element = tokenize_json(&input, JsonDialect::Jsonc, &options).unwrap();
+		let formatted = crate::format_element(&element, crate::FormatOptions::default());
+
+		let mut code = formatted.code().clone();
+		code.insert(
+			usize::from(formatted.translate_offset(TextSize::from(cursor as u32))),
+			'|',
+		);
+		code
+	}
+
+	#[test]
+	fn translates_the_cursor() {
+		assert_eq!(
+			format_json_with_cursor("{\"a\":  tr|ue,\"b\":[1,2]}", JsonFormatOptions::default()),
+			"{\"a\": tr|ue, \"b\": [1, 2]}\n"
+		);
+		assert_eq!(
+			format_json_with_cursor(
+				"{\"a\":true,\"b\":[1,  |  -2]}",
+				JsonFormatOptions::default()
+			),
+			"{\"a\": true, \"b\": [1, |-2]}\n"
+		);
+
+		let options = JsonFormatOptions {
+			key_order: JsonKeyOrder::Sorted,
+			..JsonFormatOptions::default()
+		};
+		assert_eq!(
+			format_json_with_cursor("{\"b\": \"x|y\", \"a\": 1}", options),
+			"{\"a\": 1, \"b\": \"x|y\"}\n"
+		);
+	}
+
+	#[test]
+	fn sorts_keys_alphabetically() {
+		let input = r#"{"b": {"d": 1, "c": 2},
+		/* the a */ "a": [{"f": 1, "e": 2}]}"#;
+		let options = JsonFormatOptions {
+			key_order: JsonKeyOrder::Sorted,
+			..JsonFormatOptions::default()
+		};
+
+		assert_eq!(
+			format_json(input, options),
+			"{/* the a */ \"a\": [{\"e\": 2, \"f\": 1}], \"b\": {\"c\": 2, \"d\": 1}}\n"
+		);
+	}
+
+	#[test]
+	fn sorts_package_json_keys_in_conventional_order() {
+		let input = r#"{
+	"scripts": {"test": "jest", "build": "tsc"},
+	"dependencies": {"zod": "3", "ajv": "8"},
+	"zzz": true,
+	"version": "1.0.0",
+	"name": "rome"
+}"#;
+		let options = JsonFormatOptions {
+			key_order: JsonKeyOrder::PackageJson,
+			..JsonFormatOptions::default()
+		};
+
+		assert_eq!(
+			format_json(input, options),
+			r#"{
+	"name": "rome",
+	"version": "1.0.0",
+	"scripts": {"test": "jest", "build": "tsc"},
+	"dependencies": {"ajv": "8", "zod": "3"},
+	"zzz": true
+}
+"#
+		);
+	}
+
+	#[test]
+	fn preserves_expanded_objects() {
+		let input = "{\n\"a\": [1, 2], \"b\": [\n3]}";
+		let options = JsonFormatOptions {
+			object_wrap: ObjectWrap::Preserve,
+			..JsonFormatOptions::default()
+		};
+
+		assert_eq!(
+			format_json(input, options),
+			"{\n\t\"a\": [1, 2],\n\t\"b\": [\n\t\t3\n\t]\n}\n"
+		);
+
+		assert_eq!(
+			format_json(input, JsonFormatOptions::default()),
+			format_json(input, options),
+			"expanded objects are preserved by default"
+		);
+
+		let options = JsonFormatOptions {
+			object_wrap: ObjectWrap::Collapse,
+			..JsonFormatOptions::default()
+		};
+		assert_eq!(format_json(input, options), "{\"a\": [1, 2], \"b\": [3]}\n");
+	}
+
+	#[test]
+	fn preserves_number_text() {
+		let input = "[1.0, 1e10, 1E+2, -0.0, 0.10]";
+
+		assert_eq!(
+			format_json(input, JsonFormatOptions::default()),
+			"[1.0, 1e10, 1E+2, -0.0, 0.10]\n"
+		);
+	}
 }
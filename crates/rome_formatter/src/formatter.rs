@@ -1,10 +1,11 @@
+use crate::format_element::{SourcePosition, Token};
 use crate::printer::Printer;
 use crate::{
-	concat_elements, token, FormatElement, FormatError, FormatOptions, FormatResult, Formatted,
-	ToFormatElement,
+	concat_elements, empty_element, FormatElement, FormatError, FormatOptions, FormatResult,
+	Formatted, ToFormatElement,
 };
 use rome_rowan::SyntaxElement;
-use rslint_parser::{AstNode, SyntaxNode, SyntaxToken};
+use rslint_parser::{AstNode, SyntaxNode, SyntaxNodeExt, SyntaxToken, TextSize};
 
 /// Handles the formatting of a CST and stores the options how the CST should be formatted (user preferences).
 /// The formatter is passed to the [ToFormatElement] implementation of every node in the CST so that they
@@ -34,14 +35,37 @@ impl Formatter {
 		Ok(printer.print(&element))
 	}
 
+	/// Formats a CST and translates the offset of the `cursor` in the source code to its offset in
+	/// the formatted code, so that editors can keep the cursor at the same place after formatting.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rome_formatter::{Formatter, FormatOptions};
+	/// use rslint_parser::{parse_text, TextSize};
+	///
+	/// let root = parse_text("let   a=[1,2]", 0).syntax();
+	///
+	/// // The cursor is right before the `2`
+	/// let (formatted, cursor) =
+	/// 	Formatter::format_with_cursor(&root, FormatOptions::default(), TextSize::from(11)).unwrap();
+	///
+	/// assert_eq!("let a = [1, 2];\n", formatted.code());
+	/// assert_eq!(TextSize::from(12), cursor);
+	/// ```
+	pub fn format_with_cursor(
+		root: &SyntaxNode,
+		options: FormatOptions,
+		cursor: TextSize,
+	) -> FormatResult<(Formatted, TextSize)> {
+		let formatted = Formatter::new(options).format_root(root)?;
+		let cursor = formatted.translate_offset(cursor);
+		Ok((formatted, cursor))
+	}
+
 	fn format_syntax_node(&self, node: &SyntaxNode) -> FormatResult<FormatElement> {
-		let start = self.format_node_start(node);
 		let content = node.to_format_element(self)?;
-		Ok(concat_elements(vec![
-			start,
-			content,
-			self.format_node_end(node),
-		]))
+		Ok(self.wrap_node(node, content))
 	}
 
 	/// Recursively formats the ast node and all its children
@@ -52,25 +76,37 @@ impl Formatter {
 		&self,
 		node: T,
 	) -> FormatResult<FormatElement> {
-		Ok(concat_elements(vec![
-			self.format_node_start(node.syntax()),
-			node.to_format_element(self)?,
-			self.format_node_end(node.syntax()),
-		]))
+		let content = node.to_format_element(self)?;
+		Ok(self.wrap_node(node.syntax(), content))
+	}
+
+	/// Surrounds the formatted `content` of `node` with what's printed before and after it.
+	///
+	/// The nodes that print nothing stay empty, so that the lists of elements can skip them.
+	fn wrap_node(&self, node: &SyntaxNode, content: FormatElement) -> FormatElement {
+		if content.is_empty() {
+			return content;
+		}
+
+		concat_elements(vec![
+			self.format_node_start(node),
+			content,
+			self.format_node_end(node),
+		])
 	}
 
 	/// Helper function that returns what should be printed before the node that work on
 	/// the non-generic [SyntaxNode] to avoid unrolling the logic for every [AstNode] type.
-	fn format_node_start(&self, _node: &SyntaxNode) -> FormatElement {
-		// TODO: Set the marker for the start source map location, add leading comments, ...
-		concat_elements(vec![])
+	fn format_node_start(&self, node: &SyntaxNode) -> FormatElement {
+		// TODO: add leading comments, ...
+		FormatElement::from(SourcePosition::Start(node.trimmed_range().start()))
 	}
 
 	/// Helper function that returns what should be printed after the node that work on
 	/// the non-generic [SyntaxNode] to avoid unrolling the logic for every [AstNode] type.
-	fn format_node_end(&self, _node: &SyntaxNode) -> FormatElement {
-		// TODO: Sets the marker for the end source map location, add trailing comments, ...
-		concat_elements(vec![])
+	fn format_node_end(&self, node: &SyntaxNode) -> FormatElement {
+		// TODO: add trailing comments, ...
+		FormatElement::from(SourcePosition::End(node.trimmed_range().end()))
 	}
 
 	/// Formats the passed in token.
@@ -100,7 +136,7 @@ impl Formatter {
 	/// assert_eq!(Ok(token("'abc'")), result)
 	/// ```
 	pub fn format_token(&self, syntax_token: &SyntaxToken) -> FormatResult<FormatElement> {
-		Ok(token(syntax_token.text()))
+		Ok(source_token(syntax_token))
 	}
 
 	/// Formats each child and returns the result as a list.
@@ -134,11 +170,25 @@ impl Formatter {
 	pub fn format_raw(&self, node: &SyntaxNode) -> FormatElement {
 		concat_elements(node.children_with_tokens().map(|child| match child {
 			SyntaxElement::Node(child_node) => {
-				// TODO: Add source map markers before/after node as well as any additional elements that
-				// need to be tracked for every node.
+				// The tokens keep their positions in the source code, so the nodes don't need
+				// source position markers.
+				// TODO: Add any additional elements that need to be tracked for every node.
 				self.format_raw(&child_node)
 			}
-			SyntaxElement::Token(syntax_token) => token(syntax_token.text()),
+			SyntaxElement::Token(syntax_token) => source_token(&syntax_token),
 		}))
 	}
 }
+
+/// Creates a token for the text of the syntax token that remembers its position in the source code
+fn source_token(syntax_token: &SyntaxToken) -> FormatElement {
+	let text = syntax_token.text();
+	if text.is_empty() {
+		empty_element()
+	} else {
+		FormatElement::Token(Token::new_with_source(
+			text,
+			syntax_token.text_range().start(),
+		))
+	}
+}
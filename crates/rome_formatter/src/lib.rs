@@ -48,6 +48,7 @@
 //! [IR]: https://en.wikipedia.org/wiki/Intermediate_representation
 
 mod cst;
+pub mod file_handlers;
 mod format_element;
 mod format_elements;
 mod format_json;
@@ -55,11 +56,15 @@ mod formatter;
 mod intersperse;
 mod printer;
 mod ts;
+mod verify;
 
-use crate::format_json::tokenize_json;
+pub use crate::format_json::{JsonFormatOptions, JsonKeyOrder};
 
+pub use file_handlers::create_app;
 pub use formatter::Formatter;
-use rslint_parser::SyntaxError;
+pub use rome_core::format::{FormatError, FormatResult, Formatted, SourceMarker};
+use rslint_errors::Diagnostic;
+pub use verify::{format_and_verify, verify};
 
 pub use format_element::{
 	block_indent, concat_elements, empty_element, group_elements, hard_line_break, if_group_breaks,
@@ -68,77 +73,46 @@ pub use format_element::{
 };
 pub use printer::Printer;
 pub use printer::PrinterOptions;
-use rome_core::file_handlers::Language;
+use rome_core::configuration::FormatConfiguration;
+pub use rome_core::configuration::{IndentStyle, LineEnding, ObjectWrap};
+use rome_core::file_handlers::ExtensionHandler;
+use rome_core::fs::{FileSystem, OsFileSystem};
+use rome_core::workspace::{Parsed, Services};
 use rome_core::App;
 use rome_path::RomePath;
-use rslint_parser::parse_text;
+use rslint_parser::TextRange;
 
-use std::io::Read;
-use std::str::FromStr;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Arc;
 
 /// This trait should be implemented on each node/value that should have a formatted representation
 pub trait ToFormatElement {
 	fn to_format_element(&self, formatter: &Formatter) -> FormatResult<FormatElement>;
 }
 
-/// Public return type of the formatter
-pub type FormatResult<F> = Result<F, FormatError>;
-
-#[derive(Debug, PartialEq)]
-/// Series of errors encountered during formatting
-pub enum FormatError {
-	/// Node is missing and it should be required for a correct formatting
-	MissingRequiredChild,
-
-	/// In case our formatter doesn't know how to format a certain language
-	UnsupportedLanguage,
-
-	/// When the ability to format the current file has been turned off on purpose
-	CapabilityDisabled,
-}
-
-impl From<SyntaxError> for FormatError {
-	fn from(syntax_error: SyntaxError) -> Self {
-		match syntax_error {
-			SyntaxError::MissingRequiredChild(_node) => FormatError::MissingRequiredChild,
-		}
-	}
-}
-
-#[derive(Debug, Eq, PartialEq, Clone)]
-pub enum IndentStyle {
-	/// Tab
-	Tab,
-	/// Space, with its quantity
-	Space(u8),
-}
-
-impl Default for IndentStyle {
-	fn default() -> Self {
-		Self::Tab
-	}
-}
-
-impl FromStr for IndentStyle {
-	type Err = &'static str;
-
-	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		match s {
-			"tab" => Ok(Self::Tab),
-			"space" => Ok(Self::Space(2)),
-			// TODO: replace this error with a diagnostic
-			_ => Err("Value not supported for IndentStyle"),
-		}
-	}
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FormatOptions {
 	/// The indent style
 	pub indent_style: IndentStyle,
 
+	/// The number of spaces of an indentation level, when indenting with spaces. Defaults to 2
+	pub indent_size: u8,
+
 	/// What's the max width of a line. Defaults to 80
 	pub line_width: u16,
+
+	/// The width of a tab character. Defaults to 2
+	pub tab_width: u8,
+
+	/// The line breaks of the formatted code
+	pub line_ending: LineEnding,
+
+	/// Whether the formatted code ends with a line break. Defaults to `true`
+	pub final_newline: bool,
+
+	/// Options specific to JSON files
+	pub json: JsonFormatOptions,
 }
 
 impl FormatOptions {
@@ -154,77 +128,419 @@ impl Default for FormatOptions {
 	fn default() -> Self {
 		Self {
 			indent_style: IndentStyle::default(),
+			indent_size: 2,
 			line_width: 80,
+			tab_width: 2,
+			line_ending: LineEnding::default(),
+			final_newline: true,
+			json: JsonFormatOptions::default(),
 		}
 	}
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Formatted {
-	code: String,
-}
+impl From<&FormatConfiguration> for FormatOptions {
+	fn from(configuration: &FormatConfiguration) -> Self {
+		let key_order = if configuration.json.sort_keys {
+			JsonKeyOrder::Sorted
+		} else {
+			JsonKeyOrder::Preserve
+		};
 
-impl Formatted {
-	pub fn new(code: &str) -> Self {
 		Self {
-			code: String::from(code),
+			indent_style: configuration.indent_style,
+			indent_size: configuration.indent_size,
+			line_width: configuration.line_width,
+			tab_width: configuration.tab_width,
+			line_ending: configuration.line_ending,
+			final_newline: configuration.final_newline,
+			json: JsonFormatOptions {
+				key_order,
+				object_wrap: configuration.json.object_wrap,
+			},
 		}
 	}
+}
 
-	pub fn code(&self) -> &String {
-		&self.code
-	}
-}
-
-// TODO: implement me + handle errors
-/// Main function
-pub fn format(rome_path: &mut RomePath, options: FormatOptions) -> FormatResult<Formatted> {
-	// we assume that file exists
-	let mut file = rome_path.open();
-	let mut buffer = String::new();
-	// we assume we have permissions
-	file.read_to_string(&mut buffer)
-		.expect("cannot read the file to format");
-
-	if let Some(handler) = rome_path.get_handler() {
-		if handler.capabilities().format {
-			let result = match handler.language() {
-				Language::Js => {
-					let parsed_result = parse_text(buffer.as_str(), 0);
-					Formatter::new(options).format_root(&parsed_result.syntax())
-				}
-				Language::Json => {
-					let element = tokenize_json(buffer.as_str());
-					Ok(format_element(&element, options))
-				}
-				Language::Ts | Language::Unknown => Err(FormatError::UnsupportedLanguage),
-			};
-
-			result
-		} else {
-			Err(FormatError::CapabilityDisabled)
-		}
+/// Reads the file at `rome_path` from `fs` and formats it
+pub fn format(
+	rome_path: &RomePath,
+	fs: &dyn FileSystem,
+	configuration: &FormatConfiguration,
+) -> FormatResult<Formatted> {
+	let source = read_source(rome_path, fs)?;
+	format_source(rome_path, source.as_str(), configuration)
+}
+
+/// Reads the content of the file to format
+fn read_source(rome_path: &RomePath, fs: &dyn FileSystem) -> FormatResult<String> {
+	rome_path
+		.read(fs)
+		.map_err(|error| FormatError::Io(error.to_diagnostic(0)))
+}
+
+/// Formats `source` as the content of the file at `rome_path`, without reading the file.
+///
+/// The handler of `rome_path` decides how `source` is parsed, so [RomePath::deduce_handler] must be
+/// called first. The `.js` files are parsed as scripts unless [RomePath::resolve_syntax] finds that
+/// they belong to a package of type `module`.
+pub fn format_source(
+	rome_path: &RomePath,
+	source: &str,
+	configuration: &FormatConfiguration,
+) -> FormatResult<Formatted> {
+	formatting_handler(rome_path.get_handler().map(|handler| handler.as_ref()))?;
+	let parsed = parse(rome_path, source).ok_or(FormatError::UnsupportedLanguage)?;
+	format_parsed(rome_path, &parsed, configuration)
+}
+
+/// Parses `source` as the content of the file at `rome_path` with its handler, see
+/// [ExtensionHandler::parse]. Returns `None` if the language of the file can't be parsed.
+///
+/// For JSON files, the diagnostics include the usages of syntax that isn't supported by the
+/// dialect of the file, and the tree is the one of the JSON value wrapped in parentheses.
+///
+/// The handler is recorded in the result, see [Parsed::handler].
+pub fn parse(rome_path: &RomePath, source: &str) -> Option<Parsed> {
+	let handler = rome_path.get_handler()?;
+	let syntax = rome_path.syntax().unwrap_or_else(|| handler.syntax());
+	let parsed = handler.parse(source, syntax)?;
+	Some(parsed.with_handler(Arc::clone(handler)))
+}
+
+/// Formats the file at `rome_path`, already parsed by [parse], with the handler that parsed it,
+/// see [ExtensionHandler::format]. The trees that weren't parsed by [parse] are formatted with
+/// the handler of `rome_path`.
+pub fn format_parsed(
+	rome_path: &RomePath,
+	parsed: &Parsed,
+	configuration: &FormatConfiguration,
+) -> FormatResult<Formatted> {
+	let handler = parsed
+		.handler()
+		.or_else(|| rome_path.get_handler().map(|handler| handler.as_ref()));
+	formatting_handler(handler)?.format(rome_path, parsed, configuration)
+}
+
+/// Returns `handler` if it can format its files
+fn formatting_handler(
+	handler: Option<&dyn ExtensionHandler>,
+) -> FormatResult<&dyn ExtensionHandler> {
+	let handler = handler.ok_or(FormatError::UnsupportedLanguage)?;
+	if handler.capabilities().format {
+		Ok(handler)
 	} else {
-		Err(FormatError::UnsupportedLanguage)
+		Err(FormatError::CapabilityDisabled)
 	}
 }
 
-pub fn format_file_and_save(rome_path: &mut RomePath, options: FormatOptions) {
-	let result = format(rome_path, options);
-	if let Ok(result) = result {
-		rome_path
-			.save(result.code())
-			.expect("Could not write the formatted code on file");
+/// Formats the lines of `source` that `range` touches, as the content of the file at `rome_path`.
+///
+/// The whole file is formatted, see [Formatted::format_range]. Returns the range of `source` to
+/// replace and the formatted code that replaces it, or `None` if there's no token in the range.
+pub fn format_range(
+	rome_path: &RomePath,
+	source: &str,
+	range: TextRange,
+	configuration: &FormatConfiguration,
+) -> FormatResult<Option<(TextRange, String)>> {
+	let formatted = format_source(rome_path, source, configuration)?;
+	Ok(formatted.format_range(source, range))
+}
+
+/// Returns the syntax errors of `source`, parsed as the content of the file at `rome_path`.
+///
+/// For JSON files, they include the usages of syntax that isn't supported by the dialect of the
+/// file. Files that can't be parsed don't have any diagnostic.
+pub fn syntax_diagnostics(rome_path: &RomePath, source: &str) -> Vec<Diagnostic> {
+	parse(rome_path, source).map_or_else(Vec::new, |parsed| parsed.diagnostics().to_vec())
+}
+
+/// The JSON options to use for the file at `path`
+pub(crate) fn json_options(path: &Path, options: &FormatOptions) -> JsonFormatOptions {
+	let mut json_options = options.json;
+
+	// Sorting the keys of a package.json alphabetically would be surprising
+	if json_options.key_order == JsonKeyOrder::Sorted
+		&& path.file_name() == Some(OsStr::new("package.json"))
+	{
+		json_options.key_order = JsonKeyOrder::PackageJson;
+	}
+
+	json_options
+}
+
+/// Formats the file at `rome_path` and overwrites it with the formatted code.
+///
+/// The file is left untouched when it can't be formatted, and the error is returned.
+pub fn format_file_and_save(
+	rome_path: &RomePath,
+	fs: &dyn FileSystem,
+	configuration: &FormatConfiguration,
+) -> FormatResult<()> {
+	let result = format(rome_path, fs, configuration)?;
+	rome_path
+		.save(fs, result.code())
+		.map_err(|error| FormatError::Io(error.to_diagnostic(0)))
+}
+
+pub fn format_file(
+	path_to_file: &str,
+	fs: &dyn FileSystem,
+	configuration: &FormatConfiguration,
+	app: &App,
+) -> FormatResult<Formatted> {
+	let rome_path = RomePath::new(path_to_file).deduce_handler(app);
+	let source = read_source(&rome_path, fs)?;
+	let rome_path = rome_path
+		.sniff_handler(app, &source)
+		.resolve_syntax(app, fs);
+	format_source(&rome_path, &source, configuration)
+}
+
+/// The [Services] of a [Workspace](rome_core::workspace::Workspace) that parses and formats the
+/// languages supported by the formatter
+pub struct WorkspaceServices {
+	app: App,
+	/// The file system the `package.json` files are read from, the documents themselves are
+	/// owned by the workspace
+	fs: Box<dyn FileSystem>,
+}
+
+impl WorkspaceServices {
+	pub fn new(app: App) -> Self {
+		Self::with_file_system(app, Box::new(OsFileSystem))
+	}
+
+	pub fn with_file_system(app: App, fs: Box<dyn FileSystem>) -> Self {
+		Self { app, fs }
+	}
+
+	/// The path of the document at `path`, with its handler deduced from its name or from the
+	/// start of its `content`
+	fn rome_path(&self, path: &Path, content: &str) -> RomePath<'_> {
+		RomePath::new(&path.to_string_lossy())
+			.deduce_handler(&self.app)
+			.sniff_handler(&self.app, content)
+			.resolve_syntax(&self.app, self.fs.as_ref())
 	}
 }
 
-pub fn format_file(path_to_file: &str, options: FormatOptions, app: &App) -> Formatted {
-	let mut rome_path = RomePath::new(path_to_file).deduce_handler(app);
-	let element = format(&mut rome_path, options);
-	element.unwrap()
+impl Services for WorkspaceServices {
+	type Formatted = Formatted;
+	type FormatError = FormatError;
+
+	fn parse(&self, path: &Path, content: &str) -> Option<Parsed> {
+		parse(&self.rome_path(path, content), content)
+	}
+
+	fn format(
+		&self,
+		path: &Path,
+		parsed: &Parsed,
+		configuration: &FormatConfiguration,
+	) -> FormatResult<Formatted> {
+		if !configuration.enabled {
+			return Err(FormatError::CapabilityDisabled);
+		}
+
+		// The handler was resolved when the document was parsed
+		let rome_path = RomePath::new(&path.to_string_lossy());
+		format_parsed(&rome_path, parsed, configuration)
+	}
 }
 
 pub fn format_element(element: &FormatElement, options: FormatOptions) -> Formatted {
 	let printer = Printer::new(options);
 	printer.print(element)
 }
+
+#[cfg(test)]
+mod test {
+	use super::{
+		create_app, format_range, format_source, syntax_diagnostics, FormatResult, Formatted,
+		WorkspaceServices,
+	};
+	use rome_core::{
+		configuration::{FormatConfiguration, ProjectSettings},
+		file_handlers::{Capabilities, ExtensionHandler, Language, Mime},
+		fs::MemoryFileSystem,
+		workspace::{DocumentChange, Parsed, Workspace},
+	};
+	use rome_path::RomePath;
+	use rslint_parser::{parse_text, Syntax, TextRange, TextSize};
+	use std::path::Path;
+
+	/// The handler of a language that only trims the lines of its files
+	struct TrimmedFileHandler {}
+
+	impl ExtensionHandler for TrimmedFileHandler {
+		fn capabilities(&self) -> Capabilities {
+			Capabilities {
+				format: true,
+				lint: false,
+			}
+		}
+
+		fn language(&self) -> Language {
+			Language::Other("trimmed")
+		}
+
+		fn mime(&self) -> Mime {
+			Mime::Text
+		}
+
+		fn parse(&self, content: &str, _syntax: Syntax) -> Option<Parsed> {
+			Some(Parsed::new(parse_text(content, 0).syntax(), Vec::new()))
+		}
+
+		fn format(
+			&self,
+			_path: &Path,
+			parsed: &Parsed,
+			_configuration: &FormatConfiguration,
+		) -> FormatResult<Formatted> {
+			let text = parsed.root().text().to_string();
+			let lines: Vec<_> = text.lines().map(str::trim).collect();
+			Ok(Formatted::new(&lines.join("\n")))
+		}
+	}
+
+	fn range(start: u32, end: u32) -> TextRange {
+		TextRange::new(TextSize::from(start), TextSize::from(end))
+	}
+
+	#[test]
+	fn formats_the_lines_of_the_range() {
+		let app = create_app();
+		let rome_path = RomePath::new("index.js").deduce_handler(&app);
+		let source = "let a=1\nlet   b  =  2\nlet c=3\n";
+
+		let (replaced, code) = format_range(
+			&rome_path,
+			source,
+			range(10, 11),
+			&FormatConfiguration::default(),
+		)
+		.unwrap()
+		.unwrap();
+
+		assert_eq!(&source[replaced], "let   b  =  2");
+		assert_eq!(code, "let b = 2;");
+	}
+
+	#[test]
+	fn widens_the_range_to_the_joined_lines() {
+		let app = create_app();
+		let rome_path = RomePath::new("index.js").deduce_handler(&app);
+		let source = "let a=1\nlet b=[\n1,\n2]\nlet c=3\n";
+
+		let (replaced, code) = format_range(
+			&rome_path,
+			source,
+			range(8, 9),
+			&FormatConfiguration::default(),
+		)
+		.unwrap()
+		.unwrap();
+
+		assert_eq!(&source[replaced], "let b=[\n1,\n2]");
+		assert_eq!(code, "let b = [1, 2];");
+	}
+
+	#[test]
+	fn skips_the_ranges_without_tokens() {
+		let app = create_app();
+		let rome_path = RomePath::new("index.js").deduce_handler(&app);
+		let source = "let a=1\n\nlet b=2\n";
+
+		let formatted = format_range(
+			&rome_path,
+			source,
+			range(8, 8),
+			&FormatConfiguration::default(),
+		)
+		.unwrap();
+		assert_eq!(formatted, None);
+	}
+
+	#[test]
+	fn reports_the_syntax_errors() {
+		let app = create_app();
+
+		let js = RomePath::new("index.js").deduce_handler(&app);
+		assert_eq!(syntax_diagnostics(&js, "let a = ;").len(), 1);
+		assert!(syntax_diagnostics(&js, "let a = 1;").is_empty());
+
+		let json = RomePath::new("data.json").deduce_handler(&app);
+		assert!(!syntax_diagnostics(&json, "{ \"a\": 1, }").is_empty());
+		assert!(syntax_diagnostics(&json, "{ \"a\": 1 }").is_empty());
+	}
+
+	#[test]
+	fn parses_the_js_files_of_the_module_packages_as_modules() {
+		let app = create_app();
+		let fs = MemoryFileSystem::default();
+		fs.insert("/module/package.json", r#"{ "type": "module" }"#);
+		fs.insert("/commonjs/package.json", r#"{ "type": "commonjs" }"#);
+		let source = "import a from \"a\";\nexport default a;\n";
+
+		let module = RomePath::new("/module/src/index.js")
+			.deduce_handler(&app)
+			.resolve_syntax(&app, &fs);
+		assert!(syntax_diagnostics(&module, source).is_empty());
+
+		let script = RomePath::new("/commonjs/src/index.js")
+			.deduce_handler(&app)
+			.resolve_syntax(&app, &fs);
+		assert!(!syntax_diagnostics(&script, source).is_empty());
+	}
+
+	#[test]
+	fn formats_with_the_handlers_registered_at_runtime() {
+		let mut app = create_app();
+		app.register_extension("trimmed", Box::new(TrimmedFileHandler {}));
+		let rome_path = RomePath::new("notes.trimmed").deduce_handler(&app);
+
+		let formatted = format_source(
+			&rome_path,
+			"  let a = 1;  \n\tlet b = 2;",
+			&FormatConfiguration::default(),
+		)
+		.unwrap();
+		assert_eq!(formatted.code(), "let a = 1;\nlet b = 2;");
+	}
+
+	#[test]
+	fn formats_the_documents_of_a_workspace() {
+		let mut workspace = Workspace::new(
+			WorkspaceServices::new(create_app()),
+			ProjectSettings::default(),
+		);
+		let path = Path::new("index.js");
+		workspace.open_file(path, "let a = ;".to_string(), 1);
+
+		assert_eq!(workspace.pull_diagnostics(path).unwrap().len(), 1);
+
+		let change = DocumentChange {
+			range: Some(range(8, 8)),
+			text: "1".to_string(),
+		};
+		workspace.change_file(path, 2, vec![change]).unwrap();
+		assert!(workspace.pull_diagnostics(path).unwrap().is_empty());
+		assert_eq!(workspace.format_file(path).unwrap().code(), "let a = 1;\n");
+	}
+
+	#[test]
+	fn formats_the_documents_with_the_handler_that_parsed_them() {
+		let mut app = create_app();
+		app.register_interpreter("trimmed", Box::new(TrimmedFileHandler {}));
+		let mut workspace = Workspace::new(WorkspaceServices::new(app), ProjectSettings::default());
+		let path = Path::new("bin/run");
+		workspace.open_file(path, "#!/usr/bin/env trimmed\n  a  \n".to_string(), 1);
+
+		assert_eq!(
+			workspace.format_file(path).unwrap().code(),
+			"#!/usr/bin/env trimmed\na"
+		);
+	}
+}
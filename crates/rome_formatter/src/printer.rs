@@ -1,5 +1,8 @@
-use crate::format_element::{ConditionalGroupContent, Group, GroupPrintMode, LineMode};
-use crate::{FormatElement, FormatOptions, Formatted, IndentStyle};
+use crate::format_element::{
+	ConditionalGroupContent, Group, GroupPrintMode, LineMode, SourcePosition,
+};
+use crate::{FormatElement, FormatOptions, Formatted, IndentStyle, LineEnding, SourceMarker};
+use rslint_parser::{TextRange, TextSize};
 
 /// Options that affect how the [Printer] prints the format tokens
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -13,6 +16,10 @@ pub struct PrinterOptions {
 	/// The type of line ending to apply to the printed input
 	pub line_ending: LineEnding,
 
+	/// Whether the printed input keeps its trailing line break. Only the last line break is
+	/// removed when it doesn't, the printer never adds one
+	pub final_newline: bool,
+
 	/// The never ending question whatever to use spaces or tabs, and if spaces, how many spaces
 	/// to indent code.
 	///
@@ -24,42 +31,18 @@ pub struct PrinterOptions {
 impl From<FormatOptions> for PrinterOptions {
 	fn from(options: FormatOptions) -> Self {
 		let indent_string: String;
-		let tab_width = 2;
 
 		match options.indent_style {
 			IndentStyle::Tab => indent_string = String::from("\t"),
-			IndentStyle::Space(width) => indent_string = " ".repeat(width as usize),
+			IndentStyle::Space => indent_string = " ".repeat(options.indent_size as usize),
 		};
 
 		PrinterOptions {
 			indent_string,
-			tab_width,
+			tab_width: options.tab_width,
 			print_width: options.line_width,
-			..PrinterOptions::default()
-		}
-	}
-}
-
-#[allow(dead_code)]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum LineEnding {
-	///  Line Feed only (\n), common on Linux and macOS as well as inside git repos
-	LineFeed,
-
-	/// Carriage Return + Line Feed characters (\r\n), common on Windows
-	CarriageReturnLineFeed,
-
-	/// Carriage Return character only (\r), used very rarely
-	CarriageReturn,
-}
-
-impl LineEnding {
-	#[inline]
-	pub const fn as_str(&self) -> &'static str {
-		match self {
-			LineEnding::LineFeed => "\n",
-			LineEnding::CarriageReturnLineFeed => "\r\n",
-			LineEnding::CarriageReturn => "\r",
+			line_ending: options.line_ending,
+			final_newline: options.final_newline,
 		}
 	}
 }
@@ -70,7 +53,8 @@ impl Default for PrinterOptions {
 			tab_width: 2,
 			print_width: 80,
 			indent_string: String::from("\t"),
-			line_ending: LineEnding::LineFeed,
+			line_ending: LineEnding::Lf,
+			final_newline: true,
 		}
 	}
 }
@@ -104,7 +88,18 @@ impl Printer {
 			queue.extend(self.print_element(print_element_call.element, print_element_call.args));
 		}
 
-		Formatted::new(self.state.buffer.as_str())
+		// The nodes that start after the last token print nothing
+		let last_token_end = self.state.last_token_end;
+		self.state.resolve_pending_positions(last_token_end);
+
+		let mut code = self.state.buffer.as_str();
+		if !self.options.final_newline {
+			code = code
+				.strip_suffix(self.options.line_ending.as_str())
+				.unwrap_or(code);
+		}
+
+		Formatted::with_sourcemap(code, self.state.source_markers)
 	}
 
 	/// Prints a single element and returns the elements to queue (that should be printed next).
@@ -119,6 +114,19 @@ impl Printer {
 				vec![]
 			}
 			FormatElement::Empty => vec![],
+			FormatElement::SourcePosition(SourcePosition::Start(position)) => {
+				// The pending indention and spaces may still be followed by a line break, the
+				// position is resolved once the next token is printed
+				self.state.pending_positions.push(*position);
+				vec![]
+			}
+			FormatElement::SourcePosition(SourcePosition::End(position)) => {
+				self.state.source_markers.push(SourceMarker {
+					source: TextRange::empty(*position),
+					dest: TextRange::empty(self.state.last_token_end),
+				});
+				vec![]
+			}
 			FormatElement::Token(token) => {
 				// Print pending indention
 				if self.state.pending_indent > 0 {
@@ -137,7 +145,18 @@ impl Printer {
 					self.state.pending_spaces = 0;
 				}
 
+				let dest_start = TextSize::of(self.state.buffer.as_str());
+				self.state.resolve_pending_positions(dest_start);
 				self.print_str(token);
+				self.state.last_token_end = TextSize::of(self.state.buffer.as_str());
+
+				if let Some(source_position) = token.source_position() {
+					self.state.source_markers.push(SourceMarker {
+						source: TextRange::at(source_position, TextSize::of(token.as_str())),
+						dest: TextRange::new(dest_start, TextSize::of(self.state.buffer.as_str())),
+					});
+				}
+
 				vec![]
 			}
 
@@ -264,6 +283,7 @@ impl Printer {
 			}) => vec![],
 
 			FormatElement::Empty
+			| FormatElement::SourcePosition(_)
 			| FormatElement::Space
 			| FormatElement::Indent { .. }
 			| FormatElement::List { .. } => self.print_element(element, args),
@@ -314,7 +334,12 @@ struct PrinterState {
 	generated_line: usize,
 	generated_column: usize,
 	line_width: usize,
-	// mappings: Mapping[];
+	/// Where the tokens and the nodes of the source code have been printed
+	source_markers: Vec<SourceMarker>,
+	/// The starts of the nodes that wait for their first token to be printed
+	pending_positions: Vec<TextSize>,
+	/// Where the last printed token ends, which is where the nodes end
+	last_token_end: TextSize,
 	// We'll need to clone the line suffixes elements into the state.
 	// I guess that's fine. They're only used for comments and should, therefore, be very limited
 	// in size.
@@ -332,6 +357,9 @@ impl PrinterState {
 			generated_column: self.generated_column,
 			line_width: self.line_width,
 			buffer_position: self.buffer.len(),
+			source_markers_len: self.source_markers.len(),
+			pending_positions: self.pending_positions.clone(),
+			last_token_end: self.last_token_end,
 		}
 	}
 
@@ -344,6 +372,19 @@ impl PrinterState {
 		self.generated_line = snapshot.generated_line;
 		self.line_width = snapshot.line_width;
 		self.buffer.truncate(snapshot.buffer_position);
+		self.source_markers.truncate(snapshot.source_markers_len);
+		self.pending_positions = snapshot.pending_positions;
+		self.last_token_end = snapshot.last_token_end;
+	}
+
+	/// Records that the nodes waiting for their first token start at `dest` in the output
+	fn resolve_pending_positions(&mut self, dest: TextSize) {
+		for position in self.pending_positions.drain(..) {
+			self.source_markers.push(SourceMarker {
+				source: TextRange::empty(position),
+				dest: TextRange::empty(dest),
+			});
+		}
 	}
 }
 
@@ -356,6 +397,9 @@ struct PrinterStateSnapshot {
 	generated_line: usize,
 	line_width: usize,
 	buffer_position: usize,
+	source_markers_len: usize,
+	pending_positions: Vec<TextSize>,
+	last_token_end: TextSize,
 }
 
 /// Stores arguments passed to `print_element` call, holding the state specific to printing an element.
@@ -436,12 +480,15 @@ impl<'a> ElementCallQueue<'a> {
 
 #[cfg(test)]
 mod tests {
-	use crate::format_element::join_elements;
-	use crate::printer::{LineEnding, Printer, PrinterOptions};
+	use crate::format_element::{join_elements, SourcePosition};
+	use crate::printer::{Printer, PrinterOptions};
+	use crate::LineEnding;
 	use crate::{
 		block_indent, format_elements, group_elements, hard_line_break, if_group_breaks,
 		soft_indent, soft_line_break, soft_line_break_or_space, token, FormatElement, Formatted,
+		SourceMarker,
 	};
+	use rslint_parser::{TextRange, TextSize};
 
 	/// Prints the given element with the default printer options
 	fn print_element<T: Into<FormatElement>>(element: T) -> Formatted {
@@ -514,7 +561,7 @@ two lines`,
 	#[test]
 	fn it_converts_line_endings_in_strings() {
 		let options = PrinterOptions {
-			line_ending: LineEnding::CarriageReturnLineFeed,
+			line_ending: LineEnding::Crlf,
 			..PrinterOptions::default()
 		};
 
@@ -533,6 +580,52 @@ two lines`,
 		);
 	}
 
+	#[test]
+	fn it_removes_the_final_line_break() {
+		let options = PrinterOptions {
+			line_ending: LineEnding::Crlf,
+			final_newline: false,
+			..PrinterOptions::default()
+		};
+
+		let program = format_elements![
+			token("a;"),
+			hard_line_break(),
+			token("b;"),
+			hard_line_break()
+		];
+
+		let result = Printer::new(options).print(&program);
+
+		assert_eq!("a;\r\nb;", result.code());
+	}
+
+	#[test]
+	fn it_records_where_the_nodes_start_and_end() {
+		let node = format_elements![
+			FormatElement::from(SourcePosition::Start(TextSize::from(2))),
+			token("bc"),
+			FormatElement::from(SourcePosition::End(TextSize::from(6))),
+		];
+		let result = print_element(format_elements![token("{"), block_indent(node), token("}")]);
+
+		assert_eq!("{\n  bc\n}", result.code());
+		assert_eq!(
+			result.sourcemap(),
+			[
+				SourceMarker {
+					source: TextRange::empty(TextSize::from(2)),
+					dest: TextRange::empty(TextSize::from(4)),
+				},
+				SourceMarker {
+					source: TextRange::empty(TextSize::from(6)),
+					dest: TextRange::empty(TextSize::from(6)),
+				},
+			],
+			"the start is where the first token is printed, after the indention"
+		);
+	}
+
 	#[test]
 	fn it_breaks_parent_groups_if_they_dont_fit_on_a_single_line() {
 		let result = print_element(create_array_element(vec![
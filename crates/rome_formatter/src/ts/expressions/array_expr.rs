@@ -1,20 +1,28 @@
+use crate::intersperse::Intersperse;
 use crate::{
-	empty_element, format_elements, group_elements, if_group_breaks, join_elements, soft_indent,
+	concat_elements, empty_element, format_elements, group_elements, if_group_breaks, soft_indent,
 	soft_line_break_or_space, token, FormatElement, FormatResult, Formatter, ToFormatElement,
 };
-use rslint_parser::ast::{JsArrayExpression, JsArrayHole};
+use rslint_parser::ast::{JsAnyArrayElement, JsArrayExpression, JsArrayHole};
 
 impl ToFormatElement for JsArrayExpression {
 	fn to_format_element(&self, formatter: &Formatter) -> FormatResult<FormatElement> {
-		let elements = formatter.format_nodes(self.elements())?;
+		let elements = self.elements();
+		// A trailing hole must keep its comma, `[1, ,]` has two elements but `[1, ]` only one
+		let trailing_comma = match elements.iter().last() {
+			Some(JsAnyArrayElement::JsArrayHole(_)) => token(","),
+			_ => if_group_breaks(token(",")),
+		};
+		let elements = formatter.format_nodes(elements)?;
 
 		Ok(group_elements(format_elements!(
 			formatter.format_token(&self.l_brack_token()?)?,
-			soft_indent(join_elements(
+			// Not using `join_elements` because it skips the empty elements of the holes
+			soft_indent(concat_elements(Intersperse::new(
+				elements,
 				format_elements!(token(","), soft_line_break_or_space()),
-				elements
-			)),
-			if_group_breaks(token(",")),
+			))),
+			trailing_comma,
 			formatter.format_token(&self.r_brack_token()?)?,
 		)))
 	}
@@ -20,9 +20,9 @@ mod statements;
 
 #[cfg(test)]
 mod test {
-	use rslint_parser::parse_text;
+	use rslint_parser::{parse_text, TextSize};
 
-	use crate::Formatter;
+	use crate::{FormatOptions, Formatter};
 
 	#[test]
 	fn arrow_function() {
@@ -74,4 +74,58 @@ mod test {
 "#
 		);
 	}
+
+	/// Formats `src` with the cursor at the `|` and returns the formatted code with the new cursor
+	fn format_with_cursor(src: &str) -> String {
+		let cursor = src.find('|').unwrap();
+		let src = src.replace('|', "");
+		let tree = parse_text(&src, 0);
+
+		let (result, cursor) = Formatter::format_with_cursor(
+			&tree.syntax(),
+			FormatOptions::default(),
+			TextSize::from(cursor as u32),
+		)
+		.unwrap();
+
+		let mut code = result.code().clone();
+		code.insert(usize::from(cursor), '|');
+		code
+	}
+
+	#[test]
+	fn cursor_inside_a_token() {
+		assert_eq!(
+			format_with_cursor("let   val|ue=[1,2]"),
+			"let val|ue = [1, 2];\n"
+		);
+	}
+
+	#[test]
+	fn cursor_between_tokens() {
+		assert_eq!(
+			format_with_cursor("let value=[1,  |  2]"),
+			"let value = [1, |2];\n"
+		);
+		assert_eq!(
+			format_with_cursor("function foo() {\n|return 1 }"),
+			"function foo() {\n\t|return 1;\n}\n"
+		);
+	}
+
+	#[test]
+	fn cursor_after_a_token() {
+		assert_eq!(format_with_cursor("let a = 1|"), "let a = 1|;\n");
+	}
+
+	#[test]
+	fn cursor_in_a_broken_group() {
+		// The printer first tries to print the array on a single line, the positions recorded
+		// during that attempt must be discarded
+		let src = "let value = [\"aaaaaaaaaaaaaaaaaaaa\", \"bbbbbbbbbbbbbbbbbbbb\", \"cccccccccccccccccccc\", d|d];";
+		let result = format_with_cursor(src);
+
+		assert!(result.starts_with("let value = [\n"));
+		assert!(result.contains("\n\td|d"));
+	}
 }
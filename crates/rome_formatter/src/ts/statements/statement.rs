@@ -1,5 +1,5 @@
 use crate::{FormatElement, FormatResult, Formatter, ToFormatElement};
-use rslint_parser::ast::JsAnyStatement;
+use rslint_parser::{ast::JsAnyStatement, AstNode};
 
 impl ToFormatElement for JsAnyStatement {
 	fn to_format_element(&self, formatter: &Formatter) -> FormatResult<FormatElement> {
@@ -55,12 +55,13 @@ impl ToFormatElement for JsAnyStatement {
 				decl.to_format_element(formatter)
 			}
 			JsAnyStatement::JsUnknownStatement(_) => todo!(),
-			JsAnyStatement::ImportDecl(_) => todo!(),
-			JsAnyStatement::ExportNamed(_) => todo!(),
-			JsAnyStatement::ExportDefaultDecl(_) => todo!(),
-			JsAnyStatement::ExportDefaultExpr(_) => todo!(),
-			JsAnyStatement::ExportWildcard(_) => todo!(),
-			JsAnyStatement::ExportDecl(_) => todo!(),
+			// The declarations of the modules aren't formatted yet, they are kept as they are
+			JsAnyStatement::ImportDecl(_)
+			| JsAnyStatement::ExportNamed(_)
+			| JsAnyStatement::ExportDefaultDecl(_)
+			| JsAnyStatement::ExportDefaultExpr(_)
+			| JsAnyStatement::ExportWildcard(_)
+			| JsAnyStatement::ExportDecl(_) => Ok(formatter.format_raw(self.syntax())),
 			JsAnyStatement::TsImportEqualsDecl(_) => todo!(),
 			JsAnyStatement::TsExportAssignment(_) => todo!(),
 			JsAnyStatement::TsNamespaceExportDecl(_) => todo!(),
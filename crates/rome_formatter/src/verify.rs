@@ -0,0 +1,643 @@
+//! Checks that the output of the formatter can be trusted.
+//!
+//! The formatter is only allowed to change the trivia of a file: whitespace, line breaks and the
+//! position of comments. [verify] catches the formatter changes that silently change the meaning of
+//! the code by checking that:
+//! - the formatted code parses to the same tokens and the same tree as the original code, or to the
+//!   same properties when the keys of JSON objects are sorted
+//! - formatting the formatted code again doesn't change it anymore
+//!
+//! Both checks point at the first node of the original code where things diverge.
+
+use crate::format_json::key_value;
+use crate::{
+	format_source, json_options, read_source, FormatError, FormatOptions, FormatResult, Formatted,
+	JsonKeyOrder,
+};
+use rome_core::{
+	configuration::FormatConfiguration, file_handlers::ExtensionHandler, fs::FileSystem,
+};
+use rome_path::RomePath;
+use rslint_errors::Diagnostic;
+use rslint_parser::ast::LiteralProp;
+use rslint_parser::{
+	AstNode, Syntax, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxNodeExt, SyntaxToken, TextSize, T,
+};
+
+/// Formats the file and verifies the result, see [verify].
+pub fn format_and_verify(
+	rome_path: &RomePath,
+	fs: &dyn FileSystem,
+	configuration: &FormatConfiguration,
+) -> FormatResult<Formatted> {
+	let source = read_source(rome_path, fs)?;
+	let formatted = format_source(rome_path, source.as_str(), configuration)?;
+	verify(rome_path, source.as_str(), &formatted, configuration)?;
+	Ok(formatted)
+}
+
+/// Verifies that `formatted`, the result of formatting `source` with `configuration`, is safe to
+/// use.
+///
+/// Returns [FormatError::VerificationFailed] with a diagnostic pointing at the first diverging node
+/// of `source` when the formatted code doesn't have the same tokens and tree as `source`, or
+/// when formatting it again gives a different result.
+///
+/// When the keys of JSON objects are sorted, the properties of each object are compared in key
+/// order instead, because reordering them is the whole point of the option.
+pub fn verify(
+	rome_path: &RomePath,
+	source: &str,
+	formatted: &Formatted,
+	configuration: &FormatConfiguration,
+) -> FormatResult<()> {
+	let handler = match rome_path.get_handler() {
+		Some(handler) => handler.as_ref(),
+		None => return Err(FormatError::UnsupportedLanguage),
+	};
+	let language = handler.language();
+
+	let syntax = rome_path.syntax().unwrap_or_else(|| handler.syntax());
+	let original =
+		ParsedSource::new(handler, syntax, source).ok_or(FormatError::UnsupportedLanguage)?;
+	let output = ParsedSource::new(handler, syntax, formatted.code())
+		.ok_or(FormatError::UnsupportedLanguage)?;
+
+	let options = FormatOptions::from(configuration);
+	let reordered = language.json_dialect().is_some()
+		&& json_options(rome_path, &options).key_order != JsonKeyOrder::Preserve;
+
+	let diverging = if reordered {
+		check_sorted_equivalence(&original, &output)
+	} else {
+		check_equivalence(&original, &output)
+	};
+
+	if let Some(diagnostic) = diverging {
+		return Err(FormatError::VerificationFailed(diagnostic));
+	}
+
+	let second_pass = match format_source(rome_path, formatted.code(), configuration) {
+		Ok(second_pass) => second_pass,
+		Err(_) => {
+			return Err(FormatError::VerificationFailed(
+				Diagnostic::error(
+					0,
+					"VerificationError",
+					"The formatted code can't be formatted again",
+				)
+				.primary(
+					original.range(),
+					"formatting the result of the formatter failed",
+				),
+			));
+		}
+	};
+
+	match check_idempotency(&original, &output, second_pass.code(), !reordered) {
+		Some(diagnostic) => Err(FormatError::VerificationFailed(diagnostic)),
+		None => Ok(()),
+	}
+}
+
+/// The tree of a source together with the offset of its ranges relative to the source text
+struct ParsedSource<'source> {
+	text: &'source str,
+	root: SyntaxNode,
+	offset: usize,
+}
+
+impl<'source> ParsedSource<'source> {
+	/// Parses `text` with `handler`, the JavaScript files with `syntax`. Returns `None` if the
+	/// handler can't parse its language
+	fn new(handler: &dyn ExtensionHandler, syntax: Syntax, text: &'source str) -> Option<Self> {
+		let root = handler.parse(text, syntax)?.root().clone();
+		// The JSON value is wrapped in parentheses
+		let offset = match handler.language().json_dialect() {
+			Some(_) => 1,
+			None => 0,
+		};
+
+		Some(Self { text, root, offset })
+	}
+
+	/// The range of the whole source text
+	fn range(&self) -> std::ops::Range<usize> {
+		0..self.text.len()
+	}
+
+	/// The range in the source text of the given element of the tree, without its trivia
+	fn element_range(&self, element: &SyntaxElement) -> std::ops::Range<usize> {
+		let range = match element {
+			// Empty nodes, like array holes, have no tokens to trim the range to
+			SyntaxElement::Node(node) if node.lossy_tokens().is_empty() => node.text_range(),
+			SyntaxElement::Node(node) => node.trimmed_range(),
+			SyntaxElement::Token(token) => token.text_range(),
+		};
+
+		let unwrap = |offset: TextSize| {
+			usize::from(offset)
+				.saturating_sub(self.offset)
+				.min(self.text.len())
+		};
+
+		unwrap(range.start())..unwrap(range.end())
+	}
+
+	/// The range in the source text of the node that contains the token, without its trivia
+	fn parent_range(&self, token: &SyntaxToken) -> std::ops::Range<usize> {
+		match token.parent() {
+			Some(parent) => self.element_range(&parent.into()),
+			None => self.element_range(&token.clone().into()),
+		}
+	}
+}
+
+/// Checks that the formatted code has the same tokens and the same tree as the original code.
+///
+/// When [SyntaxNodeExt::lexical_eq] fails, the tokens are compared again without the punctuation
+/// that the formatter adds and removes on purpose, see [is_optional_punctuation].
+fn check_equivalence(original: &ParsedSource, output: &ParsedSource) -> Option<Diagnostic> {
+	if original.root.lexical_eq(&output.root) {
+		return check_structure(original, output);
+	}
+
+	let original_tokens = preserved_tokens(&original.root);
+	let output_tokens = preserved_tokens(&output.root);
+	let index = original_tokens
+		.iter()
+		.zip(&output_tokens)
+		.position(|(left, right)| left.text() != right.text());
+
+	match index {
+		None if original_tokens.len() == output_tokens.len() => check_structure(original, output),
+		index => Some(changed_tokens(
+			original,
+			&original_tokens,
+			&output_tokens,
+			index.unwrap_or_else(|| original_tokens.len().min(output_tokens.len())),
+		)),
+	}
+}
+
+/// Whether `element` is punctuation that the formatter may add or remove without changing the
+/// meaning of the code: the semicolons that end statements, and the commas directly followed by
+/// a closing `}`, `]` or `)`. `next` is the element that follows it.
+///
+/// Ignoring them can't hide a change of meaning on its own: a semicolon that changes how the code
+/// parses, like the one inserted after `return`, also changes the tree, which [check_structure]
+/// catches. The semicolons of `for` loops are never optional.
+fn is_optional_punctuation(element: &SyntaxElement, next: Option<&SyntaxElement>) -> bool {
+	match element.kind() {
+		T![;] => element
+			.parent()
+			.map_or(true, |parent| parent.kind() != SyntaxKind::FOR_STMT),
+		T![,] => matches!(
+			next.map(|next| next.kind()),
+			Some(T!['}'] | T![']'] | T![')'])
+		),
+		_ => false,
+	}
+}
+
+/// Removes the trivia and the [optional punctuation](is_optional_punctuation) from `elements`
+fn preserved_elements(elements: Vec<SyntaxElement>) -> Vec<SyntaxElement> {
+	let elements: Vec<_> = elements
+		.into_iter()
+		.filter(|element| !element.kind().is_trivia())
+		.collect();
+
+	elements
+		.iter()
+		.enumerate()
+		.filter(|(index, element)| !is_optional_punctuation(element, elements.get(index + 1)))
+		.map(|(_, element)| element.clone())
+		.collect()
+}
+
+/// The tokens of `root` that the formatter must preserve, see [preserved_elements]
+fn preserved_tokens(root: &SyntaxNode) -> Vec<SyntaxToken> {
+	let tokens = root.lossy_tokens().into_iter().map(SyntaxElement::from);
+
+	preserved_elements(tokens.collect())
+		.into_iter()
+		.filter_map(|element| element.into_token())
+		.collect()
+}
+
+/// Reports the token at `index` in the preserved tokens of the formatted code, the first one that
+/// differs from the original code
+fn changed_tokens(
+	original: &ParsedSource,
+	original_tokens: &[SyntaxToken],
+	output_tokens: &[SyntaxToken],
+	index: usize,
+) -> Diagnostic {
+	let found = match output_tokens.get(index) {
+		Some(token) => format!("`{}`", token.text()),
+		None => String::from("the end of the file"),
+	};
+
+	// Point at the node that contains the divergence, a single token is rarely useful
+	let range = match original_tokens.get(index) {
+		Some(token) => original.parent_range(token),
+		None => original.text.len()..original.text.len(),
+	};
+
+	Diagnostic::error(
+		0,
+		"VerificationError",
+		"The formatter changed the tokens of the code",
+	)
+	.primary(range, format!("the formatted code has {} here", found))
+	.footer_note("only the whitespace and the position of comments should change")
+}
+
+/// Checks that the nodes of the formatted code have the same kinds as the nodes of the original
+/// code, in preorder
+fn check_structure(original: &ParsedSource, output: &ParsedSource) -> Option<Diagnostic> {
+	let mut original_nodes = original.root.descendants();
+	let mut output_nodes = output.root.descendants();
+
+	loop {
+		let (original_node, output_node) = match (original_nodes.next(), output_nodes.next()) {
+			(None, None) => return None,
+			(Some(left), Some(right)) if left.kind() == right.kind() => continue,
+			pair => pair,
+		};
+
+		let found = match &output_node {
+			Some(node) => format!("{:?}", node.kind()),
+			None => String::from("the end of the file"),
+		};
+
+		let range = match original_node {
+			Some(node) => original.element_range(&node.into()),
+			None => original.text.len()..original.text.len(),
+		};
+
+		return Some(
+			Diagnostic::error(
+				0,
+				"VerificationError",
+				"The formatter changed the structure of the code",
+			)
+			.primary(
+				range,
+				format!("the formatted code parses to {} instead", found),
+			)
+			.footer_note("only the whitespace and the position of comments should change"),
+		);
+	}
+}
+
+/// Checks that the formatted JSON has the same values as the original JSON when the keys of its
+/// objects are sorted. The properties of each object are paired by key, every other node is
+/// compared element by element.
+fn check_sorted_equivalence(original: &ParsedSource, output: &ParsedSource) -> Option<Diagnostic> {
+	let (element, found) = diverging_json_element(&original.root, &output.root)?;
+
+	Some(
+		Diagnostic::error(
+			0,
+			"VerificationError",
+			"The formatter changed the values of the JSON",
+		)
+		.primary(
+			original.element_range(&element),
+			format!("the formatted code has {} here", found),
+		)
+		.footer_note(
+			"only the whitespace, the position of comments and the order of the keys should change",
+		),
+	)
+}
+
+/// Finds the first element of `original` that doesn't match `output`, together with a description
+/// of what `output` has instead
+fn diverging_json_element(
+	original: &SyntaxNode,
+	output: &SyntaxNode,
+) -> Option<(SyntaxElement, String)> {
+	if original.kind() != output.kind() {
+		return Some((original.clone().into(), format!("{:?}", output.kind())));
+	}
+
+	let children = |node: &SyntaxNode| match node.parent() {
+		Some(parent)
+			if parent.kind() == SyntaxKind::OBJECT_EXPR && node.kind() == SyntaxKind::LIST =>
+		{
+			sorted_properties(node)
+		}
+		_ => preserved_elements(node.children_with_tokens().collect()),
+	};
+
+	let mut output_children = children(output).into_iter();
+
+	for original_child in children(original) {
+		match (original_child, output_children.next()) {
+			(SyntaxElement::Node(left), Some(SyntaxElement::Node(right))) => {
+				if let Some(diverging) = diverging_json_element(&left, &right) {
+					return Some(diverging);
+				}
+			}
+			(SyntaxElement::Token(left), Some(SyntaxElement::Token(right)))
+				if left.text() == right.text() => {}
+			(original_child, output_child) => {
+				return Some((original_child, describe_element(output_child)))
+			}
+		}
+	}
+
+	output_children.next().map(|output_child| {
+		(
+			original.clone().into(),
+			describe_element(Some(output_child)),
+		)
+	})
+}
+
+/// The properties in the list of a JSON object, sorted by key. The sort is stable so duplicated keys stay in the
+/// same order, like the formatter keeps them.
+fn sorted_properties(list: &SyntaxNode) -> Vec<SyntaxElement> {
+	let mut properties: Vec<_> = list
+		.children()
+		.map(|property| {
+			let key = property
+				.try_to::<LiteralProp>()
+				.and_then(|property| property.key().ok())
+				.map(|key| key_value(key.syntax()));
+			(key, property)
+		})
+		.collect();
+
+	properties.sort_by(|(left, _), (right, _)| left.cmp(right));
+	properties
+		.into_iter()
+		.map(|(_, property)| property.into())
+		.collect()
+}
+
+/// Describes an element of the formatted code for a diagnostic
+fn describe_element(element: Option<SyntaxElement>) -> String {
+	match element {
+		Some(SyntaxElement::Token(token)) => format!("`{}`", token.text()),
+		Some(SyntaxElement::Node(node)) => format!("{:?}", node.kind()),
+		None => String::from("nothing"),
+	}
+}
+
+/// Checks that formatting the formatted code again doesn't change it.
+///
+/// `same_tokens` tells whether the preserved tokens of the output are known to match the ones of
+/// the original code, in which case the divergence is reported on the matching node of the
+/// original code.
+fn check_idempotency(
+	original: &ParsedSource,
+	output: &ParsedSource,
+	second_pass: &str,
+	same_tokens: bool,
+) -> Option<Diagnostic> {
+	let first_pass = output.text;
+	let divergence = first_pass
+		.bytes()
+		.zip(second_pass.bytes())
+		.position(|(left, right)| left != right)
+		.unwrap_or_else(|| first_pass.len().min(second_pass.len()));
+
+	if first_pass.len() == second_pass.len() && divergence == first_pass.len() {
+		return None;
+	}
+
+	let range = if same_tokens {
+		let tokens = preserved_tokens(&output.root);
+		let offset = TextSize::from((divergence + output.offset) as u32);
+		let index = tokens
+			.iter()
+			.position(|token| token.text_range().end() > offset);
+
+		index
+			.and_then(|index| preserved_tokens(&original.root).get(index).cloned())
+			.map(|token: SyntaxToken| original.parent_range(&token))
+			.unwrap_or_else(|| original.text.len()..original.text.len())
+	} else {
+		original.range()
+	};
+
+	Some(
+		Diagnostic::error(
+			0,
+			"VerificationError",
+			"Formatting the formatted code changes it again",
+		)
+		.primary(
+			range,
+			"this code is formatted differently on the second pass",
+		)
+		.footer_note(format!(
+			"first pass: `{}`\nsecond pass: `{}`",
+			line_at(first_pass, divergence),
+			line_at(second_pass, divergence)
+		)),
+	)
+}
+
+/// The line of `text` that contains the byte at `offset`
+fn line_at(text: &str, offset: usize) -> &str {
+	let offset = offset.min(text.len());
+	let start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+	let end = text[offset..]
+		.find('\n')
+		.map_or(text.len(), |index| offset + index);
+
+	text[start..end].trim_end_matches('\r')
+}
+
+#[cfg(test)]
+mod test {
+	use super::{check_equivalence, check_idempotency, check_sorted_equivalence, ParsedSource};
+	use crate::file_handlers::{javascript::JsFileHandler, json::JsonFileHandler};
+	use rslint_parser::Syntax;
+
+	#[test]
+	fn accepts_trivia_changes() {
+		let original = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"let a   =\n1 ;",
+		)
+		.unwrap();
+		let output =
+			ParsedSource::new(&JsFileHandler::default(), Syntax::default(), "let a = 1;\n")
+				.unwrap();
+
+		assert_eq!(check_equivalence(&original, &output), None);
+
+		let original = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"f(a, [1, 2,],)",
+		)
+		.unwrap();
+		let output = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"f(a, [1, 2]);\n",
+		)
+		.unwrap();
+
+		assert_eq!(
+			check_equivalence(&original, &output),
+			None,
+			"semicolons and trailing commas may change"
+		);
+	}
+
+	#[test]
+	fn reports_changed_tokens() {
+		let original = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"let a = 1;\nlet b = 2;\n",
+		)
+		.unwrap();
+		let output = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"let a = 1;\nlet b = 3;\n",
+		)
+		.unwrap();
+
+		let diagnostic = check_equivalence(&original, &output).unwrap();
+		assert_eq!(
+			diagnostic.title,
+			"The formatter changed the tokens of the code"
+		);
+		assert_eq!(diagnostic.primary.unwrap().span.range, 19..20);
+	}
+
+	#[test]
+	fn reports_changed_structure() {
+		// The line break after `return` inserts a semicolon, so `a` isn't returned anymore
+		let original = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"function f() { return a; }",
+		)
+		.unwrap();
+		let output = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"function f() { return\na; }",
+		)
+		.unwrap();
+
+		let diagnostic = check_equivalence(&original, &output).unwrap();
+		assert_eq!(
+			diagnostic.title,
+			"The formatter changed the structure of the code"
+		);
+	}
+
+	#[test]
+	fn reports_removed_array_holes() {
+		let original =
+			ParsedSource::new(&JsFileHandler::default(), Syntax::default(), "let a = [,];")
+				.unwrap();
+		let output =
+			ParsedSource::new(&JsFileHandler::default(), Syntax::default(), "let a = [];").unwrap();
+
+		let diagnostic = check_equivalence(&original, &output).unwrap();
+		assert_eq!(
+			diagnostic.title,
+			"The formatter changed the structure of the code"
+		);
+		assert_eq!(diagnostic.primary.unwrap().span.range, 9..9);
+	}
+
+	#[test]
+	fn reports_json_ranges_in_the_original_source() {
+		let original = ParsedSource::new(
+			&JsonFileHandler::default(),
+			Syntax::default(),
+			"{ \"a\": 1 }",
+		)
+		.unwrap();
+		let output = ParsedSource::new(
+			&JsonFileHandler::default(),
+			Syntax::default(),
+			"{ \"a\": 2 }",
+		)
+		.unwrap();
+
+		let diagnostic = check_equivalence(&original, &output).unwrap();
+		assert_eq!(diagnostic.primary.unwrap().span.range, 7..8);
+	}
+
+	#[test]
+	fn compares_sorted_json_properties_by_key() {
+		let original = ParsedSource::new(
+			&JsonFileHandler::default(),
+			Syntax::default(),
+			"{ \"b\": [1, 2], \"a\": { \"d\": 3, \"c\": 4 } }",
+		)
+		.unwrap();
+		let output = ParsedSource::new(
+			&JsonFileHandler::default(),
+			Syntax::default(),
+			"{ \"a\": { \"c\": 4, \"d\": 3 }, \"b\": [1, 2] }",
+		)
+		.unwrap();
+
+		assert_eq!(check_sorted_equivalence(&original, &output), None);
+
+		let output = ParsedSource::new(
+			&JsonFileHandler::default(),
+			Syntax::default(),
+			"{ \"a\": { \"c\": 4, \"d\": 5 }, \"b\": [1, 2] }",
+		)
+		.unwrap();
+
+		let diagnostic = check_sorted_equivalence(&original, &output).unwrap();
+		assert_eq!(
+			diagnostic.title,
+			"The formatter changed the values of the JSON"
+		);
+		assert_eq!(diagnostic.primary.unwrap().span.range, 27..28);
+
+		let output = ParsedSource::new(
+			&JsonFileHandler::default(),
+			Syntax::default(),
+			"{ \"a\": { \"c\": 4, \"d\": 3 } }",
+		)
+		.unwrap();
+
+		let diagnostic = check_sorted_equivalence(&original, &output).unwrap();
+		assert_eq!(diagnostic.primary.unwrap().span.range, 2..13);
+	}
+
+	#[test]
+	fn reports_unstable_formatting() {
+		let original = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"let a = 1;\nlet b = 2;",
+		)
+		.unwrap();
+		let output = ParsedSource::new(
+			&JsFileHandler::default(),
+			Syntax::default(),
+			"let a = 1;\nlet b = 2;\n",
+		)
+		.unwrap();
+
+		assert_eq!(
+			check_idempotency(&original, &output, "let a = 1;\nlet b = 2;\n", true),
+			None
+		);
+
+		let diagnostic =
+			check_idempotency(&original, &output, "let a = 1;\nlet  b = 2;\n", true).unwrap();
+		assert_eq!(diagnostic.primary.unwrap().span.range, 15..16);
+	}
+}
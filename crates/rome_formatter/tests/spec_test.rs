@@ -1,5 +1,6 @@
-use rome_core::create_app;
-use rome_formatter::{format_file, FormatOptions};
+use rome_core::{configuration::FormatConfiguration, fs::OsFileSystem};
+use rome_formatter::{create_app, format_file, verify};
+use rome_path::RomePath;
 use std::fs;
 use std::path::Path;
 
@@ -14,6 +15,8 @@ use std::path::Path;
 ///
 /// * `json/null` -> input: `tests/specs/json/null.json`, expected output: `tests/specs/json/null.expected.json`
 /// * `null` -> input: `tests/specs/null.json`, expected output: `tests/specs/null.expected.json`
+///
+/// The output must also pass the checks of [verify]
 pub fn run(spec_input_file: &str, expected_file: &str) {
 	let app = create_app();
 	let file_path = &spec_input_file;
@@ -32,8 +35,20 @@ pub fn run(spec_input_file: &str, expected_file: &str) {
 		expected_file.display(),
 	);
 
-	let result = format_file(file_path, FormatOptions::default(), &app);
+	let result = format_file(
+		file_path,
+		&OsFileSystem,
+		&FormatConfiguration::default(),
+		&app,
+	)
+	.expect("The input could not be formatted");
 	let expected_output = fs::read_to_string(expected_file).unwrap();
 
 	assert_eq!(&expected_output, result.code());
+
+	let rome_path = RomePath::new(file_path).deduce_handler(&app);
+	let input = fs::read_to_string(spec_input_file).unwrap();
+	if let Err(error) = verify(&rome_path, &input, &result, &FormatConfiguration::default()) {
+		panic!("The formatted output failed the verification: {:#?}", error);
+	}
 }
@@ -6,6 +6,16 @@ mod formatter {
 		tests_macros::gen_tests! {"tests/specs/json/*.json", spec_test::run}
 	}
 
+	mod jsonc {
+		use crate::spec_test;
+		tests_macros::gen_tests! {"tests/specs/jsonc/*.jsonc", spec_test::run}
+	}
+
+	mod json5 {
+		use crate::spec_test;
+		tests_macros::gen_tests! {"tests/specs/json5/*.json5", spec_test::run}
+	}
+
 	mod js {
 		use crate::spec_test;
 		tests_macros::gen_tests! {"tests/specs/js/**/**.js", spec_test::run}
@@ -0,0 +1,353 @@
+//! Rome's daemon: a long-lived server that keeps a [Workspace] in memory, so that the commands
+//! of the CLI don't pay for the startup of a process, nor for parsing again the files that
+//! didn't change since the previous command.
+//!
+//! The daemon listens on a Unix domain socket and speaks JSON-RPC, with the framing of the
+//! language server. It serves one connection at a time, as the syntax trees of the workspace
+//! can't be shared between threads. It handles the requests:
+//! - `rome/format`, with the `path` of a file, its `content` and the `configuration` to format
+//!   it with. The result is the formatted code, or `null` if Rome doesn't format this kind of file;
+//! - `rome/check`, with the same parameters. The result is `null` if Rome can't parse this kind of
+//!   file, otherwise whether the file has `syntaxErrors` and its `formatted` code. The code is
+//!   `null` when the file has syntax errors or its formatting is disabled;
+//! - `shutdown`, which stops the daemon once it has answered.
+//!
+//! The daemon keeps the [MAX_OPEN_FILES] files it was most recently asked about, and forgets the
+//! others.
+use crate::transport::{read_message, write_message, Message, ResponseError};
+use rome_core::{
+	configuration::{FormatConfiguration, ProjectSettings},
+	workspace::Workspace,
+};
+use rome_formatter::{create_app, FormatError, FormatResult, WorkspaceServices};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+	collections::VecDeque,
+	io::{self, BufReader},
+	os::unix::net::{UnixListener, UnixStream},
+	panic::{catch_unwind, AssertUnwindSafe},
+	path::{Path, PathBuf},
+};
+
+/// The number of files whose content and syntax tree the daemon keeps between requests
+pub const MAX_OPEN_FILES: usize = 512;
+
+/// Serves the clients of `listener` until one of them asks the daemon to shut down
+pub fn serve(listener: UnixListener) -> io::Result<()> {
+	let mut daemon = Daemon::new(MAX_OPEN_FILES);
+
+	for stream in listener.incoming() {
+		let stream = match stream {
+			Ok(stream) => stream,
+			Err(error) => {
+				eprintln!("error: the daemon failed to accept a connection: {}", error);
+				continue;
+			}
+		};
+
+		// A client that goes away in the middle of a request doesn't stop the daemon
+		if let Ok(false) = daemon.serve_connection(stream) {
+			break;
+		}
+	}
+	Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct FormatParams {
+	path: PathBuf,
+	content: String,
+	configuration: FormatConfiguration,
+}
+
+struct Daemon {
+	/// The files formatted by the previous requests, with their syntax trees
+	workspace: Workspace<WorkspaceServices>,
+	/// The open files, from the least to the most recently requested
+	recent_files: VecDeque<PathBuf>,
+	/// The number of files kept open, the least recently requested file is closed beyond that
+	capacity: usize,
+}
+
+impl Daemon {
+	fn new(capacity: usize) -> Self {
+		Self {
+			workspace: Workspace::new(
+				WorkspaceServices::new(create_app()),
+				ProjectSettings::default(),
+			),
+			recent_files: VecDeque::new(),
+			capacity,
+		}
+	}
+
+	/// Handles the requests of a client until it closes the connection. Returns `false` when the
+	/// daemon must stop
+	fn serve_connection(&mut self, stream: UnixStream) -> io::Result<bool> {
+		let mut reader = BufReader::new(&stream);
+		let mut writer = &stream;
+
+		while let Some(message) = read_message(&mut reader)? {
+			// The clients only send requests
+			let (id, method) = match (message.id, message.method) {
+				(Some(id), Some(method)) => (id, method),
+				_ => continue,
+			};
+
+			let result = match method.as_str() {
+				"rome/format" => serde_json::from_value(message.params)
+					.map_err(|error| {
+						ResponseError::new(ResponseError::INVALID_PARAMS, error.to_string())
+					})
+					.and_then(|params| self.format(params))
+					.map(|code| json!(code)),
+				"rome/check" => serde_json::from_value(message.params)
+					.map_err(|error| {
+						ResponseError::new(ResponseError::INVALID_PARAMS, error.to_string())
+					})
+					.and_then(|params| self.check(params)),
+				"shutdown" => {
+					let response = json!({ "jsonrpc": "2.0", "id": id, "result": Value::Null });
+					write_message(&mut writer, &response)?;
+					return Ok(false);
+				}
+				_ => Err(ResponseError::new(
+					ResponseError::METHOD_NOT_FOUND,
+					format!("unknown method {}", method),
+				)),
+			};
+
+			let response = match result {
+				Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+				Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+			};
+			write_message(&mut writer, &response)?;
+		}
+
+		Ok(true)
+	}
+
+	/// Formats a file with the configuration of the client. The file is only parsed again if its
+	/// content changed since the previous request
+	fn format(&mut self, params: FormatParams) -> Result<Option<String>, ResponseError> {
+		let FormatParams {
+			path,
+			content,
+			configuration,
+		} = params;
+		let path = path.as_path();
+		self.update(path, content);
+
+		let result = self.catch_panic(path, |workspace| {
+			workspace.format_file_with(path, &configuration)
+		})?;
+		match result {
+			Ok(formatted) => Ok(Some(formatted.code().clone())),
+			Err(FormatError::UnsupportedLanguage | FormatError::CapabilityDisabled) => Ok(None),
+			Err(error) => Err(request_failed(error)),
+		}
+	}
+
+	/// Reports whether a file has syntax errors, and formats it with the configuration of the
+	/// client when it doesn't. The file is only parsed again if its content changed since the
+	/// previous request
+	fn check(&mut self, params: FormatParams) -> Result<Value, ResponseError> {
+		let FormatParams {
+			path,
+			content,
+			configuration,
+		} = params;
+		let path = path.as_path();
+		self.update(path, content);
+
+		let result = self.catch_panic(path, |workspace| -> FormatResult<Value> {
+			if !workspace.pull_diagnostics(path)?.is_empty() {
+				return Ok(json!({ "syntaxErrors": true, "formatted": Value::Null }));
+			}
+			let formatted = match workspace.format_file_with(path, &configuration) {
+				Ok(formatted) => Some(formatted.code().clone()),
+				Err(FormatError::CapabilityDisabled) => None,
+				Err(error) => return Err(error),
+			};
+			Ok(json!({ "syntaxErrors": false, "formatted": formatted }))
+		})?;
+		match result {
+			Ok(reply) => Ok(reply),
+			Err(FormatError::UnsupportedLanguage) => Ok(Value::Null),
+			Err(error) => Err(request_failed(error)),
+		}
+	}
+
+	/// Opens the file at `path` with `content`, unless it's already open with this content. The
+	/// least recently requested file is closed when more than `capacity` files are open
+	fn update(&mut self, path: &Path, content: String) {
+		let is_unchanged = self
+			.workspace
+			.content(path)
+			.map_or(false, |cached| cached == content);
+		if !is_unchanged {
+			self.workspace.open_file(path, content, 0);
+		}
+
+		self.recent_files.retain(|recent| recent != path);
+		self.recent_files.push_back(path.to_path_buf());
+		while self.recent_files.len() > self.capacity {
+			if let Some(least_recent) = self.recent_files.pop_front() {
+				let _ = self.workspace.close_file(&least_recent);
+			}
+		}
+	}
+
+	/// Forgets the file at `path`
+	fn close(&mut self, path: &Path) {
+		self.recent_files.retain(|recent| recent != path);
+		let _ = self.workspace.close_file(path);
+	}
+
+	/// Calls `f` with the workspace, and turns its panics into errors. The file at `path` is
+	/// closed after a panic, so that the next request parses it again
+	fn catch_panic<T>(
+		&mut self,
+		path: &Path,
+		f: impl FnOnce(&Workspace<WorkspaceServices>) -> T,
+	) -> Result<T, ResponseError> {
+		match catch_unwind(AssertUnwindSafe(|| f(&self.workspace))) {
+			Ok(result) => Ok(result),
+			Err(_) => {
+				self.close(path);
+				Err(ResponseError::new(
+					ResponseError::REQUEST_FAILED,
+					"the formatter panicked",
+				))
+			}
+		}
+	}
+}
+
+fn request_failed(error: FormatError) -> ResponseError {
+	ResponseError::new(ResponseError::REQUEST_FAILED, error.to_string())
+}
+
+/// The answer of the daemon to a request to format a file
+#[derive(Debug, PartialEq, Eq)]
+pub enum FormatReply {
+	Formatted(String),
+	/// Rome doesn't format this kind of file, or its formatting is disabled
+	Skipped,
+	/// The file couldn't be formatted, with the reason
+	Failed(String),
+}
+
+/// The answer of the daemon to a request to check a file
+#[derive(Debug, PartialEq, Eq)]
+pub enum CheckReply {
+	Checked {
+		syntax_errors: bool,
+		/// The formatted code, [None] when the file has syntax errors or its formatting is
+		/// disabled
+		formatted: Option<String>,
+	},
+	/// Rome can't parse this kind of file
+	Skipped,
+	/// The file couldn't be checked, with the reason
+	Failed(String),
+}
+
+/// A connection to a running daemon
+pub struct Client {
+	reader: BufReader<UnixStream>,
+	next_id: u64,
+}
+
+impl Client {
+	/// Connects to the daemon listening on `socket`
+	pub fn connect(socket: &Path) -> io::Result<Self> {
+		let stream = UnixStream::connect(socket)?;
+		Ok(Self {
+			reader: BufReader::new(stream),
+			next_id: 0,
+		})
+	}
+
+	/// Formats `content`, the content of the file at `path`, with `configuration`
+	pub fn format(
+		&mut self,
+		path: &Path,
+		content: &str,
+		configuration: &FormatConfiguration,
+	) -> io::Result<FormatReply> {
+		let params = json!({ "path": path, "content": content, "configuration": configuration });
+		let response = self.request("rome/format", params)?;
+
+		Ok(match (response.error, response.result) {
+			(Some(error), _) => FormatReply::Failed(error.message),
+			(None, Value::String(code)) => FormatReply::Formatted(code),
+			(None, _) => FormatReply::Skipped,
+		})
+	}
+
+	/// Checks `content`, the content of the file at `path`, and formats it with `configuration`
+	pub fn check(
+		&mut self,
+		path: &Path,
+		content: &str,
+		configuration: &FormatConfiguration,
+	) -> io::Result<CheckReply> {
+		let params = json!({ "path": path, "content": content, "configuration": configuration });
+		let response = self.request("rome/check", params)?;
+
+		Ok(match (response.error, response.result) {
+			(Some(error), _) => CheckReply::Failed(error.message),
+			(None, Value::Null) => CheckReply::Skipped,
+			(None, result) => CheckReply::Checked {
+				syntax_errors: result["syntaxErrors"].as_bool().unwrap_or_default(),
+				formatted: result["formatted"].as_str().map(String::from),
+			},
+		})
+	}
+
+	/// Asks the daemon to stop, and waits until it has answered
+	pub fn shutdown(mut self) -> io::Result<()> {
+		self.request("shutdown", Value::Null).map(|_| ())
+	}
+
+	/// Sends a request and waits for its response
+	fn request(&mut self, method: &str, params: Value) -> io::Result<Message> {
+		self.next_id += 1;
+		let request =
+			json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params });
+		write_message(&mut self.reader.get_ref(), &request)?;
+
+		read_message(&mut self.reader)?.ok_or_else(|| {
+			io::Error::new(
+				io::ErrorKind::UnexpectedEof,
+				"the daemon closed the connection",
+			)
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Daemon;
+	use std::path::Path;
+
+	#[test]
+	fn closes_the_least_recently_requested_files() {
+		let mut daemon = Daemon::new(2);
+		daemon.update(Path::new("a.js"), String::from("a;"));
+		daemon.update(Path::new("b.js"), String::from("b;"));
+		daemon.update(Path::new("a.js"), String::from("a;"));
+		daemon.update(Path::new("c.js"), String::from("c;"));
+
+		assert_eq!(daemon.workspace.content(Path::new("a.js")).ok(), Some("a;"));
+		assert!(daemon.workspace.content(Path::new("b.js")).is_err());
+		assert_eq!(daemon.workspace.content(Path::new("c.js")).ok(), Some("c;"));
+		assert_eq!(daemon.recent_files.len(), 2);
+
+		daemon.close(Path::new("a.js"));
+		assert!(daemon.workspace.content(Path::new("a.js")).is_err());
+		assert_eq!(daemon.recent_files, vec![Path::new("c.js")]);
+	}
+}
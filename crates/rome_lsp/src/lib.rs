@@ -0,0 +1,38 @@
+//! Rome's language server.
+//!
+//! The server speaks the [Language Server Protocol] over a pair of streams, usually the standard
+//! input and output of the process. It keeps the documents opened in the editor in memory, so
+//! that the unsaved changes are taken into account, and it provides:
+//! - the syntax errors of the documents, published each time they change;
+//! - the formatting of whole documents and of ranges of lines;
+//! - the quick fixes suggested by the diagnostics, as code actions.
+//!
+//! The settings of the project are read from the `rome.json` file of the workspace root.
+//!
+//! On Unix, the crate also provides the [daemon] that the CLI forwards its commands to.
+//!
+//! [Language Server Protocol]: https://microsoft.github.io/language-server-protocol/
+use server::Server;
+use std::io::{self, BufRead, Write};
+use transport::read_message;
+
+#[cfg(unix)]
+pub mod daemon;
+mod server;
+mod transport;
+
+/// Runs the server until the client asks it to exit or closes `reader`.
+///
+/// Returns the exit code of the process: `0` if the client asked the server to shut down
+/// before exiting, `1` otherwise.
+pub fn run(mut reader: impl BufRead, writer: impl Write) -> io::Result<i32> {
+	let mut server = Server::new(writer);
+
+	while let Some(message) = read_message(&mut reader)? {
+		if !server.handle(message)? {
+			break;
+		}
+	}
+
+	Ok(if server.is_shut_down() { 0 } else { 1 })
+}
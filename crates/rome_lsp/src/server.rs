@@ -0,0 +1,492 @@
+use crate::transport::{write_message, Message, ResponseError};
+use lsp_types::{
+	CodeActionKind, CodeActionOptions, CodeActionParams, CodeActionProviderCapability,
+	DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+	DocumentFormattingParams, DocumentRangeFormattingParams, InitializeParams, InitializeResult,
+	MessageType, OneOf, OptionalVersionedTextDocumentIdentifier, PublishDiagnosticsParams, Range,
+	ServerCapabilities, ServerInfo, ShowMessageParams, TextDocumentSyncCapability,
+	TextDocumentSyncKind, TextEdit, Url, WorkDoneProgressOptions,
+};
+use rome_core::{
+	configuration::{load_configuration, ConfigurationError, ProjectSettings},
+	fs::OsFileSystem,
+	workspace::{DocumentChange, Workspace, WorkspaceError},
+};
+use rome_formatter::{create_app, Formatted, WorkspaceServices};
+use rslint_errors::{
+	file::{Files, SimpleFile},
+	lsp::{
+		byte_span_to_range, convert_to_lsp_code_actions, convert_to_lsp_diagnostic,
+		range_to_byte_span, SnippetCodeAction,
+	},
+	Diagnostic,
+};
+use rslint_parser::{TextRange, TextSize};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::{
+	io::{self, Write},
+	panic::{catch_unwind, AssertUnwindSafe},
+	path::PathBuf,
+};
+
+/// The name of the server in the diagnostics and the `initialize` response
+const SERVER_NAME: &str = "rome";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+	/// Waiting for the `initialize` request
+	Uninitialized,
+	Running,
+	/// The `shutdown` request has been received, only `exit` is expected
+	ShutDown,
+}
+
+pub(crate) struct Server<W> {
+	writer: W,
+	state: State,
+	/// The documents opened in the editor. Their content is the one of the editor, which may
+	/// not be saved
+	workspace: Workspace<WorkspaceServices>,
+	/// The client supports the code actions whose edits are snippets
+	snippets: bool,
+}
+
+impl<W: Write> Server<W> {
+	pub fn new(writer: W) -> Self {
+		Self {
+			writer,
+			state: State::Uninitialized,
+			workspace: Workspace::new(
+				WorkspaceServices::new(create_app()),
+				ProjectSettings::default(),
+			),
+			snippets: false,
+		}
+	}
+
+	/// Returns `true` once the client asked the server to shut down
+	pub fn is_shut_down(&self) -> bool {
+		self.state == State::ShutDown
+	}
+
+	/// Handles a message of the client. Returns `false` when the server must exit
+	pub fn handle(&mut self, message: Message) -> io::Result<bool> {
+		// The responses of the client, the server doesn't send any request
+		let method = match message.method {
+			Some(method) => method,
+			None => return Ok(true),
+		};
+
+		match message.id {
+			Some(id) => {
+				let response = match self.request(&method, message.params) {
+					Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+					Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+				};
+				write_message(&mut self.writer, &response)?;
+				Ok(true)
+			}
+			None if method == "exit" => Ok(false),
+			None => {
+				self.notification(&method, message.params)?;
+				Ok(true)
+			}
+		}
+	}
+
+	fn request(&mut self, method: &str, params: Value) -> Result<Value, ResponseError> {
+		match (self.state, method) {
+			(State::Uninitialized, "initialize") => {
+				let result = self.initialize(parse_params(params)?);
+				self.state = State::Running;
+				to_value(result)
+			}
+			(State::Uninitialized, _) => Err(ResponseError::new(
+				ResponseError::SERVER_NOT_INITIALIZED,
+				"the server isn't initialized",
+			)),
+			(_, "initialize") => Err(ResponseError::new(
+				ResponseError::INVALID_REQUEST,
+				"the server is already initialized",
+			)),
+			(State::ShutDown, _) => Err(ResponseError::new(
+				ResponseError::INVALID_REQUEST,
+				"the server is shutting down",
+			)),
+			(_, "shutdown") => {
+				self.state = State::ShutDown;
+				Ok(Value::Null)
+			}
+			(_, "textDocument/formatting") => to_value(self.formatting(parse_params(params)?)?),
+			(_, "textDocument/rangeFormatting") => {
+				to_value(self.range_formatting(parse_params(params)?)?)
+			}
+			(_, "textDocument/codeAction") => to_value(self.code_action(parse_params(params)?)?),
+			_ => Err(ResponseError::new(
+				ResponseError::METHOD_NOT_FOUND,
+				format!("unknown method {}", method),
+			)),
+		}
+	}
+
+	/// Handles a notification. The notifications that can't be handled are dropped, as the client
+	/// doesn't expect any answer
+	fn notification(&mut self, method: &str, params: Value) -> io::Result<()> {
+		if self.state != State::Running {
+			return Ok(());
+		}
+
+		match method {
+			"textDocument/didOpen" => match parse_params(params) {
+				Ok(params) => self.did_open(params),
+				Err(_) => Ok(()),
+			},
+			"textDocument/didChange" => match parse_params(params) {
+				Ok(params) => self.did_change(params),
+				Err(_) => Ok(()),
+			},
+			"textDocument/didClose" => match parse_params(params) {
+				Ok(params) => self.did_close(params),
+				Err(_) => Ok(()),
+			},
+			_ => Ok(()),
+		}
+	}
+
+	fn initialize(&mut self, params: InitializeParams) -> InitializeResult {
+		if let Some(root) = params.root_uri.and_then(|uri| uri.to_file_path().ok()) {
+			let settings = self.load_settings(root);
+			self.workspace.set_settings(settings);
+		}
+
+		self.snippets = params
+			.capabilities
+			.experimental
+			.and_then(|experimental| experimental.get("snippetTextEdit")?.as_bool())
+			.unwrap_or(false);
+
+		InitializeResult {
+			capabilities: ServerCapabilities {
+				text_document_sync: Some(TextDocumentSyncCapability::Kind(
+					TextDocumentSyncKind::Full,
+				)),
+				document_formatting_provider: Some(OneOf::Left(true)),
+				document_range_formatting_provider: Some(OneOf::Left(true)),
+				code_action_provider: Some(CodeActionProviderCapability::Options(
+					CodeActionOptions {
+						code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+						work_done_progress_options: WorkDoneProgressOptions::default(),
+						resolve_provider: None,
+					},
+				)),
+				..ServerCapabilities::default()
+			},
+			server_info: Some(ServerInfo {
+				name: SERVER_NAME.to_string(),
+				version: Some(env!("CARGO_PKG_VERSION").to_string()),
+			}),
+		}
+	}
+
+	/// Loads the settings of the project at `root`. When `rome.json` can't be loaded, the
+	/// default settings are used, along with the `.editorconfig` files, and the user is warned.
+	fn load_settings(&mut self, root: PathBuf) -> ProjectSettings {
+		let message = match load_configuration(&OsFileSystem, &root) {
+			Ok(project) => return ProjectSettings::new(project.as_ref(), &root),
+			Err(ConfigurationError::Io { path, error }) => {
+				format!("cannot read {}: {}", path.display(), error)
+			}
+			Err(ConfigurationError::Invalid {
+				path, diagnostics, ..
+			}) => {
+				let errors: Vec<&str> = diagnostics
+					.iter()
+					.map(|diagnostic| diagnostic.title.as_str())
+					.collect();
+				format!("{} isn't valid: {}", path.display(), errors.join(", "))
+			}
+		};
+
+		let params = ShowMessageParams {
+			typ: MessageType::Warning,
+			message,
+		};
+		// The settings are still usable if the client can't be warned
+		let _ = self.notify("window/showMessage", params);
+		ProjectSettings::new(None, &root)
+	}
+
+	fn did_open(&mut self, params: DidOpenTextDocumentParams) -> io::Result<()> {
+		let document = params.text_document;
+		self.workspace
+			.open_file(to_path(&document.uri), document.text, document.version);
+		self.publish_diagnostics(document.uri)
+	}
+
+	fn did_change(&mut self, params: DidChangeTextDocumentParams) -> io::Result<()> {
+		let uri = params.text_document.uri;
+		let path = to_path(&uri);
+
+		for change in params.content_changes {
+			let content = match self.workspace.content(&path) {
+				Ok(content) => content,
+				Err(_) => return Ok(()),
+			};
+			// The server asks for the full text, but some clients send the edits anyway. Their
+			// ranges point into the text left by the previous edits
+			let range = match change.range {
+				Some(range) => {
+					let file = SimpleFile::new(uri.to_string(), content.to_string());
+					match byte_span(&file, &range) {
+						Ok(span) => Some(text_range(span)),
+						Err(_) => continue,
+					}
+				}
+				None => None,
+			};
+
+			let change = DocumentChange {
+				range,
+				text: change.text,
+			};
+			let _ = self
+				.workspace
+				.change_file(&path, params.text_document.version, vec![change]);
+		}
+
+		self.publish_diagnostics(uri)
+	}
+
+	fn did_close(&mut self, params: DidCloseTextDocumentParams) -> io::Result<()> {
+		let uri = params.text_document.uri;
+		let _ = self.workspace.close_file(&to_path(&uri));
+
+		// The diagnostics of a closed document would stay in the editor otherwise
+		let params = PublishDiagnosticsParams {
+			uri,
+			diagnostics: Vec::new(),
+			version: None,
+		};
+		self.notify("textDocument/publishDiagnostics", params)
+	}
+
+	fn publish_diagnostics(&mut self, uri: Url) -> io::Result<()> {
+		let path = to_path(&uri);
+		let document = self.file(&uri).and_then(|file| {
+			let version = self.workspace.version(&path)?;
+			let diagnostics = self.workspace.pull_diagnostics(&path)?;
+			Ok((file, version, diagnostics))
+		});
+		let (file, version, diagnostics) = match document {
+			Ok(document) => document,
+			Err(_) => return Ok(()),
+		};
+
+		let diagnostics = diagnostics
+			.into_iter()
+			.filter_map(|diagnostic| to_lsp_diagnostic(diagnostic, &file, &uri))
+			.collect();
+
+		let params = PublishDiagnosticsParams {
+			uri,
+			diagnostics,
+			version: Some(version),
+		};
+		self.notify("textDocument/publishDiagnostics", params)
+	}
+
+	/// Formats the whole document. Returns `None` if it can't be formatted, like when it has
+	/// syntax errors
+	fn formatting(
+		&self,
+		params: DocumentFormattingParams,
+	) -> Result<Option<Vec<TextEdit>>, ResponseError> {
+		let uri = params.text_document.uri;
+		let file = self.file(&uri).map_err(invalid_document)?;
+		let formatted = match self.format_document(&uri) {
+			Some(formatted) => formatted,
+			None => return Ok(None),
+		};
+		let path = to_path(&uri);
+		let content = self.workspace.content(&path).map_err(invalid_document)?;
+		if formatted.code() == content {
+			return Ok(Some(Vec::new()));
+		}
+
+		let range = byte_span_to_range(&file, 0, 0..content.len()).map_err(|error| {
+			ResponseError::new(ResponseError::INVALID_PARAMS, error.to_string())
+		})?;
+		Ok(Some(vec![TextEdit::new(range, formatted.code().clone())]))
+	}
+
+	/// Formats the lines of the requested range, see [rome_formatter::Formatted::format_range]
+	fn range_formatting(
+		&self,
+		params: DocumentRangeFormattingParams,
+	) -> Result<Option<Vec<TextEdit>>, ResponseError> {
+		let uri = params.text_document.uri;
+		let file = self.file(&uri).map_err(invalid_document)?;
+		let range = text_range(byte_span(&file, &params.range)?);
+		let formatted = match self.format_document(&uri) {
+			Some(formatted) => formatted,
+			None => return Ok(None),
+		};
+		let path = to_path(&uri);
+		let content = self.workspace.content(&path).map_err(invalid_document)?;
+		let (replaced, code) = match formatted.format_range(content, range) {
+			Some(formatted) => formatted,
+			None => return Ok(Some(Vec::new())),
+		};
+		if content[replaced] == code {
+			return Ok(Some(Vec::new()));
+		}
+
+		let range = byte_span_to_range(&file, 0, replaced.into()).map_err(|error| {
+			ResponseError::new(ResponseError::INVALID_PARAMS, error.to_string())
+		})?;
+		Ok(Some(vec![TextEdit::new(range, code)]))
+	}
+
+	/// The quick fixes of the diagnostics in the requested range
+	fn code_action(
+		&self,
+		params: CodeActionParams,
+	) -> Result<Vec<SnippetCodeAction>, ResponseError> {
+		if let Some(only) = &params.context.only {
+			let quick_fix = CodeActionKind::QUICKFIX;
+			if !only
+				.iter()
+				.any(|kind| quick_fix.as_str().starts_with(kind.as_str()))
+			{
+				return Ok(Vec::new());
+			}
+		}
+
+		let uri = params.text_document.uri;
+		let path = to_path(&uri);
+		let file = self.file(&uri).map_err(invalid_document)?;
+		let span = byte_span(&file, &params.range)?;
+		let text_document = OptionalVersionedTextDocumentIdentifier {
+			uri: uri.clone(),
+			version: self.workspace.version(&path).ok(),
+		};
+		let diagnostics = self
+			.workspace
+			.pull_diagnostics(&path)
+			.map_err(invalid_document)?;
+
+		let mut actions = Vec::new();
+		for diagnostic in diagnostics {
+			let in_range = diagnostic.primary.as_ref().map_or(false, |primary| {
+				primary.span.range.start <= span.end && span.start <= primary.span.range.end
+			});
+			if !in_range || diagnostic.suggestions.is_empty() {
+				continue;
+			}
+
+			let lsp_diagnostic = to_lsp_diagnostic(diagnostic.clone(), &file, &uri);
+			actions.extend(convert_to_lsp_code_actions(
+				&diagnostic,
+				&file,
+				0,
+				text_document.clone(),
+				lsp_diagnostic.as_ref(),
+				self.snippets,
+			));
+		}
+
+		Ok(actions)
+	}
+
+	/// The content of the open document at `uri`, as a file of the diagnostics
+	fn file(&self, uri: &Url) -> Result<SimpleFile, WorkspaceError> {
+		let content = self.workspace.content(&to_path(uri))?;
+		Ok(SimpleFile::new(uri.to_string(), content.to_string()))
+	}
+
+	/// Whether the settings of the project format the document at `uri`. The options of the
+	/// editor are ignored in favour of `rome.json`, so that the editor and the CLI agree
+	fn is_formatted(&self, uri: &Url) -> bool {
+		self.workspace.settings().files().is_included(&to_path(uri))
+	}
+
+	/// Formats the document at `uri`. Returns `None` if the settings of the project don't format
+	/// it, if it has syntax errors, or if the formatter fails or panics, so that a bug of the
+	/// formatter can't take the server down
+	fn format_document(&self, uri: &Url) -> Option<Formatted> {
+		let path = to_path(uri);
+		if !self.is_formatted(uri) || !self.workspace.pull_diagnostics(&path).ok()?.is_empty() {
+			return None;
+		}
+
+		catch_unwind(AssertUnwindSafe(|| self.workspace.format_file(&path)))
+			.ok()?
+			.ok()
+	}
+
+	fn notify(&mut self, method: &str, params: impl Serialize) -> io::Result<()> {
+		let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+		write_message(&mut self.writer, &message)
+	}
+}
+
+fn to_lsp_diagnostic(
+	diagnostic: Diagnostic,
+	file: &SimpleFile,
+	uri: &Url,
+) -> Option<lsp_types::Diagnostic> {
+	convert_to_lsp_diagnostic(
+		diagnostic,
+		file,
+		0,
+		uri.clone(),
+		Some(SERVER_NAME.to_string()),
+	)
+}
+
+/// The path of the document at `uri`, which may not be a file, like an unsaved document
+fn to_path(uri: &Url) -> PathBuf {
+	uri.to_file_path()
+		.unwrap_or_else(|_| PathBuf::from(uri.path()))
+}
+
+fn invalid_document(error: WorkspaceError) -> ResponseError {
+	ResponseError::new(ResponseError::INVALID_PARAMS, error.to_string())
+}
+
+fn text_range(span: std::ops::Range<usize>) -> TextRange {
+	TextRange::new(
+		TextSize::from(span.start as u32),
+		TextSize::from(span.end as u32),
+	)
+}
+
+/// Converts an LSP range to a range of bytes of `file`
+fn byte_span(file: &SimpleFile, range: &Range) -> Result<std::ops::Range<usize>, ResponseError> {
+	let invalid_range = || {
+		ResponseError::new(
+			ResponseError::INVALID_PARAMS,
+			format!("invalid range {:?}", range),
+		)
+	};
+
+	// The conversion panics when the lines don't exist
+	for position in [range.start, range.end] {
+		if file.line_range(0, position.line as usize).is_none() {
+			return Err(invalid_range());
+		}
+	}
+
+	range_to_byte_span(file, 0, range).map_err(|_| invalid_range())
+}
+
+fn parse_params<P: DeserializeOwned>(params: Value) -> Result<P, ResponseError> {
+	serde_json::from_value(params)
+		.map_err(|error| ResponseError::new(ResponseError::INVALID_PARAMS, error.to_string()))
+}
+
+fn to_value(result: impl Serialize) -> Result<Value, ResponseError> {
+	serde_json::to_value(result)
+		.map_err(|error| ResponseError::new(ResponseError::INVALID_PARAMS, error.to_string()))
+}
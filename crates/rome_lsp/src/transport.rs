@@ -0,0 +1,124 @@
+//! The base protocol of LSP: JSON-RPC messages preceded by a `Content-Length` header.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+/// A message received from the other end. Requests have an `id` and expect a response,
+/// notifications don't. Responses have an `id` but no `method`, and a `result` or an `error`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Message {
+	#[serde(default)]
+	pub id: Option<Value>,
+	#[serde(default)]
+	pub method: Option<String>,
+	#[serde(default)]
+	pub params: Value,
+	#[serde(default)]
+	pub result: Value,
+	#[serde(default)]
+	pub error: Option<ResponseError>,
+}
+
+/// The error of a request that failed
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ResponseError {
+	pub code: i64,
+	pub message: String,
+}
+
+impl ResponseError {
+	pub const INVALID_PARAMS: i64 = -32602;
+	pub const METHOD_NOT_FOUND: i64 = -32601;
+	pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+	pub const INVALID_REQUEST: i64 = -32600;
+	pub const REQUEST_FAILED: i64 = -32803;
+
+	pub fn new(code: i64, message: impl Into<String>) -> Self {
+		Self {
+			code,
+			message: message.into(),
+		}
+	}
+}
+
+/// Reads the next message of `reader`, or returns `None` when the client closed the stream
+pub(crate) fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Message>> {
+	let mut content_length = None;
+	let mut line = String::new();
+	loop {
+		line.clear();
+		if reader.read_line(&mut line)? == 0 {
+			return Ok(None);
+		}
+
+		let header = line.trim_end();
+		if header.is_empty() {
+			break;
+		}
+
+		if let Some((name, value)) = header.split_once(':') {
+			if name.eq_ignore_ascii_case("Content-Length") {
+				let length = value.trim().parse::<usize>().map_err(|_| {
+					invalid_data(format!("invalid Content-Length header: {}", header))
+				})?;
+				content_length = Some(length);
+			}
+		}
+	}
+
+	let content_length = content_length
+		.ok_or_else(|| invalid_data("the message doesn't have a Content-Length header".into()))?;
+	let mut content = vec![0; content_length];
+	reader.read_exact(&mut content)?;
+
+	serde_json::from_slice(&content)
+		.map(Some)
+		.map_err(|error| invalid_data(format!("invalid message: {}", error)))
+}
+
+/// Writes `message` to `writer`, preceded by its header
+pub(crate) fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+	let content = serde_json::to_string(message)?;
+	write!(
+		writer,
+		"Content-Length: {}\r\n\r\n{}",
+		content.len(),
+		content
+	)?;
+	writer.flush()
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{read_message, write_message};
+	use serde_json::json;
+	use std::io::Cursor;
+
+	#[test]
+	fn reads_the_messages_it_writes() {
+		let mut buffer = Vec::new();
+		write_message(&mut buffer, &json!({ "id": 1, "method": "shutdown" })).unwrap();
+		write_message(&mut buffer, &json!({ "method": "exit" })).unwrap();
+
+		let mut reader = Cursor::new(buffer);
+		let shutdown = read_message(&mut reader).unwrap().unwrap();
+		assert_eq!(shutdown.id, Some(json!(1)));
+		assert_eq!(shutdown.method.as_deref(), Some("shutdown"));
+
+		let exit = read_message(&mut reader).unwrap().unwrap();
+		assert_eq!(exit.id, None);
+		assert_eq!(exit.method.as_deref(), Some("exit"));
+
+		assert!(read_message(&mut reader).unwrap().is_none());
+	}
+
+	#[test]
+	fn rejects_the_messages_without_length() {
+		let mut reader = Cursor::new(b"Content-Type: application/json\r\n\r\n{}".to_vec());
+		assert!(read_message(&mut reader).is_err());
+	}
+}
@@ -0,0 +1,340 @@
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+/// Runs the server with the messages of `script`, and returns its exit code and the messages
+/// it sent back
+fn run_script(script: &[Value]) -> (i32, Vec<Value>) {
+	let mut input = Vec::new();
+	for message in script {
+		let content = message.to_string();
+		input.extend(format!("Content-Length: {}\r\n\r\n{}", content.len(), content).bytes());
+	}
+
+	let mut output = Vec::new();
+	let exit_code = rome_lsp::run(Cursor::new(input), &mut output).unwrap();
+
+	let mut messages = Vec::new();
+	let mut rest = String::from_utf8(output).unwrap();
+	while !rest.is_empty() {
+		let (header, content) = rest.split_once("\r\n\r\n").unwrap();
+		let length: usize = header
+			.strip_prefix("Content-Length: ")
+			.unwrap()
+			.parse()
+			.unwrap();
+		messages.push(serde_json::from_str(&content[..length]).unwrap());
+		rest = content[length..].to_string();
+	}
+
+	(exit_code, messages)
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+	json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+	json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+	json!({
+		"start": { "line": start.0, "character": start.1 },
+		"end": { "line": end.0, "character": end.1 },
+	})
+}
+
+const URI: &str = "file:///project/index.js";
+
+#[test]
+fn publishes_diagnostics_and_formats_documents() {
+	let document = json!({ "uri": URI });
+	let (exit_code, messages) = run_script(&[
+		request(1, "initialize", json!({ "capabilities": {} })),
+		notification("initialized", json!({})),
+		notification(
+			"textDocument/didOpen",
+			json!({
+				"textDocument": { "uri": URI, "languageId": "javascript", "version": 1, "text": "let a = ;" }
+			}),
+		),
+		notification(
+			"textDocument/didChange",
+			json!({
+				"textDocument": { "uri": URI, "version": 2 },
+				"contentChanges": [{ "text": "let   a=1\nlet b  =2\n" }]
+			}),
+		),
+		request(
+			2,
+			"textDocument/formatting",
+			json!({
+				"textDocument": document,
+				"options": { "tabSize": 2, "insertSpaces": true }
+			}),
+		),
+		request(
+			3,
+			"textDocument/rangeFormatting",
+			json!({
+				"textDocument": document,
+				"range": range((1, 0), (1, 3)),
+				"options": { "tabSize": 2, "insertSpaces": true }
+			}),
+		),
+		request(
+			5,
+			"textDocument/codeAction",
+			json!({
+				"textDocument": document,
+				"range": range((0, 0), (2, 0)),
+				"context": { "diagnostics": [] }
+			}),
+		),
+		notification("textDocument/didClose", json!({ "textDocument": document })),
+		request(4, "shutdown", Value::Null),
+		notification("exit", Value::Null),
+	]);
+
+	assert_eq!(exit_code, 0);
+	assert_eq!(messages.len(), 8, "{:#?}", messages);
+
+	let initialize = &messages[0];
+	assert_eq!(initialize["id"], 1);
+	let capabilities = &initialize["result"]["capabilities"];
+	assert_eq!(capabilities["textDocumentSync"], 1);
+	assert_eq!(capabilities["documentFormattingProvider"], true);
+	assert_eq!(capabilities["documentRangeFormattingProvider"], true);
+	assert_eq!(
+		capabilities["codeActionProvider"]["codeActionKinds"],
+		json!(["quickfix"])
+	);
+
+	let opened = &messages[1];
+	assert_eq!(opened["method"], "textDocument/publishDiagnostics");
+	assert_eq!(opened["params"]["version"], 1);
+	let diagnostics = opened["params"]["diagnostics"].as_array().unwrap();
+	assert_eq!(diagnostics.len(), 1);
+	assert_eq!(diagnostics[0]["source"], "rome");
+
+	let changed = &messages[2];
+	assert_eq!(changed["params"]["version"], 2);
+	assert_eq!(changed["params"]["diagnostics"], json!([]));
+
+	assert_eq!(
+		messages[3],
+		json!({
+			"jsonrpc": "2.0",
+			"id": 2,
+			"result": [{ "range": range((0, 0), (2, 0)), "newText": "let a = 1;\nlet b = 2;\n" }]
+		})
+	);
+
+	assert_eq!(
+		messages[4],
+		json!({
+			"jsonrpc": "2.0",
+			"id": 3,
+			"result": [{ "range": range((1, 0), (1, 9)), "newText": "let b = 2;" }]
+		})
+	);
+
+	// The syntax errors don't suggest any fix
+	assert_eq!(messages[5]["id"], 5);
+	assert_eq!(messages[5]["result"], json!([]));
+
+	let closed = &messages[6];
+	assert_eq!(closed["method"], "textDocument/publishDiagnostics");
+	assert_eq!(closed["params"]["diagnostics"], json!([]));
+
+	assert_eq!(messages[7]["id"], 4);
+	assert_eq!(messages[7]["result"], Value::Null);
+}
+
+#[test]
+fn does_not_format_the_documents_it_cannot_format() {
+	let document = json!({ "uri": URI });
+	let formatting = |id| {
+		request(
+			id,
+			"textDocument/formatting",
+			json!({
+				"textDocument": document,
+				"options": { "tabSize": 2, "insertSpaces": true }
+			}),
+		)
+	};
+	let (exit_code, messages) = run_script(&[
+		request(1, "initialize", json!({ "capabilities": {} })),
+		notification("initialized", json!({})),
+		notification(
+			"textDocument/didOpen",
+			json!({
+				"textDocument": { "uri": URI, "languageId": "javascript", "version": 1, "text": "let a = ;" }
+			}),
+		),
+		formatting(2),
+		// The formatter doesn't support `this` yet and panics
+		notification(
+			"textDocument/didChange",
+			json!({
+				"textDocument": { "uri": URI, "version": 2 },
+				"contentChanges": [{ "text": "this;\n" }]
+			}),
+		),
+		formatting(3),
+		request(4, "shutdown", Value::Null),
+		notification("exit", Value::Null),
+	]);
+
+	assert_eq!(exit_code, 0);
+	assert_eq!(messages.len(), 6, "{:#?}", messages);
+
+	assert_eq!(messages[2]["id"], 2);
+	assert_eq!(messages[2]["result"], Value::Null);
+
+	assert_eq!(messages[4]["id"], 3);
+	assert_eq!(messages[4]["result"], Value::Null);
+
+	assert_eq!(messages[5]["id"], 4);
+}
+
+#[test]
+fn rejects_requests_before_initialize() {
+	let (exit_code, messages) = run_script(&[
+		request(
+			1,
+			"textDocument/formatting",
+			json!({ "textDocument": { "uri": URI }, "options": { "tabSize": 2, "insertSpaces": true } }),
+		),
+		notification("exit", Value::Null),
+	]);
+
+	assert_eq!(exit_code, 1, "the server exits without a shutdown request");
+	assert_eq!(messages.len(), 1);
+	assert_eq!(messages[0]["error"]["code"], -32002);
+}
+
+#[test]
+fn formats_with_the_settings_of_the_workspace() {
+	let root = std::env::temp_dir().join(format!("rome_lsp_settings_{}", std::process::id()));
+	std::fs::create_dir_all(&root).unwrap();
+	std::fs::write(
+		root.join("rome.json"),
+		r#"{ "format": { "indentStyle": "space", "indentSize": 4 } }"#,
+	)
+	.unwrap();
+
+	let root_uri = format!("file://{}", root.display());
+	let uri = format!("{}/index.js", root_uri);
+	let (_, messages) = run_script(&[
+		request(
+			1,
+			"initialize",
+			json!({ "rootUri": root_uri, "capabilities": {} }),
+		),
+		notification(
+			"textDocument/didOpen",
+			json!({
+				"textDocument": { "uri": uri, "languageId": "javascript", "version": 1, "text": "if(a){b}" }
+			}),
+		),
+		request(
+			2,
+			"textDocument/formatting",
+			json!({
+				"textDocument": { "uri": uri },
+				"options": { "tabSize": 8, "insertSpaces": false }
+			}),
+		),
+	]);
+
+	std::fs::remove_dir_all(&root).unwrap();
+
+	let edits = messages.last().unwrap()["result"].as_array().unwrap();
+	assert_eq!(edits.len(), 1);
+	assert_eq!(edits[0]["newText"], "if (a) {\n    b;\n}\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn the_daemon_formats_the_files_of_its_clients() {
+	use rome_core::configuration::FormatConfiguration;
+	use rome_lsp::daemon::{serve, CheckReply, Client, FormatReply};
+	use std::{os::unix::net::UnixListener, path::Path};
+
+	let root = std::env::temp_dir().join(format!("rome_lsp_daemon_{}", std::process::id()));
+	std::fs::create_dir_all(&root).unwrap();
+	let socket = root.join("daemon.sock");
+	let listener = UnixListener::bind(&socket).unwrap();
+	let daemon = std::thread::spawn(move || serve(listener));
+
+	let configuration = FormatConfiguration::default();
+	let mut client = Client::connect(&socket).unwrap();
+	let path = Path::new("index.js");
+	assert_eq!(
+		client.format(path, "let   a=1", &configuration).unwrap(),
+		FormatReply::Formatted("let a = 1;\n".to_string())
+	);
+	assert_eq!(
+		client
+			.format(Path::new("notes.txt"), "some notes", &configuration)
+			.unwrap(),
+		FormatReply::Skipped
+	);
+	assert!(matches!(
+		client
+			.format(
+				Path::new("broken.json"),
+				"{ /* comment */ }",
+				&configuration
+			)
+			.unwrap(),
+		FormatReply::Failed(_)
+	));
+	drop(client);
+
+	// The next clients are served by the same daemon, with their own configuration
+	let mut client = Client::connect(&socket).unwrap();
+	let disabled = FormatConfiguration {
+		enabled: false,
+		..FormatConfiguration::default()
+	};
+	assert_eq!(
+		client.format(path, "let   a=1", &disabled).unwrap(),
+		FormatReply::Skipped
+	);
+
+	assert_eq!(
+		client.check(path, "let   a=1", &configuration).unwrap(),
+		CheckReply::Checked {
+			syntax_errors: false,
+			formatted: Some("let a = 1;\n".to_string())
+		}
+	);
+	assert_eq!(
+		client.check(path, "let a = ;", &configuration).unwrap(),
+		CheckReply::Checked {
+			syntax_errors: true,
+			formatted: None
+		},
+		"the files with syntax errors aren't formatted"
+	);
+	assert_eq!(
+		client.check(path, "let   a=1", &disabled).unwrap(),
+		CheckReply::Checked {
+			syntax_errors: false,
+			formatted: None
+		}
+	);
+	assert_eq!(
+		client
+			.check(Path::new("notes.txt"), "some notes", &configuration)
+			.unwrap(),
+		CheckReply::Skipped
+	);
+	client.shutdown().unwrap();
+
+	daemon.join().unwrap().unwrap();
+	std::fs::remove_dir_all(&root).unwrap();
+}
@@ -2,13 +2,22 @@
 //! It is a small wrapper around [path::PathBuf] but it is also able to
 //! give additional information around the the file that holds:
 //! - the [FileHandlers] for the specific file
-//! - shortcuts to open/write to the file
-use rome_core::{file_handlers::ExtensionHandler, App};
-use std::{fs::File, io::Write, ops::Deref, path::PathBuf};
+//! - the [Syntax] of the file, when it depends on its package
+//! - shortcuts to read/write the file through a [FileSystem]
+use rome_core::{
+	configuration::{FormatConfiguration, ProjectSettings},
+	file_handlers::{Capabilities, ExtensionHandler},
+	fs::{FileSystem, FileSystemError},
+	App,
+};
+use rslint_parser::Syntax;
+use std::{ops::Deref, path::PathBuf, sync::Arc};
 
 pub struct RomePath<'handler> {
 	file: PathBuf,
-	handler: Option<&'handler dyn ExtensionHandler>,
+	handler: Option<&'handler Arc<dyn ExtensionHandler>>,
+	/// The syntax resolved from the package of the file, see [RomePath::resolve_syntax]
+	syntax: Option<Syntax>,
 }
 
 impl<'handler> Deref for RomePath<'handler> {
@@ -24,93 +33,262 @@ impl<'handler> RomePath<'handler> {
 		Self {
 			file: PathBuf::from(path_to_file),
 			handler: None,
+			syntax: None,
 		}
 	}
 
-	/// Deduce the file handler based on the extension of the file.
+	/// Deduce the file handler based on the name of the file or its extension, see
+	/// [App::get_handler_for_path].
 	///
-	/// Any error will default to the base file handler for now.
+	/// The files with an unknown extension get the base file handler, the files without
+	/// extension don't get any.
 	///
 	///
 	/// ```rust
 	/// use rome_path::RomePath;
 	/// use rome_core::{
-	///   create_app,
-	///   file_handlers::{javascript::JsFileHandler, ExtensionHandler},
+	///   file_handlers::{ExtensionHandler, Language, Mime},
+	///   App,
 	/// };
 	///
-	/// let app = create_app();
-	/// let file = RomePath::new("file.js").deduce_handler(&app);
-	/// let handler = file.get_handler();
-	/// let expected = JsFileHandler {};
-	/// assert_eq!(
-	///   handler.unwrap().capabilities().format,
-	///   expected.capabilities().format
-	/// );
-	/// assert_eq!(
-	///  handler.unwrap().capabilities().lint,
-	///  expected.capabilities().lint
-	/// )
+	/// struct QueryFileHandler {}
+	///
+	/// impl ExtensionHandler for QueryFileHandler {
+	///   fn language(&self) -> Language {
+	///     Language::Other("query")
+	///   }
+	///
+	///   fn mime(&self) -> Mime {
+	///     Mime::Text
+	///   }
+	/// }
+	///
+	/// let mut app = App::new();
+	/// app.register_extension("query", Box::new(QueryFileHandler {}));
+	///
+	/// let file = RomePath::new("users.query").deduce_handler(&app);
+	/// let handler = file.get_handler().unwrap();
+	/// assert!(matches!(handler.language(), Language::Other("query")));
 	/// ```
 	pub fn deduce_handler(mut self, app: &'handler App) -> Self {
-		if self.extension().is_none() {
-			return self;
-		}
-		let extension = self.extension().unwrap().to_str().unwrap();
+		self.handler = app.get_handler_for_path(&self.file);
+		self
+	}
 
-		if let Some(handler) = app.get_handler(extension) {
-			self.handler = Some(handler);
-		} else {
-			// we know that unknown is hardcoded
-			self.handler = Some(app.get_handler("unknown").unwrap());
+	/// Deduce the file handler of a script without extension from its `content`, like its
+	/// shebang. The handler deduced from the path, if any, takes precedence.
+	pub fn sniff_handler(mut self, app: &'handler App, content: &str) -> Self {
+		if self.handler.is_none() {
+			self.handler = app.get_handler_for_content(content);
 		}
+		self
+	}
 
+	/// Resolve the syntax of the file when it depends on its package, like a `.js` file that is an
+	/// ES module in a package of type `module`. The `package.json` files are read from `fs`, see
+	/// [App::get_syntax].
+	///
+	/// You need to call [deduce_handler] first, the files without handler don't have a syntax.
+	pub fn resolve_syntax(mut self, app: &App, fs: &dyn FileSystem) -> Self {
+		self.syntax = self
+			.handler
+			.map(|handler| app.get_syntax(handler.as_ref(), fs, &self.file));
 		self
 	}
 
-	// TODO: handle error with diagnostic?
-	/// Opens a file and returns a [File] in write mode
-	pub fn open(&self) -> File {
-		File::open(&self.file).expect("cannot open the file to format")
+	/// Returns the syntax the file is parsed with: the one resolved by [RomePath::resolve_syntax],
+	/// or the one of its handler
+	pub fn syntax(&self) -> Option<Syntax> {
+		self.syntax
+			.or_else(|| self.handler.map(|handler| handler.syntax()))
+	}
+
+	/// Reads the content of the file from `fs`
+	pub fn read(&self, fs: &dyn FileSystem) -> Result<String, FileSystemError> {
+		fs.read(&self.file)
 	}
 
-	/// Accepts a file opened in read mode and saves into it
-	pub fn save(&mut self, content: &str) -> Result<(), std::io::Error> {
-		let mut file_to_write = File::create(&self.file).unwrap();
-		// TODO: handle error with diagnostic
-		file_to_write.write_all(content.as_bytes())
+	/// Replaces the content of the file in `fs` with `content`
+	pub fn save(&self, fs: &dyn FileSystem, content: &str) -> Result<(), FileSystemError> {
+		fs.write(&self.file, content)
 	}
 
 	/// Returns the current handler associated to the file.
 	///
 	/// You need to call [deduce_handler] first in order to receive one. If not, [None] is always returned.
 	// TODO: move handler deduction inside the path crate
-	pub fn get_handler(&self) -> Option<&dyn ExtensionHandler> {
+	pub fn get_handler(&self) -> Option<&Arc<dyn ExtensionHandler>> {
 		self.handler
 	}
+
+	/// Returns the configuration of the formatter for this file, once the overrides of the
+	/// project that match its path are applied
+	pub fn format_configuration(&self, settings: &ProjectSettings) -> FormatConfiguration {
+		settings.format_configuration(&self.file)
+	}
+
+	/// Returns the capabilities of the handler of this file, minus the ones that the settings of
+	/// the project disable for its path.
+	///
+	/// You need to call [deduce_handler] first in order to receive them.
+	pub fn capabilities(&self, settings: &ProjectSettings) -> Option<Capabilities> {
+		let capabilities = self.handler?.capabilities();
+		Some(Capabilities {
+			format: capabilities.format && settings.format_configuration(&self.file).enabled,
+			lint: capabilities.lint && settings.lint_configuration(&self.file).enabled,
+		})
+	}
 }
 
 #[cfg(test)]
 mod test {
 	use crate::RomePath;
 	use rome_core::{
-		create_app,
-		file_handlers::{javascript::JsFileHandler, ExtensionHandler},
+		configuration::{parse_configuration, ProjectConfiguration, ProjectSettings},
+		file_handlers::{Capabilities, ExtensionHandler, Language, Mime},
+		fs::MemoryFileSystem,
+		package::PackageType,
+		App,
 	};
+	use rslint_parser::{FileKind, Syntax};
+	use std::path::{Path, PathBuf};
+
+	/// A handler of scripts, which are modules in the packages of type `module` when it follows
+	/// the type of the packages
+	struct ScriptFileHandler {
+		follows_package_type: bool,
+	}
+
+	impl ExtensionHandler for ScriptFileHandler {
+		fn language(&self) -> Language {
+			Language::Js
+		}
+
+		fn mime(&self) -> Mime {
+			Mime::Javascript
+		}
+
+		fn capabilities(&self) -> Capabilities {
+			Capabilities {
+				format: true,
+				lint: true,
+			}
+		}
+
+		fn syntax_in_package(&self, package_type: PackageType) -> Syntax {
+			match package_type {
+				PackageType::Module if self.follows_package_type => Syntax::default().module(),
+				_ => Syntax::default(),
+			}
+		}
+
+		fn depends_on_package_type(&self) -> bool {
+			self.follows_package_type
+		}
+	}
+
+	fn create_app() -> App {
+		let mut app = App::new();
+		let handler = |follows_package_type| {
+			Box::new(ScriptFileHandler {
+				follows_package_type,
+			})
+		};
+		app.register_extension("js", handler(true));
+		app.register_extension("cjs", handler(false));
+		app.register_interpreter("node", handler(false));
+		app
+	}
+
+	#[test]
+	fn reads_and_saves_through_the_file_system() {
+		let fs = MemoryFileSystem::default();
+		let file = RomePath::new("src/file.js");
+		assert!(file.read(&fs).is_err());
+
+		file.save(&fs, "let a;").unwrap();
+		assert_eq!(file.read(&fs).unwrap(), "let a;");
+	}
 
 	#[test]
 	fn deduce_handler() {
 		let app = create_app();
 		let file = RomePath::new("file.js").deduce_handler(&app);
 		let handler = file.get_handler();
-		let expected = JsFileHandler {};
-		assert_eq!(
-			handler.unwrap().capabilities().format,
-			expected.capabilities().format
+		assert!(matches!(handler.unwrap().language(), Language::Js));
+		assert!(handler.unwrap().capabilities().format);
+		assert!(handler.unwrap().capabilities().lint);
+	}
+
+	#[test]
+	fn capabilities_follow_the_settings() {
+		let configuration = parse_configuration(
+			r#"{ "overrides": [{ "include": ["legacy"], "format": { "enabled": false } }] }"#,
+		)
+		.unwrap();
+		let project = ProjectConfiguration {
+			path: PathBuf::from("/project/rome.json"),
+			configuration,
+		};
+		let settings = ProjectSettings::new(Some(&project), Path::new("/project"));
+		let app = create_app();
+
+		let file = RomePath::new("src/file.js").deduce_handler(&app);
+		assert!(file.capabilities(&settings).unwrap().format);
+
+		let file = RomePath::new("legacy/file.js").deduce_handler(&app);
+		assert!(!file.capabilities(&settings).unwrap().format);
+
+		let file = RomePath::new("legacy/file.txt").deduce_handler(&app);
+		assert!(!file.capabilities(&settings).unwrap().format);
+
+		let file = RomePath::new("Makefile").deduce_handler(&app);
+		assert!(file.capabilities(&settings).is_none());
+	}
+
+	#[test]
+	fn sniffs_the_handler_of_the_scripts() {
+		let app = create_app();
+		let script = RomePath::new("bin/cli").deduce_handler(&app).sniff_handler(
+			&app,
+			"#!/usr/bin/env node
+",
 		);
+		assert!(script.get_handler().is_some());
+
+		let file = RomePath::new("notes.txt")
+			.deduce_handler(&app)
+			.sniff_handler(
+				&app,
+				"#!/usr/bin/env node
+",
+			);
+		assert!(!file.get_handler().unwrap().capabilities().format);
+	}
+
+	#[test]
+	fn resolves_the_syntax_from_the_package() {
+		let app = create_app();
+		let fs = MemoryFileSystem::default();
+		fs.insert("/project/package.json", r#"{ "type": "module" }"#);
+
+		let file_kind = |path: &str| {
+			RomePath::new(path)
+				.deduce_handler(&app)
+				.resolve_syntax(&app, &fs)
+				.syntax()
+				.map(|syntax| syntax.file_kind)
+		};
+
+		assert_eq!(file_kind("/project/src/index.js"), Some(FileKind::Module));
+		assert_eq!(file_kind("/project/src/index.cjs"), Some(FileKind::Script));
+		assert_eq!(file_kind("/other/index.js"), Some(FileKind::Script));
+		assert_eq!(file_kind("/project/Makefile"), None);
+
+		let unresolved = RomePath::new("/project/src/index.js").deduce_handler(&app);
 		assert_eq!(
-			handler.unwrap().capabilities().lint,
-			expected.capabilities().lint
-		)
+			unresolved.syntax().map(|syntax| syntax.file_kind),
+			Some(FileKind::Script)
+		);
 	}
 }
@@ -1,12 +1,14 @@
-//! functions to convert diagnostics to LSP (language server protocol) Diagnostics.
+//! functions to convert diagnostics to LSP (language server protocol) Diagnostics,
+//! and their suggestions to code actions.
 
 use crate::file::Files;
 use crate::*;
 
 use lsp_types::{
-	DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag as LspTag, Location,
-	NumberOrString, Url,
+	CodeActionKind, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag as LspTag,
+	InsertTextFormat, Location, NumberOrString, OptionalVersionedTextDocumentIdentifier, Url,
 };
+use serde::Serialize;
 
 pub fn convert_to_lsp_diagnostic(
 	diagnostic: Diagnostic,
@@ -71,6 +73,8 @@ pub fn convert_to_lsp_diagnostic(
 		message,
 		related_information: Some(related_information),
 		tags,
+		code_description: None,
+		data: None,
 	})
 }
 
@@ -83,6 +87,173 @@ pub fn severity_to_lsp_severity(severity: Severity) -> DiagnosticSeverity {
 	}
 }
 
+/// A code action whose edits can be snippets.
+///
+/// It's an LSP `CodeAction` whose edit is a list of `TextDocumentEdit`s, except that its edits
+/// may have an `insertTextFormat`, like the `SnippetTextEdit` extension of rust-analyzer. Editors
+/// opt in to the extension with the `snippetTextEdit` experimental capability.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetCodeAction {
+	pub title: String,
+	pub kind: CodeActionKind,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub diagnostics: Option<Vec<lsp_types::Diagnostic>>,
+	pub edit: SnippetWorkspaceEdit,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub is_preferred: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetWorkspaceEdit {
+	pub document_changes: Vec<SnippetTextDocumentEdit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetTextDocumentEdit {
+	pub text_document: OptionalVersionedTextDocumentIdentifier,
+	pub edits: Vec<SnippetTextEdit>,
+}
+
+/// A `TextEdit` whose new text is a snippet when `insert_text_format` is
+/// [InsertTextFormat::Snippet]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetTextEdit {
+	pub range: LspRange,
+	pub new_text: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub insert_text_format: Option<InsertTextFormat>,
+}
+
+/// Converts the suggestions of `diagnostic` that change the document `document_id` to quick fixes.
+///
+/// The suggestions that can always be applied are the preferred fixes. When the editor supports
+/// `snippets`, the suggestions with placeholders become snippets whose placeholders are the
+/// labels of the suggestion, so that the user can fill them in. `lsp_diagnostic` is the
+/// diagnostic that the fixes resolve, as returned by [convert_to_lsp_diagnostic].
+pub fn convert_to_lsp_code_actions(
+	diagnostic: &Diagnostic,
+	files: &impl Files,
+	document_id: usize,
+	document: OptionalVersionedTextDocumentIdentifier,
+	lsp_diagnostic: Option<&lsp_types::Diagnostic>,
+	snippets: bool,
+) -> Vec<SnippetCodeAction> {
+	diagnostic
+		.suggestions
+		.iter()
+		.filter(|suggestion| suggestion.span.file == document_id)
+		.filter_map(|suggestion| {
+			let edits = suggestion_to_edits(suggestion, files, document_id, snippets)?;
+			Some(SnippetCodeAction {
+				title: suggestion.msg.clone(),
+				kind: CodeActionKind::QUICKFIX,
+				diagnostics: lsp_diagnostic.map(|diagnostic| vec![diagnostic.clone()]),
+				edit: SnippetWorkspaceEdit {
+					document_changes: vec![SnippetTextDocumentEdit {
+						text_document: document.clone(),
+						edits,
+					}],
+				},
+				is_preferred: if suggestion.applicability == Applicability::Always {
+					Some(true)
+				} else {
+					None
+				},
+			})
+		})
+		.collect()
+}
+
+/// The edits of `suggestion`, or `None` if one of them is out of the bounds of the document
+fn suggestion_to_edits(
+	suggestion: &CodeSuggestion,
+	files: &impl Files,
+	document_id: usize,
+	snippets: bool,
+) -> Option<Vec<SnippetTextEdit>> {
+	let replacements: Vec<(Range<usize>, &str)> = match &suggestion.substitution {
+		SuggestionChange::String(text) => vec![(suggestion.span.range.clone(), text.as_str())],
+		SuggestionChange::Indels(indels) => indels
+			.iter()
+			.map(|indel| (indel.delete.as_range(), indel.insert.as_str()))
+			.collect(),
+	};
+
+	let is_snippet = snippets && suggestion.applicability == Applicability::HasPlaceholders;
+	let mut tab_stop = 1;
+
+	replacements
+		.into_iter()
+		.map(|(range, text)| {
+			let (new_text, insert_text_format) = if is_snippet {
+				let snippet = to_snippet(text, range.start, &suggestion.labels, &mut tab_stop);
+				(snippet, Some(InsertTextFormat::Snippet))
+			} else {
+				(text.to_string(), None)
+			};
+
+			Some(SnippetTextEdit {
+				range: byte_span_to_range(files, document_id, range).ok()?,
+				new_text,
+				insert_text_format,
+			})
+		})
+		.collect()
+}
+
+/// Turns `text`, which replaces the code at `offset`, into a snippet. Its placeholders are the
+/// `labels` that point into the new text, or the whole text if there are none.
+fn to_snippet(text: &str, offset: usize, labels: &[Range<usize>], tab_stop: &mut usize) -> String {
+	let mut placeholders: Vec<Range<usize>> = labels
+		.iter()
+		.filter(|label| label.start >= offset && label.end <= offset + text.len())
+		.map(|label| label.start - offset..label.end - offset)
+		.filter(|label| text.is_char_boundary(label.start) && text.is_char_boundary(label.end))
+		.collect();
+	placeholders.sort_by_key(|placeholder| placeholder.start);
+
+	if placeholders.is_empty() && !text.is_empty() {
+		placeholders.push(0..text.len());
+	}
+
+	let mut snippet = String::new();
+	let mut position = 0;
+	for placeholder in placeholders {
+		// Snippets can't have overlapping placeholders
+		if placeholder.start < position {
+			continue;
+		}
+
+		snippet.push_str(&escape_snippet(&text[position..placeholder.start]));
+		snippet.push_str(&format!(
+			"${{{}:{}}}",
+			tab_stop,
+			escape_snippet(&text[placeholder.clone()])
+		));
+		*tab_stop += 1;
+		position = placeholder.end;
+	}
+	snippet.push_str(&escape_snippet(&text[position..]));
+
+	snippet
+}
+
+/// Escapes the characters that have a meaning in snippets
+fn escape_snippet(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for char in text.chars() {
+		if matches!(char, '\\' | '$' | '}') {
+			escaped.push('\\');
+		}
+		escaped.push(char);
+	}
+	escaped
+}
+
 // This code below is taken from codespan-lsp but adapted to use rslint_errors Files
 
 use lsp_types::{Position as LspPosition, Range as LspRange};
@@ -216,8 +387,8 @@ fn location_to_position(
 		Err(LocationError::InvalidCharBoundary { given }.into())
 	} else {
 		let line_utf16 = line_str[..column].encode_utf16();
-		let character = line_utf16.count() as u64;
-		let line = line as u64;
+		let character = line_utf16.count() as u32;
+		let line = line as u32;
 
 		Ok(LspPosition { line, character })
 	}
@@ -267,7 +438,7 @@ where
 	})
 }
 
-pub fn character_to_line_offset(line: &str, character: u64) -> Result<usize, Error> {
+pub fn character_to_line_offset(line: &str, character: u32) -> Result<usize, Error> {
 	let line_len = line.len();
 	let mut character_offset = 0;
 
@@ -280,7 +451,7 @@ pub fn character_to_line_offset(line: &str, character: u64) -> Result<usize, Err
 			return Ok(line_len - chars_off - ch_off);
 		}
 
-		character_offset += ch.len_utf16() as u64;
+		character_offset += ch.len_utf16() as u32;
 	}
 
 	// Handle positions after the last character on the line
@@ -323,3 +494,99 @@ where
 	Ok(position_to_byte_index(files, file_id, &range.start)?
 		..position_to_byte_index(files, file_id, &range.end)?)
 }
+
+#[cfg(test)]
+mod test {
+	use super::{convert_to_lsp_code_actions, SnippetTextEdit};
+	use crate::{file::SimpleFile, Applicability, Diagnostic};
+	use lsp_types::{
+		InsertTextFormat, OptionalVersionedTextDocumentIdentifier, Position, Range, Url,
+	};
+	use rome_rowan::{TextRange, TextSize};
+	use rslint_text_edit::Indel;
+
+	fn document() -> OptionalVersionedTextDocumentIdentifier {
+		OptionalVersionedTextDocumentIdentifier {
+			uri: Url::parse("file:///index.js").unwrap(),
+			version: Some(1),
+		}
+	}
+
+	fn text_range(start: u32, end: u32) -> TextRange {
+		TextRange::new(TextSize::from(start), TextSize::from(end))
+	}
+
+	fn edit(start: u32, end: u32, new_text: &str) -> SnippetTextEdit {
+		SnippetTextEdit {
+			range: Range::new(Position::new(0, start), Position::new(0, end)),
+			new_text: new_text.to_string(),
+			insert_text_format: None,
+		}
+	}
+
+	#[test]
+	fn converts_suggestions_to_quick_fixes() {
+		let file = SimpleFile::new("index.js".into(), "let a = 1".into());
+		let diagnostic = Diagnostic::error(0, "code", "missing semicolon")
+			.primary(text_range(9, 9), "")
+			.suggestion(
+				text_range(9, 9),
+				"insert a semicolon",
+				";",
+				Applicability::Always,
+			)
+			.indel_suggestion(
+				vec![
+					Indel::replace(text_range(0, 3), "const".into()),
+					Indel::insert(TextSize::from(9), ";".into()),
+				],
+				text_range(0, 9),
+				"use a constant",
+				Applicability::MaybeIncorrect,
+			);
+
+		let actions = convert_to_lsp_code_actions(&diagnostic, &file, 0, document(), None, true);
+		assert_eq!(actions.len(), 2);
+
+		assert_eq!(actions[0].title, "insert a semicolon");
+		assert_eq!(actions[0].is_preferred, Some(true));
+		assert_eq!(
+			actions[0].edit.document_changes[0].text_document,
+			document()
+		);
+		assert_eq!(
+			actions[0].edit.document_changes[0].edits,
+			vec![edit(9, 9, ";")]
+		);
+
+		assert_eq!(actions[1].is_preferred, None);
+		assert_eq!(
+			actions[1].edit.document_changes[0].edits,
+			vec![edit(0, 3, "const"), edit(9, 9, ";")]
+		);
+	}
+
+	#[test]
+	fn placeholders_become_snippets() {
+		let file = SimpleFile::new("index.js".into(), "call(a)".into());
+		let diagnostic = Diagnostic::error(0, "code", "missing argument").suggestion_with_labels(
+			text_range(5, 6),
+			"add an argument",
+			"a, ${b}",
+			Applicability::HasPlaceholders,
+			vec![text_range(3, 7)],
+		);
+
+		let actions = convert_to_lsp_code_actions(&diagnostic, &file, 0, document(), None, true);
+		let edits = &actions[0].edit.document_changes[0].edits;
+		assert_eq!(edits[0].new_text, "a, ${1:\\${b\\}}");
+		assert_eq!(edits[0].insert_text_format, Some(InsertTextFormat::Snippet));
+
+		let actions = convert_to_lsp_code_actions(&diagnostic, &file, 0, document(), None, false);
+		assert_eq!(
+			actions[0].edit.document_changes[0].edits,
+			vec![edit(5, 6, "a, ${b}")],
+			"the editor doesn't support snippets"
+		);
+	}
+}
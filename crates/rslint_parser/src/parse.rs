@@ -225,6 +225,19 @@ pub fn parse_module(text: &str, file_id: usize) -> Parse<JsRoot> {
 	Parse::new_module(green, parse_errors)
 }
 
+/// Same as [`parse_text`] but configures the parser with `syntax`. The source type of TypeScript
+/// files is [`JsSourceType::Module`]
+pub fn parse_with_syntax(text: &str, file_id: usize, syntax: Syntax) -> Parse<JsRoot> {
+	let (events, errors, tokens) = parse_common(text, file_id, syntax);
+	let mut tree_sink = LosslessTreeSink::new(text, &tokens);
+	crate::process(&mut tree_sink, events, errors);
+	let (green, parse_errors) = tree_sink.finish();
+	match syntax.file_kind {
+		FileKind::Script => Parse::new_script(green, parse_errors),
+		FileKind::Module | FileKind::TypeScript => Parse::new_module(green, parse_errors),
+	}
+}
+
 /// Losslessly Parse text into an expression [`Parse`](Parse) which can then be turned into an untyped root [`SyntaxNode`](SyntaxNode).
 /// Or turned into a typed [`Expr`](Expr) with [`tree`](Parse::tree).
 pub fn parse_expr(text: &str, file_id: usize) -> Parse<JsAnyExpression> {
@@ -1,21 +1,37 @@
-use crate::{project_root, BASE_RESULT_FILE, NEW_RESULT_FILE};
+use crate::{
+	project_root, BASE_FORMAT_RESULT_FILE, BASE_RESULT_FILE, NEW_FORMAT_RESULT_FILE,
+	NEW_RESULT_FILE,
+};
 use std::path::PathBuf;
 
 mod results;
 
-pub fn run(base_result_path: Option<&str>, new_result_path: Option<&str>, markdown: bool) {
+/// Compares the results of two runs of the `coverage` command, or of the `format-coverage`
+/// command when `format` is `true`
+pub fn run(
+	base_result_path: Option<&str>,
+	new_result_path: Option<&str>,
+	markdown: bool,
+	format: bool,
+) {
+	let (base_result_file, new_result_file) = if format {
+		(BASE_FORMAT_RESULT_FILE, NEW_FORMAT_RESULT_FILE)
+	} else {
+		(BASE_RESULT_FILE, NEW_RESULT_FILE)
+	};
+
 	// resolve the path passed as argument, or retrieve the default one
 	let base_result_dir = if let Some(base_result_path) = base_result_path {
 		PathBuf::from(base_result_path)
 	} else {
-		project_root().join(BASE_RESULT_FILE)
+		project_root().join(base_result_file)
 	};
 
 	// resolve the path passed as argument, or retrieve the default one
 	let new_result_dir = if let Some(new_result_path) = new_result_path {
 		PathBuf::from(new_result_path)
 	} else {
-		project_root().join(NEW_RESULT_FILE)
+		project_root().join(new_result_file)
 	};
 
 	if !base_result_dir.exists() {
@@ -36,5 +52,10 @@ pub fn run(base_result_path: Option<&str>, new_result_path: Option<&str>, markdo
 		base_result_dir.as_path(),
 		new_result_dir.as_path(),
 		markdown,
+		if format {
+			"Prettier compatibility changes:"
+		} else {
+			"Test262 conformance changes:"
+		},
 	);
 }
@@ -3,7 +3,7 @@ use ascii_table::{AsciiTable, Column};
 use colored::Colorize;
 use std::{fs::File, path::Path};
 
-pub fn emit_compare(base: &Path, new: &Path, markdown: bool) {
+pub fn emit_compare(base: &Path, new: &Path, markdown: bool, title: &str) {
 	let base_results: TestResults =
 		serde_json::from_reader(File::open(base).expect("Can't read the file of the base results"))
 			.expect("Can't parse the JSON file of the base results");
@@ -166,7 +166,7 @@ pub fn emit_compare(base: &Path, new: &Path, markdown: bool) {
 			counter += 1;
 		};
 
-		println!("Test262 conformance changes:");
+		println!("{}", title);
 
 		create_column("Tests result".into());
 		create_column("main branch".green());
@@ -0,0 +1,82 @@
+//! A minimal line diff, based on the longest common subsequence of the lines of two texts.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+	/// The line is in both texts
+	Equal(String),
+	/// The line is only in the expected text
+	Removed(String),
+	/// The line is only in the actual text
+	Added(String),
+}
+
+#[derive(Debug)]
+pub struct Diff {
+	pub lines: Vec<DiffLine>,
+}
+
+impl Diff {
+	/// The ratio of lines that both texts have in common, between `0.0` and `1.0`
+	pub fn similarity(&self) -> f64 {
+		let equal = self
+			.lines
+			.iter()
+			.filter(|line| matches!(line, DiffLine::Equal(_)))
+			.count();
+
+		// Each equal line counts once for each text
+		let total = self.lines.len() + equal;
+		if total == 0 {
+			1.0
+		} else {
+			(equal * 2) as f64 / total as f64
+		}
+	}
+}
+
+/// Computes the lines to remove from `expected` and add to it to get `actual`
+pub fn diff_lines(expected: &str, actual: &str) -> Diff {
+	let expected = expected.lines().collect::<Vec<_>>();
+	let actual = actual.lines().collect::<Vec<_>>();
+
+	// lengths[i][j] is the length of the longest common subsequence of expected[i..] and actual[j..]
+	let mut lengths = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+	for i in (0..expected.len()).rev() {
+		for j in (0..actual.len()).rev() {
+			lengths[i][j] = if expected[i] == actual[j] {
+				lengths[i + 1][j + 1] + 1
+			} else {
+				lengths[i + 1][j].max(lengths[i][j + 1])
+			};
+		}
+	}
+
+	let mut lines = Vec::with_capacity(expected.len().max(actual.len()));
+	let (mut i, mut j) = (0, 0);
+	while i < expected.len() && j < actual.len() {
+		if expected[i] == actual[j] {
+			lines.push(DiffLine::Equal(expected[i].to_string()));
+			i += 1;
+			j += 1;
+		} else if lengths[i + 1][j] >= lengths[i][j + 1] {
+			lines.push(DiffLine::Removed(expected[i].to_string()));
+			i += 1;
+		} else {
+			lines.push(DiffLine::Added(actual[j].to_string()));
+			j += 1;
+		}
+	}
+
+	lines.extend(
+		expected[i..]
+			.iter()
+			.map(|line| DiffLine::Removed(line.to_string())),
+	);
+	lines.extend(
+		actual[j..]
+			.iter()
+			.map(|line| DiffLine::Added(line.to_string())),
+	);
+
+	Diff { lines }
+}
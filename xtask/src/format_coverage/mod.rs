@@ -0,0 +1,293 @@
+//! Measures how close the output of Rome's formatter is to Prettier's.
+//!
+//! The corpus lives in [BASE_PATH]. Each input file, for example `arrays/holes.js`, has a snapshot
+//! of Prettier's output next to it, `arrays/holes.js.prettier-snap`. The inputs are formatted with
+//! Prettier's default options (two spaces of indentation, lines of 80 characters) and compared
+//! line by line with their snapshot.
+
+mod diff;
+
+use crate::coverage::files::{Outcome, Summary};
+use crate::{project_root, BASE_FORMAT_RESULT_FILE};
+use ascii_table::{AsciiTable, Column};
+use colored::Colorize;
+use diff::{diff_lines, DiffLine};
+use rome_core::{
+	configuration::{FormatConfiguration, IndentStyle},
+	fs::OsFileSystem,
+};
+use rome_formatter::{create_app, format, FormatError};
+use rome_path::RomePath;
+use serde::Serialize;
+use std::fs::{read_to_string, File};
+use std::io::Write;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+const BASE_PATH: &str = "xtask/src/format_coverage/prettier";
+
+/// The extension of the files that contain the output of Prettier
+const SNAPSHOT_EXTENSION: &str = "prettier-snap";
+
+pub fn run(query: Option<&str>, json: bool) {
+	let files = get_test_files(query);
+	let num_ran = files.len();
+
+	std::panic::set_hook(Box::new(|_| {}));
+	let start_tests = std::time::Instant::now();
+
+	let results = files
+		.into_iter()
+		.map(|(input, snapshot)| run_test_file(input, snapshot))
+		.collect::<Vec<_>>();
+
+	let _ = std::panic::take_hook();
+
+	for result in &results {
+		report_test(result);
+	}
+
+	println!(
+		"\n{} {} tests in {:.2}s\n",
+		"Ran".bold().bright_green(),
+		num_ran,
+		start_tests.elapsed().as_secs_f32()
+	);
+
+	let report = FormatResults::new(results);
+	let summary = &report.summary;
+	let compatibility = format!("{:.2}%", summary.coverage);
+
+	let mut table = AsciiTable::default();
+
+	let mut counter = 0usize;
+	let mut create_column = |name: colored::ColoredString| {
+		let column = Column {
+			header: name.to_string(),
+			align: ascii_table::Align::Center,
+			..Column::default()
+		};
+		table.columns.insert(counter, column);
+		counter += 1;
+	};
+
+	create_column("Tests ran".into());
+	create_column("Matched".green());
+	create_column("Differed".red());
+	create_column("Panics".red());
+	create_column("Compatibility".cyan());
+	let numbers: Vec<&dyn std::fmt::Display> = vec![
+		&num_ran,
+		&summary.passed,
+		&summary.failed,
+		&summary.panics,
+		&compatibility,
+	];
+
+	table.print(vec![numbers]);
+
+	if json {
+		report.dump_to_json();
+	}
+}
+
+/// Returns the pairs of input and snapshot files of the corpus whose path contains `query`
+fn get_test_files(query: Option<&str>) -> Vec<(PathBuf, PathBuf)> {
+	let mut files = WalkDir::new(BASE_PATH)
+		.into_iter()
+		.filter_map(Result::ok)
+		.filter(|entry| entry.file_type().is_file())
+		.map(|entry| entry.into_path())
+		.filter(|path| {
+			path.extension()
+				.map_or(true, |ext| ext != SNAPSHOT_EXTENSION)
+		})
+		.filter(|path| {
+			query.map_or(true, |query| {
+				path.to_str().map_or(true, |path| path.contains(query))
+			})
+		})
+		.filter_map(|input| {
+			let mut snapshot = input.clone().into_os_string();
+			snapshot.push(".");
+			snapshot.push(SNAPSHOT_EXTENSION);
+			let snapshot = PathBuf::from(snapshot);
+
+			if snapshot.is_file() {
+				Some((input, snapshot))
+			} else {
+				println!(
+					"{} '{}' has no snapshot, skipping it",
+					"Warning".bold().yellow(),
+					input.display()
+				);
+				None
+			}
+		})
+		.collect::<Vec<_>>();
+
+	files.sort();
+	files
+}
+
+fn run_test_file(input: PathBuf, snapshot: PathBuf) -> FormatTestResult {
+	let expected = read_to_string(&snapshot).expect("Can't read the snapshot");
+	let path = input
+		.strip_prefix(BASE_PATH)
+		.unwrap_or(&input)
+		.to_path_buf();
+
+	let formatted = std::panic::catch_unwind(|| {
+		let app = create_app();
+		let rome_path = RomePath::new(input.to_str().unwrap()).deduce_handler(&app);
+		let configuration = FormatConfiguration {
+			indent_style: IndentStyle::Space,
+			indent_size: 2,
+			..FormatConfiguration::default()
+		};
+		format(&rome_path, &OsFileSystem, &configuration)
+	});
+
+	let (outcome, actual) = match formatted {
+		Ok(Ok(formatted)) => {
+			let actual = formatted.code().clone();
+			if actual == expected {
+				(Outcome::Passed, actual)
+			} else {
+				(Outcome::Failed, actual)
+			}
+		}
+		Ok(Err(error)) => (Outcome::Failed, format_error_message(error)),
+		Err(_) => (Outcome::Panicked, String::new()),
+	};
+
+	let diff = diff_lines(&expected, &actual);
+	let compatibility = match outcome {
+		Outcome::Passed => 100.0,
+		Outcome::Panicked => 0.0,
+		Outcome::Failed => diff.similarity() * 100.0,
+	};
+
+	FormatTestResult {
+		outcome,
+		path,
+		compatibility,
+		diff: diff.lines,
+	}
+}
+
+/// Formatting errors are reported as the output of the formatter, they are then counted as a
+/// mismatch of the whole file
+fn format_error_message(error: FormatError) -> String {
+	match error {
+		FormatError::SyntaxError(diagnostics) => diagnostics
+			.iter()
+			.map(|diagnostic| format!("SyntaxError: {}\n", diagnostic.title))
+			.collect(),
+		error => format!("{:?}\n", error),
+	}
+}
+
+fn report_test(result: &FormatTestResult) {
+	let path = result.path.display();
+
+	match result.outcome {
+		Outcome::Passed => {}
+		Outcome::Panicked => {
+			println!(
+				"{} '{}' {}",
+				"Test".bold().red(),
+				path,
+				"panicked while formatting".bold()
+			);
+		}
+		Outcome::Failed => {
+			println!(
+				"{} '{}' {} {}",
+				"Test".bold().red(),
+				path,
+				"differs from Prettier, compatibility:".bold(),
+				format!("{:.2}%", result.compatibility).bold()
+			);
+			println!("{}", "   - Prettier, + Rome".dimmed());
+
+			for line in &result.diff {
+				match line {
+					DiffLine::Equal(line) => println!("   {}", line),
+					DiffLine::Removed(line) => println!("{}", format!(" - {}", line).red()),
+					DiffLine::Added(line) => println!("{}", format!(" + {}", line).green()),
+				}
+			}
+			println!();
+		}
+	}
+}
+
+#[derive(Debug, Serialize)]
+struct FormatTestResult {
+	#[serde(rename = "o")]
+	outcome: Outcome,
+	#[serde(rename = "h")]
+	path: PathBuf,
+	/// The percentage of lines that match the output of Prettier
+	#[serde(rename = "m")]
+	compatibility: f64,
+	#[serde(skip)]
+	diff: Vec<DiffLine>,
+}
+
+/// The results of a run, serialized with the same schema as the results of the `coverage` command
+#[derive(Debug, Serialize)]
+struct FormatResults {
+	#[serde(rename = "s")]
+	summary: Summary,
+	#[serde(rename = "p")]
+	details: Vec<FormatTestResult>,
+}
+
+impl FormatResults {
+	fn new(details: Vec<FormatTestResult>) -> Self {
+		let count = |outcome: Outcome| {
+			details
+				.iter()
+				.filter(|result| result.outcome == outcome)
+				.count() as u32
+		};
+
+		// The compatibility of the corpus is the average compatibility of its files
+		let coverage = if details.is_empty() {
+			0.0
+		} else {
+			details
+				.iter()
+				.map(|result| result.compatibility)
+				.sum::<f64>()
+				/ details.len() as f64
+		};
+
+		Self {
+			summary: Summary {
+				tests_ran: details.len() as u32,
+				passed: count(Outcome::Passed),
+				failed: count(Outcome::Failed),
+				panics: count(Outcome::Panicked),
+				coverage,
+			},
+			details,
+		}
+	}
+
+	/// Saves the results into a JSON file at the root of the project. The file uses the format of the
+	/// `coverage` command, so that the results of two branches can be diffed with `compare --format`
+	fn dump_to_json(&self) {
+		let json = serde_json::to_string(&self).unwrap();
+		let path = project_root().join(BASE_FORMAT_RESULT_FILE);
+
+		let mut file = File::create(&path).expect("Can't open the JSON file");
+
+		file.write_all(json.as_bytes())
+			.expect("Can't write in the JSON file");
+		println!();
+		println!("The test result report has been saved in: {:?}", &path);
+	}
+}
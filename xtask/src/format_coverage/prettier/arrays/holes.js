@@ -0,0 +1,4 @@
+let a = [,];
+let b = [1,,];
+let c = [,,1];
+let d = [1,,2];
@@ -0,0 +1,2 @@
+let a = [[],[0],[0,1]];
+let b = [ [ 1 , 2 ] , [ 3 , 4 ] ];
@@ -0,0 +1 @@
+const numbers = [1000000000, 2000000000, 3000000000, 4000000000, 5000000000, 6000000000];
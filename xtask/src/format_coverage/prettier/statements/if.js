@@ -0,0 +1,2 @@
+if (a) b(); else c();
+if (a) { b() } else if (c) { d() }
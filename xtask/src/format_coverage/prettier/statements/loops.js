@@ -0,0 +1,3 @@
+while(true){ foo() }
+do { bar() } while (false)
+for (;;) {}
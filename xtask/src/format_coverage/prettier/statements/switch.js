@@ -0,0 +1 @@
+switch(a){case 1: b(); break; default: c()}
@@ -0,0 +1 @@
+try{a()}catch(e){b()}finally{c()}
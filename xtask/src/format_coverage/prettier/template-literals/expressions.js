@@ -0,0 +1 @@
+const s = `a${ b }c`;
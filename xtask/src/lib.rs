@@ -4,6 +4,7 @@ pub mod codegen;
 pub mod compare;
 pub mod coverage;
 pub mod docgen;
+pub mod format_coverage;
 pub mod glue;
 
 use std::{
@@ -24,6 +25,12 @@ pub const BASE_RESULT_FILE: &str = "base_results.json";
 // this is the filename of the results coming from the current PR
 pub const NEW_RESULT_FILE: &str = "new_results.json";
 
+// this is the filename of the formatter results coming from `main` branch
+pub const BASE_FORMAT_RESULT_FILE: &str = "base_format_results.json";
+
+// this is the filename of the formatter results coming from the current PR
+pub const NEW_FORMAT_RESULT_FILE: &str = "new_format_results.json";
+
 pub fn project_root() -> PathBuf {
 	Path::new(
 		&env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| env!("CARGO_MANIFEST_DIR").to_owned()),
@@ -1,7 +1,7 @@
 use pico_args::Arguments;
 use xtask::{
 	codegen::{self, Mode},
-	compare, coverage,
+	compare, coverage, format_coverage,
 	glue::pushd,
 	project_root, run_rustfmt, Result,
 };
@@ -28,11 +28,12 @@ fn main() -> Result<()> {
 		}
 		"compare" => {
 			let markdown = args.contains("--markdown");
+			let format = args.contains("--format");
 			let free = args.free()?;
 			let base_result_path = free.get(0).map(String::as_str);
 			let new_result_path = free.get(1).map(String::as_str);
 
-			compare::run(base_result_path, new_result_path, markdown);
+			compare::run(base_result_path, new_result_path, markdown, format);
 			Ok(())
 		}
 		// "docgen" => {
@@ -50,6 +51,15 @@ fn main() -> Result<()> {
 			coverage::run(query, yastl::Pool::with_config(num_cpus::get(), pool), json);
 			Ok(())
 		}
+		"format-coverage" => {
+			let json = args.contains("--json");
+
+			let free = args.free()?;
+			let query = free.get(0).map(String::as_str);
+
+			format_coverage::run(query, json);
+			Ok(())
+		}
 		_ => {
 			eprintln!(
 				"\
@@ -63,10 +73,12 @@ SUBCOMMANDS:
     syntax
     docgen
     coverage [--json]
-		compare [--markdown]
+    format-coverage [--json]
+		compare [--markdown] [--format]
 OPTIONS
     --markdown   Emits supported output into markdown format. Supported by compare subcommand
-    --json       Emits supported output into json format. Supported by coverage subcommand
+    --json       Emits supported output into json format. Supported by coverage and format-coverage subcommands
+    --format     Compares the results of format-coverage instead of coverage. Supported by compare subcommand
 			"
 			);
 			Ok(())